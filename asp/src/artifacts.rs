@@ -0,0 +1,352 @@
+// Circuit artifact registry
+// Resolves the witness WASM, proving key and verification key of every circuit once at
+// startup, records their SHA-256 hashes and checks each verification key against the
+// constants compiled into its Garaga verifier, so the ASP never proves with keys the
+// deployed verifier would reject. The verification keys are kept to check every proof.
+
+use crate::verifier::{self, VerificationKey};
+use num_bigint::BigUint;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Circuits the ASP proves
+pub const CIRCUITS: [&str; 4] = ["swap", "withdraw", "lp", "membership"];
+
+/// One artifact file: where it is configured and the hash of what was found there
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    pub path: PathBuf,
+    /// SHA-256 of the file, None when it is missing
+    pub sha256: Option<String>,
+}
+
+/// Artifacts of one circuit
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitArtifacts {
+    /// build/<name>/<name>_js/<name>.wasm
+    pub wasm: Artifact,
+    /// build/zkeys/<name>.zkey
+    pub zkey: Artifact,
+    /// build/vkeys/<name>_vk.json
+    pub vk: Artifact,
+    /// Garaga constants the on-chain verifier was compiled from
+    pub verifier_constants: PathBuf,
+    /// Public inputs of the verification key
+    pub n_public: Option<usize>,
+    /// The verification key matched the Garaga verifier (false when either is missing)
+    pub verifier_match: bool,
+    /// Parsed verification key every proof of the circuit is checked against
+    #[serde(skip)]
+    pub verification_key: Option<VerificationKey>,
+}
+
+impl CircuitArtifacts {
+    /// Witness WASM, proving key and verification key are all present
+    pub fn is_ready(&self) -> bool {
+        self.wasm.sha256.is_some() && self.zkey.sha256.is_some() && self.verification_key.is_some()
+    }
+}
+
+/// Artifacts of every circuit, loaded once at startup
+#[derive(Debug, Clone)]
+pub struct ArtifactRegistry {
+    circuits_dir: PathBuf,
+    /// garaga CLI that turns a proof into its verifier's full_proof_with_hints
+    garaga_bin: PathBuf,
+    circuits: BTreeMap<String, CircuitArtifacts>,
+}
+
+impl ArtifactRegistry {
+    /// Resolve and hash the artifacts of every circuit under `circuits_dir`
+    /// Missing files are recorded (proofs for that circuit fail with a clear error), but a
+    /// verification key that differs from its Garaga verifier is an error
+    pub fn load(circuits_dir: &Path, garaga_bin: &Path) -> Result<Self, String> {
+        let circuits_dir = circuits_dir
+            .canonicalize()
+            .or_else(|_| std::path::absolute(circuits_dir))
+            .map_err(|e| format!("Invalid circuits directory {:?}: {}", circuits_dir, e))?;
+        let build = circuits_dir.join("build");
+
+        let mut circuits = BTreeMap::new();
+        for name in CIRCUITS {
+            let wasm = hash_artifact(build.join(name).join(format!("{}_js", name)).join(format!("{}.wasm", name)))?;
+            let zkey = hash_artifact(build.join("zkeys").join(format!("{}.zkey", name)))?;
+            let vk = hash_artifact(build.join("vkeys").join(format!("{}_vk.json", name)))?;
+            let verifier_constants = build
+                .join("garaga")
+                .join(format!("{}_verifier", name))
+                .join("src")
+                .join("groth16_verifier_constants.cairo");
+
+            let mut n_public = None;
+            let mut verifier_match = false;
+            let mut verification_key = None;
+            if vk.sha256.is_some() {
+                let json = std::fs::read_to_string(&vk.path)
+                    .map_err(|e| format!("Failed to read {:?}: {}", vk.path, e))?;
+                let key = VerifyingKeyPoints::from_snarkjs(&json)
+                    .map_err(|e| format!("Invalid verification key for '{}': {}", name, e))?;
+                n_public = Some(key.ic.len() - 1);
+                if verifier::public_input_names(name).len() != key.ic.len() - 1 {
+                    return Err(format!(
+                        "Verification key for '{}' has {} public inputs, expected {}",
+                        name,
+                        key.ic.len() - 1,
+                        verifier::public_input_names(name).len()
+                    ));
+                }
+                verification_key = Some(
+                    VerificationKey::from_snarkjs(name, &json)
+                        .map_err(|e| format!("Invalid verification key for '{}': {}", name, e))?,
+                );
+
+                if verifier_constants.exists() {
+                    let garaga = std::fs::read_to_string(&verifier_constants)
+                        .map_err(|e| format!("Failed to read {:?}: {}", verifier_constants, e))
+                        .and_then(|cairo| VerifyingKeyPoints::from_garaga(&cairo))
+                        .map_err(|e| format!("Invalid Garaga verifier constants for '{}': {}", name, e))?;
+                    key.check_matches(&garaga).map_err(|e| {
+                        format!(
+                            "Verification key {:?} does not match the Garaga verifier {:?}: {}",
+                            vk.path, verifier_constants, e
+                        )
+                    })?;
+                    verifier_match = true;
+                } else {
+                    println!("[ASP] ⚠️  No Garaga verifier constants for '{}' circuit, verification key not checked", name);
+                }
+            }
+
+            circuits.insert(
+                name.to_string(),
+                CircuitArtifacts { wasm, zkey, vk, verifier_constants, n_public, verifier_match, verification_key },
+            );
+        }
+
+        Ok(Self { circuits_dir, garaga_bin: garaga_bin.to_path_buf(), circuits })
+    }
+
+    /// Directory the node witness/proof scripts run in (needs circuits/node_modules)
+    pub fn circuits_dir(&self) -> &Path {
+        &self.circuits_dir
+    }
+
+    /// garaga CLI the Garaga calldata step runs
+    pub fn garaga_bin(&self) -> &Path {
+        &self.garaga_bin
+    }
+
+    /// Artifacts of a circuit that can be proven
+    pub fn circuit(&self, name: &str) -> Result<&CircuitArtifacts, String> {
+        let artifacts = self
+            .circuits
+            .get(name)
+            .ok_or_else(|| format!("Unknown circuit '{}'", name))?;
+        if artifacts.wasm.sha256.is_none() {
+            return Err(format!("WASM file not found: {:?}", artifacts.wasm.path));
+        }
+        if artifacts.zkey.sha256.is_none() {
+            return Err(format!("ZKey file not found: {:?}", artifacts.zkey.path));
+        }
+        if artifacts.verification_key.is_none() {
+            return Err(format!("Verification key not found: {:?}", artifacts.vk.path));
+        }
+        Ok(artifacts)
+    }
+
+    /// Every circuit in `CIRCUITS` order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CircuitArtifacts)> {
+        CIRCUITS
+            .iter()
+            .filter_map(|name| self.circuits.get(*name).map(|artifacts| (*name, artifacts)))
+    }
+
+    /// Paths, hashes and readiness of every circuit, as /health reports them
+    pub fn summary(&self) -> serde_json::Value {
+        let circuits: serde_json::Map<String, serde_json::Value> = self
+            .iter()
+            .map(|(name, artifacts)| {
+                let mut value = serde_json::to_value(artifacts).unwrap();
+                value["ready"] = serde_json::json!(artifacts.is_ready());
+                (name.to_string(), value)
+            })
+            .collect();
+        serde_json::json!({
+            "circuits_dir": self.circuits_dir,
+            "garaga_bin": self.garaga_bin,
+            "circuits": circuits,
+        })
+    }
+}
+
+/// Hash `path` if it exists
+fn hash_artifact(path: PathBuf) -> Result<Artifact, String> {
+    if !path.is_file() {
+        return Ok(Artifact { path, sha256: None });
+    }
+    let mut file = std::fs::File::open(&path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to hash {:?}: {}", path, e))?;
+    let sha256 = hex::encode(hasher.finalize());
+    Ok(Artifact { path, sha256: Some(sha256) })
+}
+
+/// The verification key points a Garaga verifier embeds: gamma and delta in G2 as
+/// [x0, x1, y0, y1], and the IC points in G1 as [x, y]
+/// alpha and beta are only compiled in as their Miller loop result, so they are not compared
+#[derive(Debug, PartialEq)]
+struct VerifyingKeyPoints {
+    gamma_g2: [BigUint; 4],
+    delta_g2: [BigUint; 4],
+    ic: Vec<[BigUint; 2]>,
+}
+
+impl VerifyingKeyPoints {
+    /// Read a verification key exported by `snarkjs zkey export verificationkey`
+    fn from_snarkjs(json: &str) -> Result<Self, String> {
+        let vk: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let coord = |value: &serde_json::Value| -> Result<BigUint, String> {
+            value
+                .as_str()
+                .and_then(|s| BigUint::from_str(s).ok())
+                .ok_or_else(|| format!("Invalid coordinate {}", value))
+        };
+        let g2 = |key: &str| -> Result<[BigUint; 4], String> {
+            let point = &vk[key];
+            Ok([coord(&point[0][0])?, coord(&point[0][1])?, coord(&point[1][0])?, coord(&point[1][1])?])
+        };
+
+        let ic = vk["IC"]
+            .as_array()
+            .ok_or("Missing IC")?
+            .iter()
+            .map(|point| Ok([coord(&point[0])?, coord(&point[1])?]))
+            .collect::<Result<Vec<_>, String>>()?;
+        if ic.is_empty() {
+            return Err("IC is empty".to_string());
+        }
+        if let Some(n_public) = vk["nPublic"].as_u64() {
+            if n_public as usize + 1 != ic.len() {
+                return Err(format!("nPublic is {} but IC has {} points", n_public, ic.len()));
+            }
+        }
+
+        Ok(Self { gamma_g2: g2("vk_gamma_2")?, delta_g2: g2("vk_delta_2")?, ic })
+    }
+
+    /// Read the `vk` and `ic` constants of a Garaga groth16_verifier_constants.cairo
+    fn from_garaga(cairo: &str) -> Result<Self, String> {
+        let g2 = |name: &str| -> Result<[BigUint; 4], String> {
+            let start = cairo.find(&format!("{}: G2Point", name)).ok_or_else(|| format!("Missing {}", name))?;
+            let coords = read_u384s(&cairo[start..], 4)?;
+            Ok([coords[0].clone(), coords[1].clone(), coords[2].clone(), coords[3].clone()])
+        };
+
+        let start = cairo.find("pub const ic: [G1Point; ").ok_or("Missing ic")?;
+        let declaration = &cairo[start + "pub const ic: [G1Point; ".len()..];
+        let len: usize = declaration[..declaration.find(']').ok_or("Invalid ic declaration")?]
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid ic length: {}", e))?;
+        let ic = read_u384s(declaration, 2 * len)?
+            .chunks(2)
+            .map(|point| [point[0].clone(), point[1].clone()])
+            .collect();
+
+        Ok(Self { gamma_g2: g2("gamma_g2")?, delta_g2: g2("delta_g2")?, ic })
+    }
+
+    /// Describe the first difference from `other`
+    fn check_matches(&self, other: &Self) -> Result<(), String> {
+        if self.ic.len() != other.ic.len() {
+            return Err(format!("{} public inputs, verifier has {}", self.ic.len() - 1, other.ic.len() - 1));
+        }
+        if self.gamma_g2 != other.gamma_g2 {
+            return Err("gamma differs".to_string());
+        }
+        if self.delta_g2 != other.delta_g2 {
+            return Err("delta differs".to_string());
+        }
+        if let Some(i) = (0..self.ic.len()).find(|i| self.ic[*i] != other.ic[*i]) {
+            return Err(format!("IC[{}] differs", i));
+        }
+        Ok(())
+    }
+}
+
+/// The first `count` `u384 { limb0, limb1, limb2, limb3 }` values in `cairo` (96-bit limbs, low first)
+fn read_u384s(cairo: &str, count: usize) -> Result<Vec<BigUint>, String> {
+    let mut values = Vec::with_capacity(count);
+    let mut rest = cairo;
+    while values.len() < count {
+        let start = rest.find("u384 {").ok_or_else(|| format!("Expected {} u384 values, found {}", count, values.len()))?;
+        let end = start + rest[start..].find('}').ok_or("Unterminated u384")?;
+        let mut value = BigUint::default();
+        for (i, limb) in rest[start..end].split("limb").skip(1).enumerate() {
+            let hex = limb
+                .split_once("0x")
+                .map(|(_, hex)| hex.trim_end_matches(|c: char| c == ',' || c.is_whitespace()))
+                .ok_or("Invalid u384 limb")?;
+            let limb = BigUint::parse_bytes(hex.as_bytes(), 16).ok_or_else(|| format!("Invalid u384 limb 0x{}", hex))?;
+            value += limb << (96 * i);
+        }
+        values.push(value);
+        rest = &rest[end..];
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circuits_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().join("circuits")
+    }
+
+    #[test]
+    fn test_committed_verification_keys_match_garaga() {
+        let registry = ArtifactRegistry::load(&circuits_dir(), Path::new("garaga")).unwrap();
+        let expected_public = [("swap", 9), ("withdraw", 4), ("lp", 7), ("membership", 2)];
+        for (name, n_public) in expected_public {
+            let artifacts = registry.circuits.get(name).unwrap();
+            assert!(artifacts.verifier_match, "{}", name);
+            assert_eq!(artifacts.n_public, Some(n_public));
+            assert_eq!(artifacts.vk.sha256.as_ref().map(String::len), Some(64));
+        }
+        let summary = registry.summary();
+        assert!(summary["circuits"]["swap"]["ready"].is_boolean());
+    }
+
+    #[test]
+    fn test_mismatched_verification_key_is_rejected() {
+        let build = circuits_dir().join("build");
+        let vk = std::fs::read_to_string(build.join("vkeys").join("swap_vk.json")).unwrap();
+        let cairo = std::fs::read_to_string(
+            build.join("garaga").join("swap_verifier").join("src").join("groth16_verifier_constants.cairo"),
+        )
+        .unwrap();
+        let garaga = VerifyingKeyPoints::from_garaga(&cairo).unwrap();
+
+        let mut key = VerifyingKeyPoints::from_snarkjs(&vk).unwrap();
+        key.ic[3][1] += 1u32;
+        assert_eq!(key.check_matches(&garaga).unwrap_err(), "IC[3] differs");
+
+        // A key for another circuit has a different number of public inputs
+        let lp_vk = std::fs::read_to_string(build.join("vkeys").join("lp_vk.json")).unwrap();
+        let lp_key = VerifyingKeyPoints::from_snarkjs(&lp_vk).unwrap();
+        assert!(lp_key.check_matches(&garaga).unwrap_err().contains("7 public inputs"));
+    }
+
+    #[test]
+    fn test_read_u384_limbs() {
+        let cairo = "x: u384 { limb0: 0x1, limb1: 0x2, limb2: 0x0, limb3: 0x0, }, y: u384 { limb0: 0xff, limb1: 0x0, limb2: 0x0, limb3: 0x1, }";
+        let values = read_u384s(cairo, 2).unwrap();
+        assert_eq!(values[0], BigUint::from(1u32) + (BigUint::from(2u32) << 96));
+        assert_eq!(values[1], BigUint::from(0xffu32) + (BigUint::from(1u32) << 288));
+        assert!(read_u384s(cairo, 3).is_err());
+    }
+}
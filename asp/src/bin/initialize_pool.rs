@@ -6,6 +6,7 @@ use starknet::{
     signers::{LocalWallet, SigningKey},
 };
 use std::str::FromStr;
+use zylith_asp::config;
 use std::sync::Arc;
 use url::Url;
 
@@ -15,11 +16,12 @@ const Q128: &str = "340282366920938463463374607431768211456";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Configuration
+    // Configuration (network profile, STARKNET_RPC / ZYLITH_CONTRACT still take precedence)
+    let config = config::Config::load()?;
     let rpc_url = std::env::var("STARKNET_RPC")
-        .unwrap_or_else(|_| "https://starknet-sepolia-rpc.publicnode.com".to_string());
+        .unwrap_or_else(|_| config.rpc_url().to_string());
     let zylith_address = std::env::var("ZYLITH_CONTRACT")
-        .unwrap_or_else(|_| "0x05c1fecd5025d2e3d71482ac26ecf9138b08c9483f43761543cf71960373ce0b".to_string());
+        .unwrap_or_else(|_| config.profile.zylith_address.clone());
 
     // Get private key from environment or use default for testing
     let private_key_hex =
@@ -32,12 +34,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::env::var("ACCOUNT_ADDRESS").expect("ACCOUNT_ADDRESS environment variable must be set");
     let account_address = FieldElement::from_str(&account_address)?;
 
-    // Token addresses (ETH/USDC on Sepolia)
-    let token0 = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"; // ETH
-    let token1 = "0x0512feAc6339Ff7889822cb5aA2a86C848e9D392bB0E3E237C008674feeD8343"; // USDC
+    // Token pair: the first two tokens of the network profile
+    let (token0, token1) = config
+        .pool_tokens()
+        .ok_or_else(|| format!("Network '{}' needs at least two tokens", config.network))?;
 
     // Pool parameters
-    let fee = 3000u128; // 0.3%
+    let fee = config.profile.pool_fee as u128; // millionths, 3000 = 0.3%
     let tick_spacing = 60i32;
 
     // Calculate sqrt_price_x128 for 1:1 price (Q128)
@@ -46,10 +49,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("🚀 Initializing Zylith Pool");
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("Network: {}", config.network);
     println!("Contract: {}", zylith_address);
-    println!("Token0 (ETH): {}", token0);
-    println!("Token1 (USDC): {}", token1);
-    println!("Fee: {} (0.3%)", fee);
+    println!("Token0 ({}): {}", token0.symbol, token0.address);
+    println!("Token1 ({}): {}", token1.symbol, token1.address);
+    println!("Fee: {} ({}%)", fee, fee as f64 / 10_000.0);
     println!("Tick Spacing: {}", tick_spacing);
     println!("Sqrt Price X128: {} (1:1 price)", Q128);
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -92,8 +96,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let initialize_selector = get_selector_from_name("initialize")?;
 
     let calldata = build_initialize_calldata(
-        token0,
-        token1,
+        &token0.address,
+        &token1.address,
         fee,
         tick_spacing,
         sqrt_price_low,
@@ -125,10 +129,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("✅ Transaction sent successfully!");
     println!("   Hash: 0x{:x}", transaction_hash);
     println!("\n⏳ Waiting for transaction to be confirmed...");
-    println!(
-        "   Check status at: https://sepolia.starkscan.co/tx/0x{:x}",
-        transaction_hash
-    );
+    if let Some(explorer_url) = &config.profile.explorer_url {
+        println!(
+            "   Check status at: {}/tx/0x{:x}",
+            explorer_url.trim_end_matches('/'),
+            transaction_hash
+        );
+    }
 
     // Wait for transaction (poll every 5 seconds)
     loop {
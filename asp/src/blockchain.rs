@@ -1,5 +1,8 @@
+use crate::events::{decode_zylith_event, PrivacyEvent, ZylithEvent};
+use crate::fees::{FeeGrowth, PositionFeeState};
+use primitive_types::U256;
 use starknet::core::types::{BlockId, BlockTag, FieldElement, FunctionCall};
-use starknet::core::utils::starknet_keccak;
+use starknet::core::utils::{get_storage_var_address, starknet_keccak};
 use starknet::providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider};
 use starknet_crypto::{pedersen_hash, FieldElement as CryptoFieldElement};
 use url::Url;
@@ -7,6 +10,8 @@ use url::Url;
 pub struct BlockchainClient {
     provider: JsonRpcClient<HttpTransport>,
     zylith_address: FieldElement,
+    /// Block the Zylith contract was deployed in (start of event scans)
+    deployment_block: u64,
 }
 
 impl BlockchainClient {
@@ -22,9 +27,15 @@ impl BlockchainClient {
         Ok(Self {
             provider,
             zylith_address: zylith_addr,
+            deployment_block: 0,
         })
     }
 
+    pub fn with_deployment_block(mut self, block: u64) -> Self {
+        self.deployment_block = block;
+        self
+    }
+
     /// Get Merkle root from contract
     pub async fn get_merkle_root(&self) -> Result<String, String> {
         let call = FunctionCall {
@@ -95,6 +106,25 @@ impl BlockchainClient {
         &self,
         token_address: &str,
         owner: &str,
+    ) -> Result<(u128, u128), String> {
+        self.token_balance(token_address, owner, BlockId::Tag(BlockTag::Latest)).await
+    }
+
+    /// Token balance at the end of `block`, for balance history
+    pub async fn get_token_balance_at(
+        &self,
+        token_address: &str,
+        owner: &str,
+        block: u64,
+    ) -> Result<(u128, u128), String> {
+        self.token_balance(token_address, owner, BlockId::Number(block)).await
+    }
+
+    async fn token_balance(
+        &self,
+        token_address: &str,
+        owner: &str,
+        block: BlockId,
     ) -> Result<(u128, u128), String> {
         let token_addr = parse_felt(token_address)?;
         let owner_addr = parse_felt(owner)?;
@@ -107,7 +137,7 @@ impl BlockchainClient {
         };
 
         let result = self.provider
-            .call(call, BlockId::Tag(BlockTag::Latest))
+            .call(call, block)
             .await
             .map_err(|e| format!("Failed to call balance_of: {}", e))?;
 
@@ -353,25 +383,27 @@ impl BlockchainClient {
         ))
     }
 
-    /// Search for a specific commitment in Deposit events
-    /// Returns the leaf_index if found
-    /// This is much faster than waiting for full sync when looking for a specific commitment
-    pub async fn find_commitment_in_events(&self, commitment: &str) -> Result<Option<u32>, String> {
+    /// Search for a specific commitment in the Deposit events emitted after `after_block`
+    /// At most the newest `max_blocks` blocks are scanned; returns the leaf_index if found
+    /// Meant for the unsynced tail only - synced deposits are answered from the event index
+    pub async fn find_commitment_in_events(
+        &self,
+        commitment: &str,
+        after_block: u64,
+        max_blocks: u64,
+    ) -> Result<Option<u32>, String> {
         use starknet::core::types::EventFilter;
-        use num_bigint::BigUint;
         
         let commitment_felt = parse_felt(commitment)?;
-        let commitment_bigint = BigUint::from_bytes_be(&commitment_felt.to_bytes_be());
         
-        // Deposit event selector (same as in syncer.rs)
-        let deposit_selector = FieldElement::from_hex_be("0x9149d2123147c5f43d258257fef0b7b969db78269369ebcf5ebb9eef8592f2")
-            .map_err(|e| format!("Failed to parse deposit selector: {}", e))?;
-        
-        // Always search from contract deployment block to ensure we find all deposits
-        // This is critical - even if syncer missed events, we can still find them here
-        let from_block = 4438440u64;
         let latest_block = self.provider.block_number().await
             .map_err(|e| format!("Failed to get latest block: {}", e))?;
+        let from_block = (after_block + 1)
+            .max(latest_block.saturating_sub(max_blocks.saturating_sub(1)))
+            .max(self.deployment_block);
+        if from_block > latest_block {
+            return Ok(None);
+        }
         
         // Filter for all events from our contract
         // We can't filter by commitment in keys, so we'll search through all Deposit events
@@ -379,7 +411,7 @@ impl BlockchainClient {
             from_block: Some(BlockId::Number(from_block)),
             to_block: Some(BlockId::Number(latest_block)),
             address: Some(self.zylith_address),
-            keys: None, // We'll check all events and filter by Deposit selector + commitment
+            keys: None, // Deposits are told apart by the event decoder
         };
         
         let chunk_size = 1000;
@@ -397,32 +429,14 @@ impl BlockchainClient {
             
             for event in events_page.events {
                 events_searched += 1;
-                
-                // Check if this is a Deposit event (for nested events, selector can be in any key)
-                let is_deposit = !event.keys.is_empty() && 
-                    event.keys.iter().any(|key| *key == deposit_selector);
-                
-                if is_deposit && event.data.len() >= 3 {
+
+                if let Ok(ZylithEvent::Privacy(PrivacyEvent::Deposit(deposit))) =
+                    decode_zylith_event(&event.keys, &event.data)
+                {
                     deposit_events_found += 1;
-                    // Parse commitment from data[0]
-                    let event_commitment_felt = event.data[0];
-                    let event_commitment_bigint = BigUint::from_bytes_be(&event_commitment_felt.to_bytes_be());
-                    
-                    // Skip logging commitment details
-                    
-                    if event_commitment_bigint == commitment_bigint {
-                        // Found it! Extract leaf_index from data[1]
-                        let leaf_index_felt = event.data[1];
-                        let leaf_index: u32 = {
-                            let bytes = leaf_index_felt.to_bytes_be();
-                            let mut arr = [0u8; 4];
-                            let start = bytes.len().saturating_sub(4);
-                            arr.copy_from_slice(&bytes[start..]);
-                            u32::from_be_bytes(arr)
-                        };
-                        
-                        println!("[ASP] ✅ Found commitment in events at index {} (searched {} events, {} deposit events)", leaf_index, events_searched, deposit_events_found);
-                        return Ok(Some(leaf_index));
+                    if deposit.commitment == commitment_felt {
+                        println!("[ASP] ✅ Found commitment in events at index {} (searched {} events, {} deposit events)", deposit.leaf_index, events_searched, deposit_events_found);
+                        return Ok(Some(deposit.leaf_index));
                     }
                 }
             }
@@ -436,6 +450,109 @@ impl BlockchainClient {
         println!("[ASP] ⚠️  Commitment not found after searching {} events ({} deposit events found)", events_searched, deposit_events_found);
         Ok(None)
     }
+
+    /// Read what `private_collect` pays a private position from, all at the latest block:
+    /// the pool's tick and fee_growth_global, the fee_growth_outside of the range's ticks and
+    /// `positions.positions[(position_commitment, tick_lower, tick_upper)]`
+    /// Returns the block the state was read at
+    pub async fn get_position_fee_state(
+        &self,
+        position_commitment: &str,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<(u64, PositionFeeState), String> {
+        let block = self.provider.block_number().await
+            .map_err(|e| format!("Failed to get latest block: {}", e))?;
+        let pool = |member: &str| storage_address("pool", &[starknet_keccak(member.as_bytes())]);
+        let tick = |tick: i32| storage_address("ticks", &[i32_to_felt(tick)]);
+        let position = storage_address("positions", &[
+            starknet_keccak("positions".as_bytes()),
+            parse_felt(position_commitment)?,
+            i32_to_felt(tick_lower),
+            i32_to_felt(tick_upper),
+        ])?;
+
+        // TickInfo: liquidity_gross, liquidity_net, then fee_growth_outside0/1_x128 at offsets 2 and 4
+        let (lower, upper) = (tick(tick_lower)?, tick(tick_upper)?);
+        // PositionInfo: liquidity, fee_growth_inside0/1_last_x128 at offsets 1 and 3, tokens_owed0/1
+        let state = PositionFeeState {
+            current_tick: felt_to_i32(self.read_storage(pool("tick")?, 0, block).await?)?,
+            fee_growth_global: FeeGrowth {
+                token0: self.read_u256(pool("fee_growth_global0_x128")?, 0, block).await?,
+                token1: self.read_u256(pool("fee_growth_global1_x128")?, 0, block).await?,
+            },
+            outside_lower: self.read_fee_growth(lower, 2, block).await?,
+            outside_upper: self.read_fee_growth(upper, 2, block).await?,
+            liquidity: felt_to_u128(self.read_storage(position, 0, block).await?)?,
+            fee_growth_inside_last: self.read_fee_growth(position, 1, block).await?,
+            tokens_owed0: felt_to_u128(self.read_storage(position, 5, block).await?)?,
+            tokens_owed1: felt_to_u128(self.read_storage(position, 6, block).await?)?,
+        };
+        Ok((block, state))
+    }
+
+    /// One storage slot of the Zylith contract at `block`
+    async fn read_storage(&self, base: FieldElement, offset: u64, block: u64) -> Result<FieldElement, String> {
+        let address = base + FieldElement::from(offset);
+        self.provider
+            .get_storage_at(self.zylith_address, address, BlockId::Number(block))
+            .await
+            .map_err(|e| format!("Failed to read storage 0x{:x}: {}", address, e))
+    }
+
+    /// A u256 stored as its (low, high) limbs
+    async fn read_u256(&self, base: FieldElement, offset: u64, block: u64) -> Result<U256, String> {
+        let low = felt_to_u128(self.read_storage(base, offset, block).await?)?;
+        let high = felt_to_u128(self.read_storage(base, offset + 1, block).await?)?;
+        Ok(U256::from(low) | (U256::from(high) << 128))
+    }
+
+    /// Two consecutive u256 fields, token0's first
+    async fn read_fee_growth(&self, base: FieldElement, offset: u64, block: u64) -> Result<FeeGrowth, String> {
+        Ok(FeeGrowth {
+            token0: self.read_u256(base, offset, block).await?,
+            token1: self.read_u256(base, offset + 2, block).await?,
+        })
+    }
+}
+
+/// Storage address of `variable` followed by storage node members and map keys: Cairo's
+/// storage paths hash them in the same Pedersen chain as a legacy storage_var's arguments
+fn storage_address(variable: &str, path: &[FieldElement]) -> Result<FieldElement, String> {
+    get_storage_var_address(variable, path).map_err(|e| format!("Invalid storage variable '{}': {}", variable, e))
+}
+
+/// An i32 as Cairo hashes and stores it: negative values are PRIME - |value|
+fn i32_to_felt(value: i32) -> FieldElement {
+    let magnitude = FieldElement::from(value.unsigned_abs());
+    if value < 0 {
+        FieldElement::ZERO - magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn felt_to_i32(felt: FieldElement) -> Result<i32, String> {
+    let negated = FieldElement::ZERO - felt;
+    if let Ok(value) = felt_to_u128(felt) {
+        if value <= i32::MAX as u128 {
+            return Ok(value as i32);
+        }
+    }
+    match felt_to_u128(negated) {
+        Ok(value) if value <= 1u128 << 31 => Ok(-(value as i64) as i32),
+        _ => Err(format!("Stored value 0x{:x} is not an i32", felt)),
+    }
+}
+
+fn felt_to_u128(felt: FieldElement) -> Result<u128, String> {
+    let bytes = felt.to_bytes_be();
+    if bytes[..16].iter().any(|b| *b != 0) {
+        return Err(format!("Stored value 0x{:x} is not a u128", felt));
+    }
+    let mut low = [0u8; 16];
+    low.copy_from_slice(&bytes[16..]);
+    Ok(u128::from_be_bytes(low))
 }
 
 /// Get function selector from function name
@@ -450,3 +567,29 @@ fn parse_felt(hex_str: &str) -> Result<FieldElement, String> {
         .map_err(|e| format!("Failed to parse felt252 '{}': {}", hex_str, e))
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_encoding() {
+        // Negative ticks are stored and hashed as PRIME - |tick|
+        for tick in [0, 60, -60, i32::MAX, i32::MIN] {
+            assert_eq!(felt_to_i32(i32_to_felt(tick)).unwrap(), tick);
+        }
+        assert_eq!(i32_to_felt(-1) + FieldElement::ONE, FieldElement::ZERO);
+        assert!(felt_to_i32(FieldElement::from(1u64 << 31)).is_err());
+        assert!(felt_to_u128(FieldElement::from(u128::MAX) + FieldElement::ONE).is_err());
+
+        // A storage node member is one more key in the chain; map keys follow it
+        let member = starknet_keccak("positions".as_bytes());
+        let key = [member, FieldElement::from(0x333u64), i32_to_felt(-600), i32_to_felt(600)];
+        let node = storage_address("positions", &key[..1]).unwrap();
+        assert_eq!(storage_address("positions", &key).unwrap(), {
+            let to_crypto = |felt: FieldElement| CryptoFieldElement::from_bytes_be(&felt.to_bytes_be()).unwrap();
+            let hash = key[1..].iter().fold(to_crypto(node), |hash, key| pedersen_hash(&hash, &to_crypto(*key)));
+            FieldElement::from_bytes_be(&hash.to_bytes_be()).unwrap()
+        });
+    }
+}
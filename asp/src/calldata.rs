@@ -2,6 +2,7 @@ use starknet::core::types::FieldElement;
 use std::str::FromStr;
 use num_bigint::BigUint;
 use num_traits::Num;
+use primitive_types::U256;
 
 /// Build calldata for ERC20 approve
 pub fn build_approve_calldata(spender: &str, amount_low: u128, amount_high: u128) -> Result<Vec<FieldElement>, String> {
@@ -46,46 +47,32 @@ pub fn build_swap_calldata(
     public_inputs: &[String],
     zero_for_one: bool,
     amount_specified: u128,
-    sqrt_price_limit_low: u128,
-    sqrt_price_limit_high: u128,
+    sqrt_price_limit: &str,
     new_commitment: &str,
 ) -> Result<Vec<FieldElement>, String> {
     // private_swap(
-    //   proof: Array<felt252>,
-    //   public_inputs: Array<felt252>,
     //   zero_for_one: bool,
     //   amount_specified: u128,
     //   sqrt_price_limit_x128: u256,
-    //   new_commitment: felt252
+    //   new_commitment: felt252,
+    //   proof: Array<felt252>,
+    //   public_inputs: Array<felt252>
     // )
-    
-    let mut calldata = Vec::new();
-    
-    // Format proof array: [length, ...elements]
-    calldata.push(FieldElement::from(proof.len() as u64));
-    for p in proof {
-        calldata.push(parse_felt(p)?);
-    }
-    
-    // Format public_inputs array: [length, ...elements]
-    calldata.push(FieldElement::from(public_inputs.len() as u64));
-    for pi in public_inputs {
-        calldata.push(parse_felt(pi)?);
-    }
-    
-    // zero_for_one: bool -> 0 or 1
-    calldata.push(if zero_for_one { FieldElement::ONE } else { FieldElement::ZERO });
-    
-    // amount_specified: u128
-    calldata.push(FieldElement::from(amount_specified));
-    
+    // The arrays go last, matching the deployed ABI
+
     // sqrt_price_limit_x128: u256 -> [low, high]
-    calldata.push(FieldElement::from(sqrt_price_limit_low));
-    calldata.push(FieldElement::from(sqrt_price_limit_high));
-    
-    // new_commitment: felt252
-    calldata.push(parse_felt(new_commitment)?);
-    
+    let (sqrt_price_limit_low, sqrt_price_limit_high) =
+        u256_to_low_high(crate::clmm::parse_u256(sqrt_price_limit)?);
+
+    let mut calldata = vec![
+        // zero_for_one: bool -> 0 or 1
+        if zero_for_one { FieldElement::ONE } else { FieldElement::ZERO },
+        FieldElement::from(amount_specified),
+        FieldElement::from(sqrt_price_limit_low),
+        FieldElement::from(sqrt_price_limit_high),
+        parse_felt(new_commitment)?,
+    ];
+    push_arrays(&mut calldata, proof, public_inputs)?;
     Ok(calldata)
 }
 
@@ -98,27 +85,16 @@ pub fn build_withdraw_calldata(
     amount: u128,
 ) -> Result<Vec<FieldElement>, String> {
     // private_withdraw(
-    //   proof: Array<felt252>,
-    //   public_inputs: Array<felt252>,
     //   token: ContractAddress,
     //   recipient: ContractAddress,
-    //   amount: u128
+    //   amount: u128,
+    //   proof: Array<felt252>,
+    //   public_inputs: Array<felt252>
     // )
+    // The arrays go last, matching the deployed ABI
     
     let mut calldata = Vec::new();
     
-    // Format proof array
-    calldata.push(FieldElement::from(proof.len() as u64));
-    for p in proof {
-        calldata.push(parse_felt(p)?);
-    }
-    
-    // Format public_inputs array
-    calldata.push(FieldElement::from(public_inputs.len() as u64));
-    for pi in public_inputs {
-        calldata.push(parse_felt(pi)?);
-    }
-    
     // token: ContractAddress -> single felt252
     let token_felt = parse_felt(token)?;
     calldata.push(token_felt);
@@ -130,11 +106,22 @@ pub fn build_withdraw_calldata(
     // amount: u128
     calldata.push(FieldElement::from(amount));
     
+    // Format proof array: [length, ...elements]
+    calldata.push(FieldElement::from(proof.len() as u64));
+    for p in proof {
+        calldata.push(parse_felt(p)?);
+    }
+    
+    // Format public_inputs array: [length, ...elements]
+    calldata.push(FieldElement::from(public_inputs.len() as u64));
+    for pi in public_inputs {
+        calldata.push(parse_felt(pi)?);
+    }
+    
     Ok(calldata)
 }
 
 /// Build calldata for private_mint_liquidity
-/// Returns Vec<String> with decimal strings (not hex) to avoid JSON serialization issues
 pub fn build_mint_liquidity_calldata(
     proof: &[String],
     public_inputs: &[String],
@@ -142,76 +129,47 @@ pub fn build_mint_liquidity_calldata(
     tick_upper: i32,
     liquidity: u128,
     new_commitment: &str,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<FieldElement>, String> {
     // private_mint_liquidity(
-    //   proof: Array<felt252>,
-    //   public_inputs: Array<felt252>,
-    //   tick_lower: i32,
-    //   tick_upper: i32,
+    //   tick_lower_felt: felt252,
+    //   tick_upper_felt: felt252,
     //   liquidity: u128,
-    //   new_commitment: felt252
+    //   new_commitment: felt252,
+    //   proof: Array<felt252>,
+    //   public_inputs: Array<felt252>
     // )
     
     println!("[Calldata] Building mint_liquidity calldata:");
-    println!("[Calldata]   proof length: {}", proof.len());
-    println!("[Calldata]   public_inputs length: {}", public_inputs.len());
     println!("[Calldata]   tick_lower: {} (i32)", tick_lower);
     println!("[Calldata]   tick_upper: {} (i32)", tick_upper);
     println!("[Calldata]   liquidity: {}", liquidity);
     println!("[Calldata]   new_commitment: {}", new_commitment);
+    println!("[Calldata]   proof length: {}", proof.len());
+    println!("[Calldata]   public_inputs length: {}", public_inputs.len());
     
-    let mut calldata = Vec::new();
-    
-    // Format proof array - convert to decimal strings
-    calldata.push(proof.len().to_string());
+    let mut calldata = vec![
+        // Ticks: the contract decodes tick_*_felt as a 32-bit two's complement
+        // (_felt252_to_i32), e.g. -1000 -> 0xfffffc18
+        FieldElement::from(tick_lower as u32),
+        FieldElement::from(tick_upper as u32),
+        FieldElement::from(liquidity),
+        parse_felt(new_commitment)?,
+    ];
+    push_arrays(&mut calldata, proof, public_inputs)?;
+    Ok(calldata)
+}
+
+/// Append the proof and public input arrays, each as [length, ...elements]
+fn push_arrays(calldata: &mut Vec<FieldElement>, proof: &[String], public_inputs: &[String]) -> Result<(), String> {
+    calldata.push(FieldElement::from(proof.len() as u64));
     for p in proof {
-        // Parse and convert to decimal string
-        let felt = parse_felt(p)?;
-        let fe_str = format!("{:x}", felt); // Get hex without 0x prefix
-        let big_uint = BigUint::from_str_radix(&fe_str, 16)
-            .map_err(|e| format!("Failed to parse proof element '{}': {}", p, e))?;
-        calldata.push(big_uint.to_str_radix(10)); // Convert to decimal string
+        calldata.push(parse_felt(p)?);
     }
-    
-    // Format public_inputs array - convert to decimal strings
-    calldata.push(public_inputs.len().to_string());
+    calldata.push(FieldElement::from(public_inputs.len() as u64));
     for pi in public_inputs {
-        // Parse and convert to decimal string
-        let felt = parse_felt(pi)?;
-        let fe_str = format!("{:x}", felt); // Get hex without 0x prefix
-        let big_uint = BigUint::from_str_radix(&fe_str, 16)
-            .map_err(|e| format!("Failed to parse public input '{}': {}", pi, e))?;
-        calldata.push(big_uint.to_str_radix(10)); // Convert to decimal string
+        calldata.push(parse_felt(pi)?);
     }
-    
-    println!("[Calldata] After arrays: calldata length = {}", calldata.len());
-    
-    // tick_lower: i32 -> send as signed integer string
-    // StarkNet.js will handle the conversion to felt252 internally
-    // Don't pre-convert to felt252 - let the SDK do it
-    let tick_lower_str = tick_lower.to_string(); // e.g., "-1000" or "1000"
-    println!("[Calldata] tick_lower: {} (i32) -> {} (string) - StarkNet.js will convert to felt252", 
-        tick_lower, tick_lower_str);
-    calldata.push(tick_lower_str);
-    
-    // tick_upper: i32 -> send as signed integer string
-    // StarkNet.js will handle the conversion to felt252 internally
-    let tick_upper_str = tick_upper.to_string(); // e.g., "-1000" or "1000"
-    println!("[Calldata] tick_upper: {} (i32) -> {} (string) - StarkNet.js will convert to felt252", 
-        tick_upper, tick_upper_str);
-    calldata.push(tick_upper_str);
-    
-    // liquidity: u128 -> decimal string
-    calldata.push(liquidity.to_string());
-    
-    // new_commitment: felt252 -> decimal string
-    let commitment_felt = parse_felt(new_commitment)?;
-    let commitment_str = format!("{:x}", commitment_felt); // Get hex without 0x prefix
-    let commitment_big = BigUint::from_str_radix(&commitment_str, 16)
-        .map_err(|e| format!("Failed to parse commitment '{}': {}", new_commitment, e))?;
-    calldata.push(commitment_big.to_str_radix(10)); // Convert to decimal string
-    
-    Ok(calldata)
+    Ok(())
 }
 
 /// Build calldata for private_burn_liquidity
@@ -222,51 +180,82 @@ pub fn build_burn_liquidity_calldata(
     tick_upper: i32,
     liquidity: u128,
     new_commitment: &str,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<FieldElement>, String> {
     // Same signature as mint
     build_mint_liquidity_calldata(proof, public_inputs, tick_lower, tick_upper, liquidity, new_commitment)
 }
 
-/// Convert u256 amount to (low, high) tuple
-pub fn u256_to_low_high(amount: u128) -> (u128, u128) {
-    // For amounts that fit in u128, high is always 0
-    (amount, 0)
+/// Verified public inputs `private_collect` reads, by index. Collects are proven with the LP
+/// circuit, so this is the LP layout: liquidity (index 4) must be 0 and new_commitment
+/// (index 5) must equal the call's new_commitment argument
+pub const PRIVATE_COLLECT_PUBLIC_INPUTS: [&str; 7] = [
+    "nullifier",
+    "root",
+    "tick_lower",
+    "tick_upper",
+    "liquidity",
+    "new_commitment",
+    "position_commitment",
+];
+
+/// Build calldata for private_collect
+pub fn build_collect_calldata(
+    proof: &[String],
+    public_inputs: &[String],
+    tick_lower: i32,
+    tick_upper: i32,
+    new_commitment: &str,
+) -> Result<Vec<FieldElement>, String> {
+    // private_collect(
+    //   tick_lower_felt: felt252,
+    //   tick_upper_felt: felt252,
+    //   new_commitment: felt252,
+    //   proof: Array<felt252>,
+    //   public_inputs: Array<felt252>
+    // )
+    let new_commitment = parse_felt(new_commitment)?;
+    if public_inputs.len() != PRIVATE_COLLECT_PUBLIC_INPUTS.len() {
+        return Err(format!(
+            "private_collect reads {} public inputs, got {}",
+            PRIVATE_COLLECT_PUBLIC_INPUTS.len(),
+            public_inputs.len()
+        ));
+    }
+    if parse_felt(&public_inputs[4])? != FieldElement::ZERO {
+        return Err(format!("A collect proof must have liquidity 0, got {}", public_inputs[4]));
+    }
+    if parse_felt(&public_inputs[5])? != new_commitment {
+        return Err(format!(
+            "Public input new_commitment {} does not match new_commitment 0x{:x} (the contract would revert)",
+            public_inputs[5], new_commitment
+        ));
+    }
+
+    let mut calldata = vec![
+        // Ticks as 32-bit two's complement, like mint and burn
+        FieldElement::from(tick_lower as u32),
+        FieldElement::from(tick_upper as u32),
+        new_commitment,
+    ];
+    push_arrays(&mut calldata, proof, public_inputs)?;
+    Ok(calldata)
+}
+
+/// Split a u256 into its Cairo (low, high) u128 limbs
+pub fn u256_to_low_high(value: U256) -> (u128, u128) {
+    (value.low_u128(), (value >> 128).low_u128())
 }
 
 // Note: ContractAddress in Cairo is a single felt252, NOT u256
 // It should be passed directly as a FieldElement, not split into low/high
 
-/// Convert i32 to felt252 (handles negative values)
-/// In Cairo, i32 negative values use Cairo prime field arithmetic
-/// Negative value -n is represented as: CAIRO_PRIME - n
-/// CAIRO_PRIME = 2^251 + 17 * 2^192 + 1 = 0x800000000000011000000000000000000000000000000000000000000000001
-fn i32_to_felt(value: i32) -> FieldElement {
-    if value >= 0 {
-        FieldElement::from(value as u64)
-    } else {
-        // For negative: use Cairo prime field arithmetic
-        // Formula: CAIRO_PRIME - |value|
-        // CAIRO_PRIME = 3618502788666131106986593281521497120414687020801267626233049500247285301248
-        let cairo_prime_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-        let cairo_prime = FieldElement::from_str(cairo_prime_str)
-            .expect("Failed to parse CAIRO_PRIME constant");
-        
-        let abs_value = (-value) as u64;
-        let abs_felt = FieldElement::from(abs_value);
-        
-        // CAIRO_PRIME - abs_value (field arithmetic)
-        cairo_prime - abs_felt
-    }
-}
-
 /// Build calldata for initialize
 pub fn build_initialize_calldata(
     token0: &str,
     token1: &str,
     fee: u128,
     tick_spacing: i32,
-    sqrt_price_low: u128,
-    sqrt_price_high: u128,
+    sqrt_price_x128: U256,
 ) -> Result<Vec<FieldElement>, String> {
     // initialize(
     //     token0: ContractAddress,
@@ -284,6 +273,7 @@ pub fn build_initialize_calldata(
     // In Cairo, i32 is stored as a felt252, which can represent negative values
     // For simplicity, we'll pass it as u128 and let Cairo handle the conversion
     let tick_spacing_u128 = tick_spacing as u128;
+    let (sqrt_price_low, sqrt_price_high) = u256_to_low_high(sqrt_price_x128);
     
     Ok(vec![
         token0_felt, // ContractAddress as single felt252
@@ -296,36 +286,138 @@ pub fn build_initialize_calldata(
 }
 
 /// Parse felt252 from hex string or decimal string
-/// Handles values that may exceed felt252 by applying modulo arithmetic
+/// Values at or above the Starknet prime are rejected: reducing them would put a different
+/// value in the calldata than the one that was proved or signed for
 fn parse_felt(value_str: &str) -> Result<FieldElement, String> {
-    // STARKNET_FELT_MAX = 2^251 + 17 * 2^192 + 1
-    // This value exceeds u128, so we use BigUint for calculations
-    let felt_max_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-    let felt_max_big = BigUint::from_str(felt_max_str)
-        .map_err(|_| "Failed to parse FELT_MAX constant".to_string())?;
-    
-    // Parse value as BigUint (handles both hex and decimal)
-    let value_big = if value_str.starts_with("0x") {
-        // Parse hex string - remove "0x" prefix and parse as base 16
-        num_bigint::BigUint::from_str_radix(&value_str[2..], 16)
+    let value_big = if let Some(hex) = value_str.strip_prefix("0x") {
+        BigUint::from_str_radix(hex, 16)
             .map_err(|e| format!("Failed to parse hex value '{}': {}", value_str, e))?
     } else {
-        // Parse decimal string
         BigUint::from_str(value_str)
             .map_err(|e| format!("Failed to parse decimal value '{}': {}", value_str, e))?
     };
-    
-    // Apply felt252 modulo if value exceeds limit
-    let modulo_big = if value_big >= felt_max_big {
-        &value_big % &felt_max_big
-    } else {
-        value_big.clone()
-    };
-    
-    // Convert BigUint to string and parse as FieldElement
-    // FieldElement can handle the full felt252 range directly
-    let modulo_str = modulo_big.to_str_radix(10);
-    FieldElement::from_str(&modulo_str)
+
+    let prime = BigUint::from_str(crate::garaga::STARKNET_PRIME)
+        .map_err(|_| "Failed to parse STARKNET_PRIME".to_string())?;
+    if value_big >= prime {
+        return Err(format!("Value '{}' is not a felt252 (not below the Starknet prime)", value_str));
+    }
+
+    FieldElement::from_str(&value_big.to_str_radix(10))
         .map_err(|e| format!("Failed to convert to FieldElement: {}", e))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_withdraw_calldata_follows_abi_order() {
+        let proof: Vec<String> = (1..=8).map(|i| i.to_string()).collect();
+        let public_inputs = vec!["0x11".to_string(), "0x22".to_string(), "0x33".to_string(), "500".to_string()];
+        let calldata = build_withdraw_calldata(&proof, &public_inputs, "0xabc", "0x33", 500).unwrap();
+
+        // token, recipient, amount, then the proof and public input arrays
+        assert_eq!(calldata.len(), 3 + 1 + 8 + 1 + 4);
+        assert_eq!(calldata[0], FieldElement::from(0xabcu64));
+        assert_eq!(calldata[1], FieldElement::from(0x33u64));
+        assert_eq!(calldata[2], FieldElement::from(500u64));
+        assert_eq!(calldata[3], FieldElement::from(8u64));
+        assert_eq!(calldata[11], FieldElement::from(8u64));
+        assert_eq!(calldata[12], FieldElement::from(4u64));
+        assert_eq!(calldata[16], FieldElement::from(500u64));
+
+        assert!(build_withdraw_calldata(&proof, &public_inputs, "not-a-token", "0x33", 500).is_err());
+        // felt252 values stop below the Starknet prime; nothing is reduced into range
+        let prime = crate::garaga::STARKNET_PRIME;
+        assert!(build_withdraw_calldata(&proof, &public_inputs, "0xabc", prime, 500).is_err());
+        let max_felt = (BigUint::from_str(prime).unwrap() - 1u8).to_string();
+        assert!(build_withdraw_calldata(&proof, &public_inputs, "0xabc", &max_felt, 500).is_ok());
+    }
+
+    #[test]
+    fn test_swap_calldata_follows_abi_order() {
+        let proof: Vec<String> = (1..=8).map(|i| i.to_string()).collect();
+        let public_inputs: Vec<String> = (1..=9).map(|i| i.to_string()).collect();
+        // 2^128 + 5: the limit's high limb is set
+        let calldata = build_swap_calldata(&proof, &public_inputs, true, 1000, "5,1", "0xff").unwrap();
+
+        // zero_for_one, amount_specified, sqrt_price_limit (low, high), new_commitment, then the arrays
+        assert_eq!(calldata.len(), 5 + 1 + 8 + 1 + 9);
+        assert_eq!(calldata[0], FieldElement::ONE);
+        assert_eq!(calldata[1], FieldElement::from(1000u64));
+        assert_eq!(calldata[2], FieldElement::from(5u64));
+        assert_eq!(calldata[3], FieldElement::ONE);
+        assert_eq!(calldata[4], FieldElement::from(0xffu64));
+        assert_eq!(calldata[5], FieldElement::from(8u64));
+        assert_eq!(calldata[14], FieldElement::from(9u64));
+
+        let decimal = build_swap_calldata(&proof, &public_inputs, true, 1000, "340282366920938463463374607431768211461", "0xff");
+        assert_eq!(decimal.unwrap(), calldata);
+        assert!(build_swap_calldata(&proof, &public_inputs, false, 1000, "not-a-price", "0xff").is_err());
+    }
+
+    #[test]
+    fn test_liquidity_calldata_follows_abi_order() {
+        let proof: Vec<String> = (1..=8).map(|i| i.to_string()).collect();
+        let public_inputs: Vec<String> = (1..=7).map(|i| format!("0x{:x}", i * 16)).collect();
+        let calldata = build_mint_liquidity_calldata(&proof, &public_inputs, -1000, 600, 400, "0xff").unwrap();
+
+        // tick_lower, tick_upper, liquidity, new_commitment, then the arrays
+        assert_eq!(calldata.len(), 4 + 1 + 8 + 1 + 7);
+        let felts = |values: &[u64]| values.iter().map(|v| FieldElement::from(*v)).collect::<Vec<_>>();
+        assert_eq!(calldata[..4], felts(&[0xfffffc18, 600, 400, 0xff]));
+        assert_eq!(calldata[4], FieldElement::from(8u64));
+        assert_eq!(calldata[13], FieldElement::from(7u64));
+        assert_eq!(calldata[14], FieldElement::from(16u64));
+
+        let burn = build_burn_liquidity_calldata(&proof, &public_inputs, -1000, 600, 400, "0xff").unwrap();
+        assert_eq!(burn, calldata);
+    }
+
+    #[test]
+    fn test_collect_calldata_matches_private_collect_layout() {
+        // The LP circuit's public inputs are what private_collect indexes
+        assert_eq!(crate::verifier::public_input_names("lp"), PRIVATE_COLLECT_PUBLIC_INPUTS);
+
+        let proof: Vec<String> = (1..=8).map(|i| i.to_string()).collect();
+        let mut public_inputs: Vec<String> = (1..=7).map(|i| format!("0x{:x}", i * 16)).collect();
+        public_inputs[4] = "0".to_string();
+        public_inputs[5] = "0xff".to_string();
+        let collect = build_collect_calldata(&proof, &public_inputs, -1000, 600, "0xff").unwrap();
+
+        // tick_lower, tick_upper, new_commitment, then the arrays; the new_commitment argument
+        // is the public input the contract reads at index 5
+        assert_eq!(collect.len(), 3 + 1 + 8 + 1 + 7);
+        assert_eq!(collect[0], FieldElement::from(0xfffffc18u64));
+        assert_eq!(collect[1], FieldElement::from(600u64));
+        assert_eq!(collect[2], FieldElement::from(0xffu64));
+        assert_eq!(collect[12], FieldElement::from(7u64));
+        let verified = &collect[13..];
+        assert_eq!(verified[4], FieldElement::ZERO);
+        assert_eq!(verified[5], collect[2]);
+        assert_eq!(verified[6], FieldElement::from(112u64));
+
+        // A six-input layout (new_commitment at 4, position_commitment at 5) and moved
+        // liquidity are rejected before they reach the chain
+        let mut shifted = public_inputs.clone();
+        shifted.remove(4);
+        assert!(build_collect_calldata(&proof, &shifted, -1000, 600, "0xff").is_err());
+        let mut liquidity = public_inputs.clone();
+        liquidity[4] = "1".to_string();
+        assert!(build_collect_calldata(&proof, &liquidity, -1000, 600, "0xff").is_err());
+        assert!(build_collect_calldata(&proof, &public_inputs, -1000, 600, "0xfe").is_err());
+    }
+
+    #[test]
+    fn test_initialize_calldata_splits_u256_price() {
+        // sqrt price just above 2 * 2^128: above the 1:1 price, so the high limb is set
+        let sqrt_price_x128 = (U256::from(2u8) << 128) | U256::from(7u8);
+        let calldata = build_initialize_calldata("0x1", "0x2", 3000, -60, sqrt_price_x128).unwrap();
+        assert_eq!(calldata[4], FieldElement::from(7u64));
+        assert_eq!(calldata[5], FieldElement::from(2u64));
+
+        assert_eq!(u256_to_low_high(U256::MAX), (u128::MAX, u128::MAX));
+        assert_eq!(u256_to_low_high(U256::from(u128::MAX)), (u128::MAX, 0));
+    }
+}
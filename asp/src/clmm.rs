@@ -0,0 +1,681 @@
+// CLMM math - port of the contract's clmm/math.cairo, liquidity.cairo and swap step
+// Every function reproduces the Cairo arithmetic step by step (same rounding, same
+// clamps, same approximations) so results match the contract bit for bit. Where Cairo
+// would panic (u256/u128 overflow, failed asserts), these return an error instead.
+
+use crate::ticks::{TickMap, MAX_TICK, MIN_TICK, TICK_SPACING};
+use num_bigint::BigUint;
+use primitive_types::U256;
+
+/// 2^128, sqrt price of tick 0 (price = 1.0)
+pub const Q128: U256 = U256([0, 0, 1, 0]);
+/// 2^64, lowest sqrt price the contract accepts
+pub const MIN_SQRT_RATIO: U256 = U256([0, 1, 0, 0]);
+/// 2^192, highest sqrt price the contract accepts
+pub const MAX_SQRT_RATIO: U256 = U256([0, 0, 0, 1]);
+
+/// Fee denominator: pool fees are in millionths (3000 = 0.3%)
+const FEE_DENOMINATOR: u128 = 1_000_000;
+
+/// Sqrt price (Q128.128) at a tick
+/// The contract uses a linear approximation, 1 + |tick| / d with d = 20000, 2000 or 200
+/// depending on |tick|, clamped to [MIN_SQRT_RATIO, MAX_SQRT_RATIO]
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256, String> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(format!("Tick {} out of bounds", tick));
+    }
+    if tick == 0 {
+        return Ok(Q128);
+    }
+
+    let abs_tick = tick.unsigned_abs();
+    let denominator = if abs_tick <= 100 {
+        20000u32
+    } else if abs_tick <= 1000 {
+        2000
+    } else {
+        200
+    };
+    let delta = mul(Q128, U256::from(abs_tick))? / U256::from(denominator);
+
+    if tick > 0 {
+        Ok(add(Q128, delta)?.min(MAX_SQRT_RATIO))
+    } else if delta < Q128 {
+        Ok((Q128 - delta).max(MIN_SQRT_RATIO))
+    } else {
+        Ok(MIN_SQRT_RATIO)
+    }
+}
+
+/// Highest tick whose sqrt price is at or below `sqrt_price_x128` (binary search, as on-chain)
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x128: U256) -> Result<i32, String> {
+    if sqrt_price_x128 == Q128 {
+        return Ok(0);
+    }
+    if sqrt_price_x128 < MIN_SQRT_RATIO || sqrt_price_x128 > MAX_SQRT_RATIO {
+        return Err(format!("Sqrt price {} out of bounds", sqrt_price_x128));
+    }
+
+    let mut low = MIN_TICK;
+    let mut high = MAX_TICK;
+    while low < high {
+        // Cairo's integer division truncates toward zero, like Rust's
+        let mid = (low + high + 1) / 2;
+        if get_sqrt_ratio_at_tick(mid)? <= sqrt_price_x128 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// (a * b) / 2^128
+pub fn mul_q128(a: U256, b: U256) -> Result<U256, String> {
+    Ok(mul(a, b)? / Q128)
+}
+
+/// Token0 backing `liquidity` over a price range
+pub fn get_amount0_for_liquidity(sqrt_price_a_x128: U256, sqrt_price_b_x128: U256, liquidity: u128) -> Result<u128, String> {
+    let (price_lower, price_upper) = ordered_range(sqrt_price_a_x128, sqrt_price_b_x128)?;
+    let q64 = U256::one() << 64;
+
+    // amount0 = L * (P_upper - P_lower) / (P_upper/2^64 * P_lower/2^64)
+    let denominator = mul(price_upper / q64, price_lower / q64)?;
+    if denominator.is_zero() {
+        return Err("Denominator overflow/underflow".to_string());
+    }
+    let numerator = mul(U256::from(liquidity), price_upper - price_lower)?;
+    to_u128(numerator / denominator)
+}
+
+/// Token1 backing `liquidity` over a price range (0 for an empty range)
+pub fn get_amount1_for_liquidity(sqrt_price_a_x128: U256, sqrt_price_b_x128: U256, liquidity: u128) -> Result<u128, String> {
+    let (price_lower, price_upper) = if sqrt_price_a_x128 < sqrt_price_b_x128 {
+        (sqrt_price_a_x128, sqrt_price_b_x128)
+    } else {
+        (sqrt_price_b_x128, sqrt_price_a_x128)
+    };
+    if price_lower >= price_upper {
+        return Ok(0);
+    }
+
+    // amount1 = L * (P_upper - P_lower) / 2^128
+    to_u128(mul(U256::from(liquidity), price_upper - price_lower)? / Q128)
+}
+
+/// Tokens `private_mint_liquidity` accounts for `liquidity` at the current price
+pub fn get_private_mint_amounts(
+    current_tick: i32,
+    current_sqrt_price_x128: U256,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<(u128, u128), String> {
+    validate_range(tick_lower, tick_upper)?;
+    let sqrt_prices = (get_sqrt_ratio_at_tick(tick_lower)?, get_sqrt_ratio_at_tick(tick_upper)?);
+    amounts_for_liquidity(current_tick, current_sqrt_price_x128, (tick_lower, tick_upper), sqrt_prices, liquidity)
+}
+
+/// Tokens `private_burn_liquidity` returns for `liquidity`, before protocol fees
+/// Unlike mint, the contract prices a burn over the whole range whatever the current tick
+pub fn get_private_burn_amounts(tick_lower: i32, tick_upper: i32, liquidity: u128) -> Result<(u128, u128), String> {
+    validate_range(tick_lower, tick_upper)?;
+    let sqrt_price_lower = get_sqrt_ratio_at_tick(tick_lower)?;
+    let sqrt_price_upper = get_sqrt_ratio_at_tick(tick_upper)?;
+    Ok((
+        get_amount0_for_liquidity(sqrt_price_lower, sqrt_price_upper, liquidity)?,
+        get_amount1_for_liquidity(sqrt_price_lower, sqrt_price_upper, liquidity)?,
+    ))
+}
+
+/// The contract's position bounds checks: ordered and on the tick spacing
+fn validate_range(tick_lower: i32, tick_upper: i32) -> Result<(), String> {
+    if tick_lower >= tick_upper {
+        return Err(format!("tick_lower {} must be below tick_upper {}", tick_lower, tick_upper));
+    }
+    if tick_lower % TICK_SPACING != 0 || tick_upper % TICK_SPACING != 0 {
+        return Err(format!("Ticks must be multiples of the tick spacing ({})", TICK_SPACING));
+    }
+    Ok(())
+}
+
+/// Token amounts for `liquidity` in a range, split on where the current tick is like mint does
+/// (token1 only below the range, token0 only at or above it)
+fn amounts_for_liquidity(
+    current_tick: i32,
+    current_sqrt_price_x128: U256,
+    (tick_lower, tick_upper): (i32, i32),
+    (sqrt_price_lower, sqrt_price_upper): (U256, U256),
+    liquidity: u128,
+) -> Result<(u128, u128), String> {
+    Ok(if current_tick < tick_lower {
+        (0, get_amount1_for_liquidity(sqrt_price_lower, sqrt_price_upper, liquidity)?)
+    } else if current_tick >= tick_upper {
+        (get_amount0_for_liquidity(sqrt_price_lower, sqrt_price_upper, liquidity)?, 0)
+    } else {
+        (
+            get_amount0_for_liquidity(current_sqrt_price_x128, sqrt_price_upper, liquidity)?,
+            get_amount1_for_liquidity(sqrt_price_lower, current_sqrt_price_x128, liquidity)?,
+        )
+    })
+}
+
+/// Result of one swap step, as `_compute_swap_step` returns it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x128: U256,
+    /// Signed like the Swap event: zero_for_one gives amount0 < 0 and amount1 > 0
+    pub amount0: i128,
+    pub amount1: i128,
+    pub fee_amount0: u128,
+    pub fee_amount1: u128,
+}
+
+/// Move the price from `sqrt_price_current` towards `sqrt_price_target` with `liquidity`,
+/// spending at most `amount_remaining`
+/// The amount to reach the target is L * |ΔP| / 2^128 for either token. If it is covered
+/// the step ends on the target; otherwise it consumes `amount_remaining` and moves the
+/// price by (amount_remaining * P / 2^128) / L. Fees are charged on the token1 leg for
+/// zero_for_one swaps and on the token0 leg otherwise
+pub fn compute_swap_step(
+    sqrt_price_current: U256,
+    sqrt_price_target: U256,
+    liquidity: u128,
+    amount_remaining: u128,
+    zero_for_one: bool,
+    fee: u128,
+) -> Result<SwapStep, String> {
+    let sqrt_price_diff = if zero_for_one {
+        sqrt_price_current.saturating_sub(sqrt_price_target)
+    } else {
+        sqrt_price_target.saturating_sub(sqrt_price_current)
+    };
+
+    let amount_needed = if sqrt_price_diff.is_zero() || liquidity == 0 {
+        0
+    } else {
+        to_u128(mul(U256::from(liquidity), sqrt_price_diff)? / Q128)?
+    };
+
+    let (sqrt_price_next_x128, amount) = if amount_remaining >= amount_needed {
+        (sqrt_price_target, amount_needed)
+    } else {
+        let next = if liquidity == 0 || sqrt_price_current.is_zero() {
+            sqrt_price_current
+        } else {
+            let price_delta = mul_q128(U256::from(amount_remaining), sqrt_price_current)? / U256::from(liquidity);
+            if !zero_for_one {
+                add(sqrt_price_current, price_delta)?
+            } else if price_delta < sqrt_price_current {
+                sqrt_price_current - price_delta
+            } else {
+                sqrt_price_target
+            }
+        };
+        (next, amount_remaining)
+    };
+
+    let amount = i128::try_from(amount).map_err(|_| format!("Swap amount {} overflows i128", amount))?;
+    let (amount0, amount1) = if zero_for_one { (-amount, amount) } else { (amount, -amount) };
+
+    let fee_on = |leg: i128| -> Result<u128, String> {
+        leg.unsigned_abs()
+            .checked_mul(fee)
+            .map(|scaled| scaled / FEE_DENOMINATOR)
+            .ok_or_else(|| "Fee amount overflows u128".to_string())
+    };
+    let (fee_amount0, fee_amount1) = if zero_for_one {
+        (0, fee_on(amount1)?)
+    } else {
+        (fee_on(amount0)?, 0)
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next_x128,
+        amount0,
+        amount1,
+        fee_amount0,
+        fee_amount1,
+    })
+}
+
+/// Pool state a swap starts from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolPrice {
+    pub sqrt_price_x128: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+}
+
+/// Outcome of a simulated swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Signed like the Swap event and the circuit's amount deltas
+    pub amount0: i128,
+    pub amount1: i128,
+    /// Pool state after the swap
+    pub end: PoolPrice,
+    /// Price limit after the contract's clamping and direction fix-up
+    pub sqrt_price_limit_x128: U256,
+    pub ticks_crossed: u32,
+    pub steps: u32,
+}
+
+/// Step cap of the contract's swap loop
+const MAX_SWAP_STEPS: u32 = 100;
+
+/// Run `_execute_swap` off-chain against `start` and the tick map
+pub fn simulate_swap(
+    start: PoolPrice,
+    ticks: &TickMap,
+    zero_for_one: bool,
+    amount_specified: u128,
+    sqrt_price_limit_x128: U256,
+) -> Result<SwapQuote, String> {
+    let PoolPrice { sqrt_price_x128: mut sqrt_price, tick: mut current_tick, mut liquidity } = start;
+
+    // A limit on the wrong side of the price is moved just past it, as on-chain
+    let min_diff = Q128 / U256::from(1_000_000u32);
+    let mut limit = sqrt_price_limit_x128.clamp(MIN_SQRT_RATIO, MAX_SQRT_RATIO);
+    if zero_for_one && limit >= sqrt_price {
+        limit = if sqrt_price > min_diff { sqrt_price - min_diff } else { MIN_SQRT_RATIO };
+    } else if !zero_for_one && limit <= sqrt_price {
+        limit = if sqrt_price < MAX_SQRT_RATIO - min_diff { sqrt_price + min_diff } else { MAX_SQRT_RATIO };
+    }
+    let tick_limit = get_tick_at_sqrt_ratio(limit)?;
+
+    let mut amount0: i128 = 0;
+    let mut amount1: i128 = 0;
+    let mut remaining = amount_specified;
+    let mut ticks_crossed = 0;
+    let mut steps = 0;
+
+    while remaining > 0 && steps < MAX_SWAP_STEPS {
+        steps += 1;
+        let next_tick = ticks.next_initialized_tick(current_tick, tick_limit, zero_for_one);
+        if next_tick == current_tick {
+            break;
+        }
+        let next_sqrt_price = get_sqrt_ratio_at_tick(next_tick)?;
+        let target = if zero_for_one { next_sqrt_price.max(limit) } else { next_sqrt_price.min(limit) };
+
+        let step = compute_swap_step(sqrt_price, target, liquidity, remaining, zero_for_one, 0)?;
+        amount0 = amount0
+            .checked_add(step.amount0)
+            .ok_or_else(|| "amount0 overflows i128".to_string())?;
+        amount1 = amount1
+            .checked_add(step.amount1)
+            .ok_or_else(|| "amount1 overflows i128".to_string())?;
+        let consumed = if zero_for_one { step.amount0 } else { step.amount1 }.unsigned_abs();
+
+        if consumed == 0 && liquidity == 0 {
+            break;
+        }
+
+        sqrt_price = step.sqrt_price_next_x128;
+        current_tick = get_tick_at_sqrt_ratio(sqrt_price)?;
+        if (zero_for_one && sqrt_price <= limit) || (!zero_for_one && sqrt_price >= limit) {
+            break;
+        }
+
+        // The contract treats landing within 2^128 / 10^6 of the tick's price as reaching it
+        let price_diff = if sqrt_price > next_sqrt_price { sqrt_price - next_sqrt_price } else { next_sqrt_price - sqrt_price };
+        if price_diff > min_diff {
+            // Partial step: the input ran out before the next tick
+            break;
+        }
+
+        current_tick = next_tick;
+        sqrt_price = next_sqrt_price;
+        ticks_crossed += 1;
+
+        // Crossing down removes |liquidity_net|, crossing up adds it only when positive
+        let liquidity_net = ticks.get(next_tick).map_or(0, |info| info.liquidity_net);
+        liquidity = if zero_for_one {
+            liquidity.saturating_sub(liquidity_net.unsigned_abs())
+        } else {
+            liquidity.saturating_add(liquidity_net.max(0).unsigned_abs())
+        };
+        remaining = remaining.saturating_sub(consumed);
+    }
+
+    Ok(SwapQuote {
+        amount0,
+        amount1,
+        end: PoolPrice { sqrt_price_x128: sqrt_price, tick: current_tick, liquidity },
+        sqrt_price_limit_x128: limit,
+        ticks_crossed,
+        steps,
+    })
+}
+
+/// Initialized ticks crossed while the price moves from the pool's `current_tick` to
+/// `sqrt_price_end_x128`, in crossing order
+/// `current_tick` is the pool's stored tick, not one derived from its price: after a swap that
+/// ends on a tick boundary going down, the pool sits one tick below it. Like the swap loop's
+/// search, the tick the pool is on is skipped downwards. A swap ending exactly on a tick's price
+/// is not charged for it, since a limit there stops before crossing
+pub fn crossed_ticks(
+    ticks: &TickMap,
+    current_tick: i32,
+    sqrt_price_end_x128: U256,
+    zero_for_one: bool,
+) -> Result<Vec<i32>, String> {
+    let mut crossed = Vec::new();
+    if zero_for_one {
+        for (tick, _) in ticks.ticks().rev().filter(|(tick, _)| *tick < current_tick) {
+            if get_sqrt_ratio_at_tick(tick)? <= sqrt_price_end_x128 {
+                break;
+            }
+            crossed.push(tick);
+        }
+    } else {
+        for (tick, _) in ticks.ticks().filter(|(tick, _)| *tick > current_tick) {
+            if get_sqrt_ratio_at_tick(tick)? >= sqrt_price_end_x128 {
+                break;
+            }
+            crossed.push(tick);
+        }
+    }
+    Ok(crossed)
+}
+
+/// Price limits that split a swap crossing `crossed` into sub-swaps of at most `budget` ticks each
+/// Every sub-swap but the last stops on the price of the first tick past its budget; an empty
+/// result means the swap already fits
+pub fn tick_budget_limits(crossed: &[i32], budget: u32) -> Result<Vec<U256>, String> {
+    if budget == 0 {
+        return Err("Tick budget must be positive".to_string());
+    }
+    crossed
+        .iter()
+        .skip(budget as usize)
+        .step_by(budget as usize)
+        .map(|tick| get_sqrt_ratio_at_tick(*tick))
+        .collect()
+}
+
+/// Parse a u256 given as decimal, 0x-hex or "low,high"
+pub fn parse_u256(value: &str) -> Result<U256, String> {
+    let value = value.trim();
+    if let Some((low, high)) = value.split_once(',') {
+        let low = parse_u256(low)?;
+        let high = parse_u256(high)?;
+        if low > U256::from(u128::MAX) || high > U256::from(u128::MAX) {
+            return Err(format!("Invalid u256 '{}': limbs must fit in u128", value));
+        }
+        return Ok(low | (high << 128));
+    }
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(value).ok(),
+    };
+    parsed.ok_or_else(|| format!("Invalid u256 '{}'", value))
+}
+
+/// A u256 request value: a `parse_u256` string or a JSON number
+fn u256_from_json(value: &serde_json::Value) -> Result<U256, String> {
+    match value {
+        serde_json::Value::String(value) => parse_u256(value),
+        serde_json::Value::Number(number) => number.as_u64().map(U256::from).ok_or_else(|| format!("Invalid u256 {}", number)),
+        other => Err(format!("Invalid u256 {}", other)),
+    }
+}
+
+/// Serde adapter for optional u256 request fields, given as a `parse_u256` string or a number;
+/// null is None
+pub fn deserialize_optional_u256<'de, D>(deserializer: D) -> Result<Option<U256>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => u256_from_json(&value).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+pub fn u256_from_biguint(value: &BigUint) -> Result<U256, String> {
+    let bytes = value.to_bytes_be();
+    if bytes.len() > 32 {
+        return Err(format!("{} does not fit in u256", value));
+    }
+    Ok(U256::from_big_endian(&bytes))
+}
+
+pub fn biguint_from_u256(value: U256) -> BigUint {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    BigUint::from_bytes_be(&bytes)
+}
+
+fn ordered_range(a: U256, b: U256) -> Result<(U256, U256), String> {
+    let (lower, upper) = if a < b { (a, b) } else { (b, a) };
+    if lower >= upper {
+        return Err("Invalid price range".to_string());
+    }
+    Ok((lower, upper))
+}
+
+fn mul(a: U256, b: U256) -> Result<U256, String> {
+    a.checked_mul(b).ok_or_else(|| format!("u256 overflow in {} * {}", a, b))
+}
+
+fn add(a: U256, b: U256) -> Result<U256, String> {
+    a.checked_add(b).ok_or_else(|| format!("u256 overflow in {} + {}", a, b))
+}
+
+fn to_u128(value: U256) -> Result<u128, String> {
+    u128::try_from(value).map_err(|_| format!("{} does not fit in u128", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_sqrt_ratio_at_tick_vectors() {
+        // Asserted by test_sqrt_ratio_at_tick_vectors in zylith/tests/test_clmm.cairo: both sides
+        // of the 100 and 1000 boundaries where the denominator goes 20000 -> 2000 -> 200, and
+        // the MIN_SQRT_RATIO clamp below -1000
+        let vectors = [
+            (0, "340282366920938463463374607431768211456"),
+            (1, "340299381039284510386547776162139799866"),
+            (60, "341303214021701278853764731254063516090"),
+            (100, "341983778755543155780691480468927052513"),
+            (101, "357466626450445855868275025107072506134"),
+            (600, "442367076997220002502386989661298674892"),
+            (1000, "510423550381407695195061911147652317184"),
+            (1001, "2043395613360235473097564517627768109793"),
+            (887272, "1509955363680295500233839948033430990786396"),
+            (-1, "340265352802592416540201438701396623046"),
+            (-60, "339261519820175648072984483609472906822"),
+            (-100, "338580955086333771146057734394609370399"),
+            (-101, "323098107391431071058474189756463916778"),
+            (-600, "238197656844656924424362225202237748020"),
+            (-1000, "170141183460469231731687303715884105728"),
+            (-1001, "18446744073709551616"),
+            (-887272, "18446744073709551616"),
+        ];
+        for (tick, expected) in vectors {
+            assert_eq!(get_sqrt_ratio_at_tick(tick).unwrap(), u(expected), "tick {}", tick);
+        }
+        assert!(get_sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn test_tick_at_sqrt_ratio_vectors() {
+        // test_tick_at_sqrt_ratio_vectors in zylith/tests/test_clmm.cairo
+        let vectors = [
+            ("340282366920938463463374607431768211456", 0),
+            ("341303214021701278853764731254063516090", 60),
+            ("341303214021701278853764731254063516089", 59),
+            ("339261519820175648072984483609472906822", -60),
+            ("339261519820175648072984483609472906823", -60),
+            ("357466626450445855868275025107072506134", 101),
+            // new_sqrt_price_x128 from test_swap_with_frontend_values.cairo
+            ("340248338684246369617028269971025034634", -3),
+        ];
+        for (sqrt_price, expected) in vectors {
+            assert_eq!(get_tick_at_sqrt_ratio(u(sqrt_price)).unwrap(), expected, "{}", sqrt_price);
+        }
+        assert_eq!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO).unwrap(), -1001);
+        assert_eq!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO).unwrap(), MAX_TICK);
+        assert!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO - 1).is_err());
+    }
+
+    #[test]
+    fn test_liquidity_amounts() {
+        let lower = get_sqrt_ratio_at_tick(-60).unwrap();
+        let upper = get_sqrt_ratio_at_tick(60).unwrap();
+        assert_eq!(get_amount0_for_liquidity(lower, upper, 333333333).unwrap(), 2000017);
+        assert_eq!(get_amount1_for_liquidity(lower, upper, 333333333).unwrap(), 1999999);
+        assert_eq!(get_amount1_for_liquidity(upper, upper, 333333333).unwrap(), 0);
+        assert!(get_amount0_for_liquidity(upper, upper, 1).is_err());
+    }
+
+    #[test]
+    fn test_private_liquidity_amounts() {
+        // Private mints charge what mint does for the same liquidity
+        assert_eq!(get_private_mint_amounts(0, Q128, -60, 60, 333333333).unwrap(), (997008, 999999));
+        assert_eq!(get_private_mint_amounts(0, Q128, 120, 240, 166666666).unwrap(), (0, 9999999));
+        assert_eq!(get_private_mint_amounts(0, Q128, -120, -60, 16441754).unwrap(), (999999, 0));
+
+        // Burns price the whole range even when the current tick is inside it
+        assert_eq!(get_private_burn_amounts(-60, 60, 333333333).unwrap(), (2000017, 1999999));
+        assert!(get_private_burn_amounts(60, -60, 1).is_err());
+        assert!(get_private_mint_amounts(0, Q128, -50, 60, 1).is_err());
+    }
+
+    #[test]
+    fn test_swap_step_vectors() {
+        let fee = 3000;
+        let lower = get_sqrt_ratio_at_tick(-600).unwrap();
+        let upper = get_sqrt_ratio_at_tick(600).unwrap();
+
+        // test_swap_basic: 100 token0 against 33333 liquidity is a partial step and,
+        // with the contract's unscaled price delta, leaves the price where it was
+        let step = compute_swap_step(Q128, lower, 33333, 100, true, fee).unwrap();
+        assert_eq!(step, SwapStep { sqrt_price_next_x128: Q128, amount0: -100, amount1: 100, fee_amount0: 0, fee_amount1: 0 });
+
+        // test_swap_reverse_direction
+        let step = compute_swap_step(Q128, upper, 33333, 100, false, fee).unwrap();
+        assert_eq!((step.amount0, step.amount1), (100, -100));
+
+        // Enough input to reach the next initialized tick
+        let step = compute_swap_step(Q128, lower, 33333, 1_000_000_000, true, fee).unwrap();
+        assert_eq!(step, SwapStep { sqrt_price_next_x128: lower, amount0: -9999, amount1: 9999, fee_amount0: 0, fee_amount1: 29 });
+        let step = compute_swap_step(Q128, upper, 33333, 1_000_000_000, false, fee).unwrap();
+        assert_eq!(step, SwapStep { sqrt_price_next_x128: upper, amount0: 9999, amount1: -9999, fee_amount0: 29, fee_amount1: 0 });
+
+        // No liquidity: the step jumps to the target for free
+        let step = compute_swap_step(Q128, lower, 0, 100, true, fee).unwrap();
+        assert_eq!((step.sqrt_price_next_x128, step.amount0, step.amount1), (lower, 0, 0));
+    }
+
+    #[test]
+    fn test_simulate_swap_against_test_clmm_pool() {
+        // test_swap_basic pool: mint(-600, 600, 10000) at Q128 credits 33333 liquidity
+        let mut ticks = TickMap::new();
+        ticks.update_position(-600, 600, 33333);
+        let start = PoolPrice { sqrt_price_x128: Q128, tick: 0, liquidity: 33333 };
+        let lower = get_sqrt_ratio_at_tick(-600).unwrap();
+
+        // test_swap_basic: a single partial step
+        let quote = simulate_swap(start, &ticks, true, 100, MIN_SQRT_RATIO).unwrap();
+        assert_eq!((quote.amount0, quote.amount1), (-100, 100));
+        assert_eq!(quote.end, start);
+        assert_eq!((quote.ticks_crossed, quote.steps), (0, 1));
+
+        // Crossing -600 going down removes the position's liquidity, then the empty
+        // step ends the loop
+        let quote = simulate_swap(start, &ticks, true, 1_000_000_000, MIN_SQRT_RATIO).unwrap();
+        assert_eq!((quote.amount0, quote.amount1), (-9999, 9999));
+        assert_eq!(quote.end, PoolPrice { sqrt_price_x128: lower, tick: -600, liquidity: 0 });
+        assert_eq!((quote.ticks_crossed, quote.steps), (1, 2));
+
+        // Going up, a negative liquidity_net is not removed on-chain, so the swap runs to MAX_TICK
+        let quote = simulate_swap(start, &ticks, false, 1_000_000_000, MAX_SQRT_RATIO).unwrap();
+        assert_eq!((quote.amount0, quote.amount1), (147877186, -147877186));
+        assert_eq!(quote.end.tick, MAX_TICK);
+        assert_eq!(quote.end.liquidity, 33333);
+        assert_eq!((quote.ticks_crossed, quote.steps), (2, 3));
+
+        // test_swap_with_slippage_protection: stops at a limit 10% below the price
+        let limit = Q128 - Q128 / 10;
+        let quote = simulate_swap(start, &ticks, true, 1_000_000_000, limit).unwrap();
+        assert_eq!((quote.amount0, quote.amount1), (-3333, 3333));
+        assert_eq!(quote.end, PoolPrice { sqrt_price_x128: limit, tick: -200, liquidity: 33333 });
+        assert_eq!(quote.ticks_crossed, 0);
+    }
+
+    #[test]
+    fn test_crossed_ticks_and_budget_limits() {
+        let mut ticks = TickMap::new();
+        ticks.update_position(-600, 600, 1000);
+        ticks.update_position(-120, 120, 1000);
+        ticks.update_position(-60, 60, 1000);
+
+        let crossed = crossed_ticks(&ticks, 0, MIN_SQRT_RATIO, true).unwrap();
+        assert_eq!(crossed, vec![-60, -120, -600]);
+        // Ending on a tick's price does not charge for it
+        let end = get_sqrt_ratio_at_tick(-120).unwrap();
+        assert_eq!(crossed_ticks(&ticks, 0, end, true).unwrap(), vec![-60]);
+        // Starting on an initialized tick skips it downwards, not upwards
+        assert_eq!(crossed_ticks(&ticks, 60, Q128, true).unwrap(), Vec::<i32>::new());
+        assert_eq!(crossed_ticks(&ticks, 60, MAX_SQRT_RATIO, false).unwrap(), vec![120, 600]);
+        // A pool left just below tick 60 by a swap down, at tick 60's price, crosses it going up
+        assert_eq!(crossed_ticks(&ticks, 59, MAX_SQRT_RATIO, false).unwrap(), vec![60, 120, 600]);
+
+        let at = |tick| get_sqrt_ratio_at_tick(tick).unwrap();
+        assert_eq!(tick_budget_limits(&crossed, 1).unwrap(), vec![at(-120), at(-600)]);
+        assert_eq!(tick_budget_limits(&crossed, 2).unwrap(), vec![at(-600)]);
+        assert!(tick_budget_limits(&crossed, 3).unwrap().is_empty());
+        assert!(tick_budget_limits(&crossed, 0).is_err());
+
+        // A sub-swap stopped on a suggested limit fits the budget
+        let first = crossed_ticks(&ticks, 0, at(-120), true).unwrap();
+        assert_eq!(first.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_u256_formats() {
+        assert_eq!(parse_u256("340282366920938463463374607431768211456").unwrap(), Q128);
+        assert_eq!(parse_u256("0x100000000000000000000000000000000").unwrap(), Q128);
+        assert_eq!(parse_u256("0,1").unwrap(), Q128);
+        assert!(parse_u256("1,0x1ffffffffffffffffffffffffffffffff").is_err());
+        assert!(parse_u256("price").is_err());
+        assert_eq!(u256_from_biguint(&biguint_from_u256(MAX_SQRT_RATIO)).unwrap(), MAX_SQRT_RATIO);
+
+        // Decimal and hex round-trip over the full range; 2^256 does not fit
+        for value in [U256::zero(), Q128 + 1, MAX_SQRT_RATIO, U256::MAX] {
+            assert_eq!(parse_u256(&value.to_string()).unwrap(), value);
+            assert_eq!(parse_u256(&format!("{:#x}", value)).unwrap(), value);
+        }
+        assert!(parse_u256("115792089237316195423570985008687907853269984665640564039457584007913129639936").is_err());
+        assert!(parse_u256("0x10000000000000000000000000000000000000000000000000000000000000000").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_u256_fields() {
+        #[derive(serde::Deserialize)]
+        struct Request {
+            #[serde(default, deserialize_with = "deserialize_optional_u256")]
+            price: Option<U256>,
+        }
+        let parse = |json: &str| serde_json::from_str::<Request>(json).map(|r| r.price);
+
+        assert_eq!(parse(r#"{"price": "0,1"}"#).unwrap(), Some(Q128));
+        assert_eq!(parse(r#"{"price": "0x10"}"#).unwrap(), Some(U256::from(16)));
+        assert_eq!(parse(r#"{"price": 0}"#).unwrap(), Some(U256::zero()));
+        assert_eq!(parse(r#"{"price": null}"#).unwrap(), None);
+        assert_eq!(parse(r#"{}"#).unwrap(), None);
+        assert!(parse(r#"{"price": "1.5"}"#).is_err());
+        assert!(parse(r#"{"price": -1}"#).is_err());
+    }
+}
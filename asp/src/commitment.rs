@@ -4,7 +4,6 @@ use light_poseidon::{Poseidon, PoseidonHasher};
 use num_bigint::BigUint;
 use num_traits::Num;
 use std::str::FromStr;
-use serde_json;
 
 /// Mask used in Cairo contract to ensure BN254 hash fits in felt252
 /// 0x3ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff (250 bits)
@@ -217,7 +216,10 @@ mod tests {
         
         let commitment = generate_commitment(secret, nullifier, amount).unwrap();
         assert!(commitment.starts_with("0x"));
-        assert_eq!(commitment.len(), 66); // 0x + 64 hex chars
+        // Unpadded hex of a value masked to 250 bits: at most 63 hex chars
+        let value = BigUint::from_str_radix(&commitment[2..], 16).unwrap();
+        assert_eq!(commitment, format!("0x{:x}", value));
+        assert!(value.bits() <= 250);
     }
 
     #[test]
@@ -0,0 +1,461 @@
+use serde::{Deserialize, Serialize};
+use starknet::core::types::FieldElement;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use url::Url;
+
+/// Built-in network profiles (devnet, sepolia, mainnet)
+const DEFAULT_CONFIG: &str = include_str!("../config/networks.toml");
+
+/// Local config file picked up when ASP_CONFIG is not set
+const LOCAL_CONFIG_FILE: &str = "asp.toml";
+
+/// ASP configuration: the selected network profile plus server settings
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Name of the selected profile
+    pub network: String,
+    pub profile: NetworkProfile,
+    pub port: u16,
+    pub database_url: String,
+    /// Blocks a deposit must be buried under before it counts as final
+    pub confirmation_depth: u64,
+    /// Initialized ticks a single swap proof may cross
+    pub swap_tick_budget: u32,
+    /// Circuits directory holding build/ (wasm, zkeys, vkeys, garaga), relative to the working directory
+    pub circuits_dir: String,
+    /// garaga CLI that builds the verifiers' full_proof_with_hints calldata
+    pub garaga_bin: String,
+    /// Proof jobs allowed to run at once
+    pub proof_workers: usize,
+    /// How long a finished proof job's result is kept, in seconds
+    pub proof_job_ttl_secs: u64,
+    /// Reverse proxies whose X-Forwarded-For is trusted for the client IP; empty when the
+    /// ASP is exposed directly
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// Everything that differs between networks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    /// RPC endpoints, the first one is used by default
+    pub rpc_urls: Vec<String>,
+    pub zylith_address: String,
+    /// Block the Zylith contract was deployed in
+    pub deployment_block: u64,
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+    #[serde(default)]
+    pub verifiers: VerifierAddresses,
+    /// Block explorer base URL, used for transaction links
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+    /// Swap fee the pool was initialized with, in millionths (3000 = 0.3%)
+    #[serde(default = "default_pool_fee")]
+    pub pool_fee: u32,
+    /// Whether the deployed contract's private_collect reads the LP circuit's 7 public inputs.
+    /// Contracts deployed before that fix read 6 and revert every collect the ASP prepares, so
+    /// collects stay disabled until the contract is redeployed and this is set
+    #[serde(default)]
+    pub private_collect_lp_layout: bool,
+}
+
+fn default_pool_fee() -> u32 {
+    3000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenConfig {
+    pub symbol: String,
+    pub address: String,
+    pub decimals: u8,
+}
+
+/// Garaga verifier contracts, one per circuit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerifierAddresses {
+    pub membership: Option<String>,
+    pub swap: Option<String>,
+    pub withdraw: Option<String>,
+    pub lp: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawConfig {
+    network: String,
+    port: u16,
+    database_url: String,
+    confirmation_depth: u64,
+    swap_tick_budget: u32,
+    circuits_dir: String,
+    garaga_bin: String,
+    proof_workers: usize,
+    proof_job_ttl_secs: u64,
+    trusted_proxies: Vec<String>,
+    networks: BTreeMap<String, NetworkProfile>,
+}
+
+impl Config {
+    /// Load the built-in profiles, overlay ASP_CONFIG (or ./asp.toml) and env overrides,
+    /// then validate the selected profile
+    pub fn load() -> Result<Self, String> {
+        let file = match std::env::var("ASP_CONFIG") {
+            Ok(path) => Some(
+                std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read config file '{}': {}", path, e))?,
+            ),
+            Err(_) => std::fs::read_to_string(LOCAL_CONFIG_FILE).ok(),
+        };
+        Self::load_from(file.as_deref(), |key| std::env::var(key).ok())
+    }
+
+    /// Same as `load`, with the config file contents and env lookup passed in
+    pub fn load_from(
+        file: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        let mut value: toml::Value = toml::from_str(DEFAULT_CONFIG)
+            .map_err(|e| format!("Invalid built-in config: {}", e))?;
+        if let Some(file) = file {
+            let overlay: toml::Value = toml::from_str(file)
+                .map_err(|e| format!("Invalid config file: {}", e))?;
+            merge(&mut value, overlay);
+        }
+        let mut raw: RawConfig = value
+            .try_into()
+            .map_err(|e| format!("Invalid config: {}", e))?;
+
+        let network = env("ZYLITH_NETWORK").unwrap_or(raw.network);
+        let mut profile = raw.networks.remove(&network).ok_or_else(|| {
+            format!(
+                "Unknown network '{}' (available: {})",
+                network,
+                raw.networks.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })?;
+
+        // Env overrides keep the variable names the ASP has always used
+        if let Some(rpc_url) = env("RPC_URL") {
+            profile.rpc_urls.retain(|url| url != &rpc_url);
+            profile.rpc_urls.insert(0, rpc_url);
+        }
+        if let Some(address) = env("CONTRACT_ADDRESS") {
+            profile.zylith_address = address;
+        }
+        if let Some(block) = env("DEPLOYMENT_BLOCK") {
+            profile.deployment_block = parse_env("DEPLOYMENT_BLOCK", &block)?;
+        }
+        if let Some(fee) = env("POOL_FEE") {
+            profile.pool_fee = parse_env("POOL_FEE", &fee)?;
+        }
+        if let Some(layout) = env("PRIVATE_COLLECT_LP_LAYOUT") {
+            profile.private_collect_lp_layout = parse_env("PRIVATE_COLLECT_LP_LAYOUT", &layout)?;
+        }
+        let port = match env("PORT") {
+            Some(port) => parse_env("PORT", &port)?,
+            None => raw.port,
+        };
+        let confirmation_depth = match env("CONFIRMATION_DEPTH") {
+            Some(depth) => parse_env("CONFIRMATION_DEPTH", &depth)?,
+            None => raw.confirmation_depth,
+        };
+        let swap_tick_budget = match env("SWAP_TICK_BUDGET") {
+            Some(budget) => parse_env("SWAP_TICK_BUDGET", &budget)?,
+            None => raw.swap_tick_budget,
+        };
+        let proof_workers = match env("PROOF_WORKERS") {
+            Some(workers) => parse_env("PROOF_WORKERS", &workers)?,
+            None => raw.proof_workers,
+        };
+        let proof_job_ttl_secs = match env("PROOF_JOB_TTL") {
+            Some(ttl) => parse_env("PROOF_JOB_TTL", &ttl)?,
+            None => raw.proof_job_ttl_secs,
+        };
+        let trusted_proxies = match env("TRUSTED_PROXIES") {
+            Some(proxies) => proxies
+                .split(',')
+                .map(str::trim)
+                .filter(|proxy| !proxy.is_empty())
+                .map(|proxy| parse_env("TRUSTED_PROXIES", proxy))
+                .collect::<Result<_, _>>()?,
+            None => raw
+                .trusted_proxies
+                .iter()
+                .map(|proxy| parse_env("trusted_proxies", proxy))
+                .collect::<Result<_, _>>()?,
+        };
+
+        let config = Self {
+            network,
+            profile,
+            port,
+            database_url: env("DATABASE_URL").unwrap_or(raw.database_url),
+            confirmation_depth,
+            swap_tick_budget,
+            circuits_dir: env("CIRCUITS_DIR").unwrap_or(raw.circuits_dir),
+            garaga_bin: env("GARAGA_BIN").unwrap_or(raw.garaga_bin),
+            proof_workers,
+            proof_job_ttl_secs,
+            trusted_proxies,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Primary RPC endpoint
+    pub fn rpc_url(&self) -> &str {
+        &self.profile.rpc_urls[0]
+    }
+
+    /// The pool's token pair: the first two tokens of the profile, as initialize_pool sets it up
+    pub fn pool_tokens(&self) -> Option<(&TokenConfig, &TokenConfig)> {
+        match self.profile.tokens.as_slice() {
+            [token0, token1, ..] => Some((token0, token1)),
+            _ => None,
+        }
+    }
+
+    /// The client a request comes from: the peer address, unless the peer is a trusted proxy,
+    /// in which case X-Forwarded-For is walked from the right and the first hop that is not a
+    /// trusted proxy is the client. Hops left of it are client-supplied and ignored
+    pub fn client_ip(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        let mut client = peer;
+        if !self.trusted_proxies.contains(&peer) {
+            return client;
+        }
+        for hop in forwarded_for.unwrap_or("").rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(ip) => {
+                    client = ip;
+                    if !self.trusted_proxies.contains(&ip) {
+                        break;
+                    }
+                }
+                // A malformed hop cannot be attributed; keep the last proxy that forwarded it
+                Err(_) => break,
+            }
+        }
+        client
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let network = &self.network;
+
+        if self.profile.rpc_urls.is_empty() {
+            return Err(format!("Network '{}' has no RPC URL", network));
+        }
+        for rpc_url in &self.profile.rpc_urls {
+            let url = Url::parse(rpc_url)
+                .map_err(|e| format!("Invalid RPC URL '{}' for network '{}': {}", rpc_url, network, e))?;
+            if url.scheme() != "http" && url.scheme() != "https" {
+                return Err(format!("RPC URL '{}' for network '{}' must be http(s)", rpc_url, network));
+            }
+        }
+
+        if self.profile.zylith_address.is_empty() {
+            return Err(format!(
+                "Zylith address is not set for network '{}' (set CONTRACT_ADDRESS)",
+                network
+            ));
+        }
+        validate_address("Zylith address", &self.profile.zylith_address)?;
+
+        let mut symbols = Vec::new();
+        for token in &self.profile.tokens {
+            validate_address(&format!("Token {} address", token.symbol), &token.address)?;
+            let symbol = token.symbol.to_uppercase();
+            if symbols.contains(&symbol) {
+                return Err(format!("Token {} is listed twice for network '{}'", token.symbol, network));
+            }
+            symbols.push(symbol);
+        }
+
+        let verifiers = &self.profile.verifiers;
+        for (name, address) in [
+            ("Membership verifier", &verifiers.membership),
+            ("Swap verifier", &verifiers.swap),
+            ("Withdraw verifier", &verifiers.withdraw),
+            ("LP verifier", &verifiers.lp),
+        ] {
+            if let Some(address) = address {
+                validate_address(name, address)?;
+            }
+        }
+
+        if let Some(explorer_url) = &self.profile.explorer_url {
+            Url::parse(explorer_url)
+                .map_err(|e| format!("Invalid explorer URL '{}' for network '{}': {}", explorer_url, network, e))?;
+        }
+
+        if self.profile.pool_fee >= 1_000_000 {
+            return Err(format!("POOL_FEE {} must be below 1000000 (100%)", self.profile.pool_fee));
+        }
+
+        if self.port == 0 {
+            return Err("PORT must not be 0".to_string());
+        }
+        if self.swap_tick_budget == 0 {
+            return Err("SWAP_TICK_BUDGET must be at least 1".to_string());
+        }
+        if self.database_url.is_empty() {
+            return Err("DATABASE_URL must not be empty".to_string());
+        }
+        if self.circuits_dir.is_empty() {
+            return Err("CIRCUITS_DIR must not be empty".to_string());
+        }
+        if self.garaga_bin.is_empty() {
+            return Err("GARAGA_BIN must not be empty".to_string());
+        }
+        if self.proof_workers == 0 {
+            return Err("PROOF_WORKERS must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Recursively overlay `overlay` onto `base`; tables merge, everything else is replaced
+fn merge(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn parse_env<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse::<T>()
+        .map_err(|e| format!("Invalid {} '{}': {}", key, value, e))
+}
+
+fn validate_address(name: &str, address: &str) -> Result<(), String> {
+    if !address.starts_with("0x") {
+        return Err(format!("{} '{}' must be a 0x-prefixed hex felt", name, address));
+    }
+    let felt = FieldElement::from_hex_be(address)
+        .map_err(|e| format!("{} '{}' is not a valid felt: {}", name, address, e))?;
+    if felt == FieldElement::ZERO {
+        return Err(format!("{} must not be zero", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_default_profile_is_sepolia() {
+        let config = Config::load_from(None, env_from(&[])).unwrap();
+        assert_eq!(config.network, "sepolia");
+        assert_eq!(config.profile.deployment_block, 4438440);
+        assert_eq!(config.rpc_url(), "https://api.cartridge.gg/x/starknet/sepolia");
+        assert_eq!(config.profile.tokens[1].symbol, "USDC");
+        assert!(config.profile.verifiers.swap.is_some());
+        assert_eq!(config.profile.pool_fee, 3000);
+        // The sepolia contract predates the private_collect fix
+        assert!(!config.profile.private_collect_lp_layout);
+    }
+
+    #[test]
+    fn test_file_and_env_overrides() {
+        let file = r#"
+            [networks.devnet]
+            deployment_block = 12
+        "#;
+        let config = Config::load_from(
+            Some(file),
+            env_from(&[
+                ("ZYLITH_NETWORK", "devnet"),
+                ("CONTRACT_ADDRESS", "0x1234"),
+                ("RPC_URL", "http://127.0.0.1:5050"),
+                ("PORT", "4000"),
+                ("CIRCUITS_DIR", "/opt/zylith/circuits"),
+                ("GARAGA_BIN", "/opt/garaga/bin/garaga"),
+                ("PRIVATE_COLLECT_LP_LAYOUT", "true"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.network, "devnet");
+        assert_eq!(config.profile.deployment_block, 12);
+        assert_eq!(config.profile.zylith_address, "0x1234");
+        assert_eq!(config.rpc_url(), "http://127.0.0.1:5050");
+        assert_eq!(config.port, 4000);
+        assert_eq!(config.circuits_dir, "/opt/zylith/circuits");
+        assert_eq!(config.garaga_bin, "/opt/garaga/bin/garaga");
+        assert!(config.profile.private_collect_lp_layout);
+        // Tokens from the built-in profile survive a partial overlay
+        assert_eq!(config.profile.tokens[0].symbol, "ETH");
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let err = Config::load_from(None, env_from(&[("ZYLITH_NETWORK", "testnet")])).unwrap_err();
+        assert!(err.contains("Unknown network"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("ZYLITH_NETWORK", "mainnet")])).unwrap_err();
+        assert!(err.contains("CONTRACT_ADDRESS"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("CONTRACT_ADDRESS", "not-an-address")])).unwrap_err();
+        assert!(err.contains("Zylith address"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("DEPLOYMENT_BLOCK", "soon")])).unwrap_err();
+        assert!(err.contains("DEPLOYMENT_BLOCK"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("SWAP_TICK_BUDGET", "0")])).unwrap_err();
+        assert!(err.contains("SWAP_TICK_BUDGET"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("PROOF_WORKERS", "0")])).unwrap_err();
+        assert!(err.contains("PROOF_WORKERS"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("POOL_FEE", "1000000")])).unwrap_err();
+        assert!(err.contains("POOL_FEE"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("TRUSTED_PROXIES", "10.0.0.1,proxy")])).unwrap_err();
+        assert!(err.contains("TRUSTED_PROXIES"), "{}", err);
+
+        let err = Config::load_from(None, env_from(&[("PRIVATE_COLLECT_LP_LAYOUT", "yes")])).unwrap_err();
+        assert!(err.contains("PRIVATE_COLLECT_LP_LAYOUT"), "{}", err);
+    }
+
+    #[test]
+    fn test_client_ip_behind_trusted_proxies() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+        // Exposed directly: X-Forwarded-For is whatever the client wants, so it is ignored
+        let direct = Config::load_from(None, env_from(&[])).unwrap();
+        assert!(direct.trusted_proxies.is_empty());
+        assert_eq!(direct.client_ip(ip("203.0.113.7"), Some("198.51.100.1")), ip("203.0.113.7"));
+
+        let config = Config::load_from(None, env_from(&[("TRUSTED_PROXIES", "10.0.0.1, 10.0.0.2")])).unwrap();
+        assert_eq!(config.trusted_proxies, vec![ip("10.0.0.1"), ip("10.0.0.2")]);
+        // The rightmost hop not added by a trusted proxy is the client; spoofed hops left of it are ignored
+        assert_eq!(config.client_ip(ip("10.0.0.1"), Some("1.2.3.4, 198.51.100.1")), ip("198.51.100.1"));
+        assert_eq!(config.client_ip(ip("10.0.0.1"), Some("198.51.100.1, 10.0.0.2")), ip("198.51.100.1"));
+        // Untrusted peers are the client whatever they forward
+        assert_eq!(config.client_ip(ip("203.0.113.7"), Some("198.51.100.1")), ip("203.0.113.7"));
+        // No usable header: the proxy itself is all that is known
+        assert_eq!(config.client_ip(ip("10.0.0.1"), None), ip("10.0.0.1"));
+        assert_eq!(config.client_ip(ip("10.0.0.1"), Some("garbage")), ip("10.0.0.1"));
+    }
+}
@@ -0,0 +1,624 @@
+use crate::abi::{get_zylith_abi, AbiEntry};
+use num_bigint::BigUint;
+use once_cell::sync::Lazy;
+use starknet::core::types::FieldElement;
+use starknet::core::utils::get_selector_from_name;
+use std::collections::HashMap;
+
+/// Root event enum of the Zylith contract in the ABI
+const ZYLITH_EVENT: &str = "zylith::zylith::Zylith::Event";
+
+/// Every event the Zylith contract emits, mirroring the Cairo `Event` enum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZylithEvent {
+    Pool(PoolEvent),
+    Privacy(PrivacyEvent),
+    Initialized(Initialized),
+    ProofRejected(ProofRejected),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolEvent {
+    Initialize(PoolInitialize),
+    Swap(Swap),
+    Mint(Mint),
+    Burn(Burn),
+    Collect(Collect),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivacyEvent {
+    Deposit(Deposit),
+    NullifierSpent(NullifierSpent),
+}
+
+/// `PoolEvent::Initialize` (named apart from the contract-level `Initialized`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolInitialize {
+    pub sqrt_price_x128: BigUint,
+    pub tick: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Swap {
+    pub sender: FieldElement,
+    pub zero_for_one: bool,
+    pub amount0: i128,
+    pub amount1: i128,
+    pub sqrt_price_x128: BigUint,
+    pub liquidity: u128,
+    pub tick: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mint {
+    pub sender: FieldElement,
+    pub owner: FieldElement,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub amount: u128,
+    pub amount0: u128,
+    pub amount1: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Burn {
+    pub owner: FieldElement,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub amount: u128,
+    pub amount0: u128,
+    pub amount1: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collect {
+    pub owner: FieldElement,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub amount0: u128,
+    pub amount1: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deposit {
+    pub commitment: FieldElement,
+    pub leaf_index: u32,
+    pub root: FieldElement,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NullifierSpent {
+    pub nullifier: FieldElement,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Initialized {
+    pub token0: FieldElement,
+    pub token1: FieldElement,
+    pub fee: u128,
+    pub tick_spacing: i32,
+    pub sqrt_price_x128: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofRejected {
+    pub proof_type: FieldElement,
+    pub caller: FieldElement,
+    pub error: FieldElement,
+}
+
+/// A single decoded ABI value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    Felt(FieldElement),
+    Bool(bool),
+    /// u8 through u128
+    UInt(u128),
+    U256(BigUint),
+    /// i8 through i128
+    Int(i128),
+}
+
+/// An event decoded against the ABI without knowing its Rust type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEvent {
+    /// Variant names from the root enum down, e.g. ["PoolEvent", "Swap"]
+    pub path: Vec<String>,
+    /// Members in declaration order
+    pub fields: Vec<(String, AbiValue)>,
+}
+
+enum EventDef {
+    Struct(Vec<Member>),
+    Enum(Vec<Variant>),
+}
+
+struct Member {
+    name: String,
+    type_: String,
+    /// Keys members are read from the event keys, data members from the data
+    is_key: bool,
+}
+
+struct Variant {
+    name: String,
+    selector: FieldElement,
+    type_: String,
+    /// Flat variants don't emit their own selector
+    flat: bool,
+}
+
+/// Decodes raw event keys/data into ABI values by walking the event definitions
+pub struct EventDecoder {
+    defs: HashMap<String, EventDef>,
+    root: String,
+}
+
+static ZYLITH_EVENTS: Lazy<EventDecoder> = Lazy::new(|| {
+    EventDecoder::from_abi(get_zylith_abi(), ZYLITH_EVENT).expect("Invalid events in Zylith ABI")
+});
+
+/// Decode a Zylith contract event into its typed form
+pub fn decode_zylith_event(keys: &[FieldElement], data: &[FieldElement]) -> Result<ZylithEvent, String> {
+    ZylithEvent::from_raw(ZYLITH_EVENTS.decode(keys, data)?)
+}
+
+impl EventDecoder {
+    /// Build a decoder for the event enum `root` from the event entries of `abi`
+    pub fn from_abi(abi: &[AbiEntry], root: &str) -> Result<Self, String> {
+        let mut defs = HashMap::new();
+        for entry in abi {
+            let AbiEntry::Event { name, kind, members, variants } = entry else {
+                continue;
+            };
+            let def = match kind.as_str() {
+                "struct" => EventDef::Struct(
+                    members
+                        .iter()
+                        .map(|m| Member {
+                            name: m.name.clone(),
+                            type_: m.type_.clone(),
+                            is_key: m.kind == "key",
+                        })
+                        .collect(),
+                ),
+                "enum" => EventDef::Enum(
+                    variants
+                        .iter()
+                        .map(|v| {
+                            let type_ = v.type_.clone().ok_or_else(|| {
+                                format!("Event variant {}::{} has no type", name, v.name)
+                            })?;
+                            let selector = get_selector_from_name(&v.name)
+                                .map_err(|e| format!("Invalid event name '{}': {}", v.name, e))?;
+                            Ok(Variant {
+                                name: v.name.clone(),
+                                selector,
+                                type_,
+                                flat: v.kind == "flat",
+                            })
+                        })
+                        .collect::<Result<_, String>>()?,
+                ),
+                other => return Err(format!("Unsupported event kind '{}' for {}", other, name)),
+            };
+            defs.insert(name.clone(), def);
+        }
+
+        if !matches!(defs.get(root), Some(EventDef::Enum(_))) {
+            return Err(format!("Event enum '{}' not found in ABI", root));
+        }
+        Ok(Self { defs, root: root.to_string() })
+    }
+
+    /// Decode one emitted event
+    pub fn decode(&self, keys: &[FieldElement], data: &[FieldElement]) -> Result<RawEvent, String> {
+        let mut keys = Felts::new(keys, "keys");
+        let mut data = Felts::new(data, "data");
+        let mut event = RawEvent { path: Vec::new(), fields: Vec::new() };
+
+        self.decode_def(&self.root, &mut keys, &mut data, &mut event)?;
+
+        if !keys.is_empty() || !data.is_empty() {
+            return Err(format!(
+                "Event {} has {} unread key(s) and {} unread data felt(s)",
+                event.path.join("::"),
+                keys.remaining(),
+                data.remaining()
+            ));
+        }
+        Ok(event)
+    }
+
+    fn decode_def<'a>(
+        &self,
+        name: &str,
+        keys: &mut Felts<'a>,
+        data: &mut Felts<'a>,
+        event: &mut RawEvent,
+    ) -> Result<(), String> {
+        match self.defs.get(name) {
+            Some(EventDef::Enum(variants)) => {
+                let selector = keys.peek()?;
+                if let Some(variant) = variants.iter().find(|v| !v.flat && v.selector == selector) {
+                    keys.next()?;
+                    event.path.push(variant.name.clone());
+                    return self.decode_def(&variant.type_, keys, data, event);
+                }
+                // A flat variant leaves the selector to the enum it wraps
+                for variant in variants.iter().filter(|v| v.flat) {
+                    if self.enum_has_selector(&variant.type_, selector) {
+                        event.path.push(variant.name.clone());
+                        return self.decode_def(&variant.type_, keys, data, event);
+                    }
+                }
+                Err(format!("Unknown selector 0x{:x} for event {}", selector, name))
+            }
+            Some(EventDef::Struct(members)) => {
+                for member in members {
+                    let source = if member.is_key { &mut *keys } else { &mut *data };
+                    let value = decode_value(&member.type_, source)
+                        .map_err(|e| format!("{}.{}: {}", name, member.name, e))?;
+                    event.fields.push((member.name.clone(), value));
+                }
+                Ok(())
+            }
+            None => Err(format!("Event type '{}' not found in ABI", name)),
+        }
+    }
+
+    fn enum_has_selector(&self, name: &str, selector: FieldElement) -> bool {
+        match self.defs.get(name) {
+            Some(EventDef::Enum(variants)) => variants.iter().any(|v| {
+                (!v.flat && v.selector == selector) || (v.flat && self.enum_has_selector(&v.type_, selector))
+            }),
+            _ => false,
+        }
+    }
+}
+
+/// Read one value of Cairo type `type_` from `felts`
+fn decode_value(type_: &str, felts: &mut Felts) -> Result<AbiValue, String> {
+    match type_ {
+        "core::felt252" | "core::starknet::contract_address::ContractAddress" => {
+            Ok(AbiValue::Felt(felts.next()?))
+        }
+        "core::bool" => match felt_to_u128(felts.next()?) {
+            Some(0) => Ok(AbiValue::Bool(false)),
+            Some(1) => Ok(AbiValue::Bool(true)),
+            _ => Err("bool must be 0 or 1".to_string()),
+        },
+        "core::integer::u256" => {
+            let low = felt_to_u128(felts.next()?).ok_or("u256 low limb exceeds u128")?;
+            let high = felt_to_u128(felts.next()?).ok_or("u256 high limb exceeds u128")?;
+            Ok(AbiValue::U256((BigUint::from(high) << 128) + BigUint::from(low)))
+        }
+        _ => {
+            let Some(int) = type_.strip_prefix("core::integer::") else {
+                return Err(format!("Unsupported type '{}'", type_));
+            };
+            let (signed, bits) = match int.split_at(1) {
+                ("u", bits) => (false, bits),
+                ("i", bits) => (true, bits),
+                _ => return Err(format!("Unsupported type '{}'", type_)),
+            };
+            let bits: u32 = bits
+                .parse()
+                .ok()
+                .filter(|b| [8, 16, 32, 64, 128].contains(b))
+                .ok_or_else(|| format!("Unsupported type '{}'", type_))?;
+            let felt = felts.next()?;
+
+            if signed {
+                let value = felt_to_i128(felt).ok_or_else(|| format!("Value 0x{:x} is not an {}", felt, int))?;
+                if bits < 128 {
+                    let bound = 1i128 << (bits - 1);
+                    if value < -bound || value >= bound {
+                        return Err(format!("Value {} out of range for {}", value, int));
+                    }
+                }
+                Ok(AbiValue::Int(value))
+            } else {
+                let value = felt_to_u128(felt).ok_or_else(|| format!("Value 0x{:x} is not a {}", felt, int))?;
+                if bits < 128 && value >> bits != 0 {
+                    return Err(format!("Value {} out of range for {}", value, int));
+                }
+                Ok(AbiValue::UInt(value))
+            }
+        }
+    }
+}
+
+fn felt_to_u128(felt: FieldElement) -> Option<u128> {
+    let bytes = felt.to_bytes_be();
+    if bytes[..16].iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(u128::from_be_bytes(bytes[16..].try_into().unwrap()))
+}
+
+/// Cairo signed integers are felts: negative values wrap around the field prime
+fn felt_to_i128(felt: FieldElement) -> Option<i128> {
+    if let Some(value) = felt_to_u128(felt) {
+        return i128::try_from(value).ok();
+    }
+    let magnitude = felt_to_u128(FieldElement::ZERO - felt)?;
+    if magnitude == 1u128 << 127 {
+        return Some(i128::MIN);
+    }
+    i128::try_from(magnitude).ok().map(|m| -m)
+}
+
+/// Cursor over event keys or data
+struct Felts<'a> {
+    felts: &'a [FieldElement],
+    pos: usize,
+    what: &'static str,
+}
+
+impl<'a> Felts<'a> {
+    fn new(felts: &'a [FieldElement], what: &'static str) -> Self {
+        Self { felts, pos: 0, what }
+    }
+
+    fn peek(&self) -> Result<FieldElement, String> {
+        self.felts
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| format!("Event {} ended early", self.what))
+    }
+
+    fn next(&mut self) -> Result<FieldElement, String> {
+        let felt = self.peek()?;
+        self.pos += 1;
+        Ok(felt)
+    }
+
+    fn remaining(&self) -> usize {
+        self.felts.len() - self.pos
+    }
+
+    fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+}
+
+/// Named access to the fields of a RawEvent
+struct Fields(HashMap<String, AbiValue>);
+
+impl Fields {
+    fn take(&mut self, name: &str) -> Result<AbiValue, String> {
+        self.0.remove(name).ok_or_else(|| format!("Missing event field '{}'", name))
+    }
+
+    fn felt(&mut self, name: &str) -> Result<FieldElement, String> {
+        match self.take(name)? {
+            AbiValue::Felt(value) => Ok(value),
+            other => Err(format!("Field '{}' is not a felt: {:?}", name, other)),
+        }
+    }
+
+    fn bool(&mut self, name: &str) -> Result<bool, String> {
+        match self.take(name)? {
+            AbiValue::Bool(value) => Ok(value),
+            other => Err(format!("Field '{}' is not a bool: {:?}", name, other)),
+        }
+    }
+
+    fn u128(&mut self, name: &str) -> Result<u128, String> {
+        match self.take(name)? {
+            AbiValue::UInt(value) => Ok(value),
+            other => Err(format!("Field '{}' is not an unsigned integer: {:?}", name, other)),
+        }
+    }
+
+    fn u32(&mut self, name: &str) -> Result<u32, String> {
+        let value = self.u128(name)?;
+        u32::try_from(value).map_err(|_| format!("Field '{}' exceeds u32: {}", name, value))
+    }
+
+    fn u256(&mut self, name: &str) -> Result<BigUint, String> {
+        match self.take(name)? {
+            AbiValue::U256(value) => Ok(value),
+            other => Err(format!("Field '{}' is not a u256: {:?}", name, other)),
+        }
+    }
+
+    fn i128(&mut self, name: &str) -> Result<i128, String> {
+        match self.take(name)? {
+            AbiValue::Int(value) => Ok(value),
+            other => Err(format!("Field '{}' is not a signed integer: {:?}", name, other)),
+        }
+    }
+
+    fn i32(&mut self, name: &str) -> Result<i32, String> {
+        let value = self.i128(name)?;
+        i32::try_from(value).map_err(|_| format!("Field '{}' exceeds i32: {}", name, value))
+    }
+}
+
+impl ZylithEvent {
+    /// Map an ABI-decoded event onto its typed variant
+    pub fn from_raw(raw: RawEvent) -> Result<Self, String> {
+        let path: Vec<&str> = raw.path.iter().map(String::as_str).collect();
+        let mut f = Fields(raw.fields.into_iter().collect());
+
+        let event = match path.as_slice() {
+            ["PoolEvent", "Initialize"] => Self::Pool(PoolEvent::Initialize(PoolInitialize {
+                sqrt_price_x128: f.u256("sqrt_price_x128")?,
+                tick: f.i32("tick")?,
+            })),
+            ["PoolEvent", "Swap"] => Self::Pool(PoolEvent::Swap(Swap {
+                sender: f.felt("sender")?,
+                zero_for_one: f.bool("zero_for_one")?,
+                amount0: f.i128("amount0")?,
+                amount1: f.i128("amount1")?,
+                sqrt_price_x128: f.u256("sqrt_price_x128")?,
+                liquidity: f.u128("liquidity")?,
+                tick: f.i32("tick")?,
+            })),
+            ["PoolEvent", "Mint"] => Self::Pool(PoolEvent::Mint(Mint {
+                sender: f.felt("sender")?,
+                owner: f.felt("owner")?,
+                tick_lower: f.i32("tick_lower")?,
+                tick_upper: f.i32("tick_upper")?,
+                amount: f.u128("amount")?,
+                amount0: f.u128("amount0")?,
+                amount1: f.u128("amount1")?,
+            })),
+            ["PoolEvent", "Burn"] => Self::Pool(PoolEvent::Burn(Burn {
+                owner: f.felt("owner")?,
+                tick_lower: f.i32("tick_lower")?,
+                tick_upper: f.i32("tick_upper")?,
+                amount: f.u128("amount")?,
+                amount0: f.u128("amount0")?,
+                amount1: f.u128("amount1")?,
+            })),
+            ["PoolEvent", "Collect"] => Self::Pool(PoolEvent::Collect(Collect {
+                owner: f.felt("owner")?,
+                tick_lower: f.i32("tick_lower")?,
+                tick_upper: f.i32("tick_upper")?,
+                amount0: f.u128("amount0")?,
+                amount1: f.u128("amount1")?,
+            })),
+            ["PrivacyEvent", "Deposit"] => Self::Privacy(PrivacyEvent::Deposit(Deposit {
+                commitment: f.felt("commitment")?,
+                leaf_index: f.u32("leaf_index")?,
+                root: f.felt("root")?,
+            })),
+            ["PrivacyEvent", "NullifierSpent"] => Self::Privacy(PrivacyEvent::NullifierSpent(NullifierSpent {
+                nullifier: f.felt("nullifier")?,
+            })),
+            ["Initialized"] => Self::Initialized(Initialized {
+                token0: f.felt("token0")?,
+                token1: f.felt("token1")?,
+                fee: f.u128("fee")?,
+                tick_spacing: f.i32("tick_spacing")?,
+                sqrt_price_x128: f.u256("sqrt_price_x128")?,
+            }),
+            ["ProofRejected"] => Self::ProofRejected(ProofRejected {
+                proof_type: f.felt("proof_type")?,
+                caller: f.felt("caller")?,
+                error: f.felt("error")?,
+            }),
+            _ => return Err(format!("No typed event for {}", raw.path.join("::"))),
+        };
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector(name: &str) -> FieldElement {
+        get_selector_from_name(name).unwrap()
+    }
+
+    fn felt(value: u64) -> FieldElement {
+        FieldElement::from(value)
+    }
+
+    fn negative(value: u64) -> FieldElement {
+        FieldElement::ZERO - FieldElement::from(value)
+    }
+
+    #[test]
+    fn test_decode_deposit() {
+        let keys = [selector("PrivacyEvent"), selector("Deposit")];
+        let data = [felt(0xc0ffee), felt(7), felt(0xabc)];
+        let event = decode_zylith_event(&keys, &data).unwrap();
+        assert_eq!(
+            event,
+            ZylithEvent::Privacy(PrivacyEvent::Deposit(Deposit {
+                commitment: felt(0xc0ffee),
+                leaf_index: 7,
+                root: felt(0xabc),
+            }))
+        );
+        // The selector the syncer used to hardcode
+        assert_eq!(
+            selector("Deposit"),
+            FieldElement::from_hex_be("0x9149d2123147c5f43d258257fef0b7b969db78269369ebcf5ebb9eef8592f2").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_swap_with_signed_amounts_and_u256() {
+        let keys = [selector("PoolEvent"), selector("Swap")];
+        let data = [
+            felt(0x123),           // sender
+            felt(1),               // zero_for_one
+            felt(1000),            // amount0
+            negative(995),         // amount1
+            felt(5),               // sqrt_price_x128.low
+            felt(1),               // sqrt_price_x128.high
+            felt(42),              // liquidity
+            negative(887272),      // tick
+        ];
+        let ZylithEvent::Pool(PoolEvent::Swap(swap)) = decode_zylith_event(&keys, &data).unwrap() else {
+            panic!("expected a swap");
+        };
+        assert!(swap.zero_for_one);
+        assert_eq!(swap.amount0, 1000);
+        assert_eq!(swap.amount1, -995);
+        assert_eq!(swap.sqrt_price_x128, (BigUint::from(1u8) << 128) + BigUint::from(5u8));
+        assert_eq!(swap.liquidity, 42);
+        assert_eq!(swap.tick, -887272);
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_events() {
+        // Unknown selector
+        let err = decode_zylith_event(&[selector("Transfer")], &[]).unwrap_err();
+        assert!(err.contains("Unknown selector"), "{}", err);
+
+        // Missing data
+        let err = decode_zylith_event(&[selector("PrivacyEvent"), selector("NullifierSpent")], &[]).unwrap_err();
+        assert!(err.contains("ended early"), "{}", err);
+
+        // Trailing data
+        let err = decode_zylith_event(
+            &[selector("PrivacyEvent"), selector("NullifierSpent")],
+            &[felt(1), felt(2)],
+        )
+        .unwrap_err();
+        assert!(err.contains("unread"), "{}", err);
+
+        // i32 out of range
+        let keys = [selector("PoolEvent"), selector("Initialize")];
+        let err = decode_zylith_event(&keys, &[felt(1), felt(0), felt(1 << 40)]).unwrap_err();
+        assert!(err.contains("out of range"), "{}", err);
+    }
+
+    #[test]
+    fn test_every_abi_event_has_a_typed_variant() {
+        let decoder = &*ZYLITH_EVENTS;
+        let Some(EventDef::Enum(variants)) = decoder.defs.get(ZYLITH_EVENT) else {
+            panic!("missing root event");
+        };
+        for variant in variants {
+            let inner: Vec<&Variant> = match decoder.defs.get(&variant.type_) {
+                Some(EventDef::Enum(inner)) => inner.iter().collect(),
+                _ => vec![variant],
+            };
+            for leaf in inner {
+                let Some(EventDef::Struct(members)) = decoder.defs.get(&leaf.type_) else {
+                    panic!("{} is not a struct event", leaf.type_);
+                };
+                let mut keys = vec![variant.selector];
+                if leaf.name != variant.name {
+                    keys.push(leaf.selector);
+                }
+                // Zero is a valid encoding of every member type
+                let width: usize = members
+                    .iter()
+                    .map(|m| if m.type_ == "core::integer::u256" { 2 } else { 1 })
+                    .sum();
+                decode_zylith_event(&keys, &vec![FieldElement::ZERO; width])
+                    .unwrap_or_else(|e| panic!("{}::{}: {}", variant.name, leaf.name, e));
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Owed fees - the contract's fee accounting for a private position, applied to the position and
+// pool state read from contract storage (see BlockchainClient::get_position_fee_state), so the
+// estimate is what private_collect pays at that block. Pool events cannot give it: private
+// mints and burns emit none, so neither their ticks nor the positions' liquidity are indexed
+
+use crate::clmm::Q128;
+use primitive_types::U256;
+
+/// A fee growth accumulator per token, in Q128.128 fees per unit of liquidity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeGrowth {
+    pub token0: U256,
+    pub token1: U256,
+}
+
+/// What `private_collect` reads to pay a position: the pool's tick and fee_growth_global, the
+/// fee_growth_outside of the range's ticks and the position's PositionInfo
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PositionFeeState {
+    pub current_tick: i32,
+    pub fee_growth_global: FeeGrowth,
+    pub outside_lower: FeeGrowth,
+    pub outside_upper: FeeGrowth,
+    pub liquidity: u128,
+    pub fee_growth_inside_last: FeeGrowth,
+    pub tokens_owed0: u128,
+    pub tokens_owed1: u128,
+}
+
+/// Fee growth inside a range, as `_get_fee_growth_inside` computes it
+pub fn fee_growth_inside(
+    current_tick: i32,
+    global: FeeGrowth,
+    outside_lower: FeeGrowth,
+    outside_upper: FeeGrowth,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Result<FeeGrowth, String> {
+    let below = if current_tick >= tick_lower {
+        outside_lower
+    } else {
+        sub(global, outside_lower, "fee growth below")?
+    };
+    let above = if current_tick < tick_upper {
+        outside_upper
+    } else {
+        sub(global, outside_upper, "fee growth above")?
+    };
+    sub(sub(global, below, "fee growth inside")?, above, "fee growth inside")
+}
+
+/// Fees `liquidity` earned between two fee-growth-inside readings, as `private_collect`
+/// computes them: (inside - inside_last) * liquidity / 2^128 per token
+pub fn fees_owed(liquidity: u128, inside: FeeGrowth, inside_last: FeeGrowth) -> Result<(u128, u128), String> {
+    let delta = sub(inside, inside_last, "fee growth delta")?;
+    let owed = |growth: U256| -> Result<u128, String> {
+        let fees = growth
+            .checked_mul(U256::from(liquidity))
+            .ok_or_else(|| "Owed fees overflow u256".to_string())?
+            / Q128;
+        if fees > U256::from(u128::MAX) {
+            return Err(format!("Owed fees {} overflow u128", fees));
+        }
+        Ok(fees.as_u128())
+    };
+    Ok((owed(delta.token0)?, owed(delta.token1)?))
+}
+
+/// What `private_collect` pays out for a position over [tick_lower, tick_upper): its
+/// tokens_owed plus the fees its liquidity earned since fee_growth_inside_last
+pub fn collect_amounts(state: &PositionFeeState, tick_lower: i32, tick_upper: i32) -> Result<(u128, u128), String> {
+    let inside = fee_growth_inside(
+        state.current_tick,
+        state.fee_growth_global,
+        state.outside_lower,
+        state.outside_upper,
+        tick_lower,
+        tick_upper,
+    )?;
+    let (fees0, fees1) = fees_owed(state.liquidity, inside, state.fee_growth_inside_last)?;
+    let total = |owed: u128, fees: u128| {
+        owed.checked_add(fees)
+            .ok_or_else(|| "Collected fees overflow u128 (the contract would revert)".to_string())
+    };
+    Ok((total(state.tokens_owed0, fees0)?, total(state.tokens_owed1, fees1)?))
+}
+
+/// Per-token a - b, failing where the contract's u256 subtraction would panic
+fn sub(a: FeeGrowth, b: FeeGrowth, what: &str) -> Result<FeeGrowth, String> {
+    let token = |a: U256, b: U256| {
+        a.checked_sub(b)
+            .ok_or_else(|| format!("{} underflows (the contract would revert)", what))
+    };
+    Ok(FeeGrowth { token0: token(a.token0, b.token0)?, token1: token(a.token1, b.token1)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn growth(token0: u32, token1: u32) -> FeeGrowth {
+        FeeGrowth { token0: U256::from(token0) * Q128, token1: U256::from(token1) * Q128 }
+    }
+
+    #[test]
+    fn test_fee_growth_inside() {
+        let global = growth(10, 4);
+        let (lower, upper) = (growth(3, 1), growth(2, 0));
+
+        // In range: everything not below the lower tick or above the upper one
+        assert_eq!(fee_growth_inside(0, global, lower, upper, -600, 600).unwrap(), growth(5, 3));
+        // Below the range the lower tick's outside counts from above, and vice versa
+        assert_eq!(fee_growth_inside(-1200, global, lower, upper, -600, 600).unwrap(), growth(1, 1));
+        assert_eq!(fee_growth_inside(600, global, lower, growth(8, 3), -600, 600).unwrap(), growth(5, 2));
+        // Ticks never crossed keep fee_growth_outside at 0
+        let zero = FeeGrowth::default();
+        assert_eq!(fee_growth_inside(0, global, zero, zero, -600, 600).unwrap(), global);
+        assert_eq!(fee_growth_inside(-1200, global, zero, zero, -600, 600).unwrap(), zero);
+        // Outsides above global are an underflow, as on-chain
+        assert!(fee_growth_inside(0, growth(1, 1), lower, upper, -600, 600).is_err());
+    }
+
+    #[test]
+    fn test_collect_amounts() {
+        // 3 of token0 per 10^6 liquidity since the last reading
+        let per_unit = U256::from(3u8) * Q128 / U256::from(1_000_000u32);
+        let mut state = PositionFeeState {
+            fee_growth_global: FeeGrowth { token0: per_unit, token1: U256::zero() },
+            liquidity: 1_000_000,
+            tokens_owed1: 7,
+            ..Default::default()
+        };
+        assert_eq!(collect_amounts(&state, -600, 600).unwrap(), (2, 7));
+        assert_eq!(fees_owed(500_000, state.fee_growth_global, FeeGrowth::default()).unwrap(), (1, 0));
+
+        // Collected up to the current reading: only tokens_owed is left
+        state.fee_growth_inside_last = state.fee_growth_global;
+        assert_eq!(collect_amounts(&state, -600, 600).unwrap(), (0, 7));
+
+        // A reading older than the stored one is an underflow, as on-chain
+        state.fee_growth_global = FeeGrowth::default();
+        assert!(collect_amounts(&state, -600, 600).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+// Garaga calldata for Groth16 BN254 proofs
+// The deployed verifiers deserialize `full_proof_with_hints` (deserialize_full_proof_with_hints_bn254):
+// the Groth16 proof with its coordinates as u384 limbs and the public inputs as u256, followed
+// by the MPCheck and MSM hints. The hints are built by the garaga CLI
+// (`garaga calldata --format array`, GARAGA_BIN); the proof points are encoded here as well and
+// checked against the CLI's output, so a proof or public input file it read differently fails
+// before the calldata leaves the ASP.
+
+use num_bigint::BigUint;
+use num_traits::Num;
+use serde_json::Value;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Starknet field prime: 2^251 + 17 * 2^192 + 1
+pub const STARKNET_PRIME: &str =
+    "3618502788666131213697322783095070105623107215331596699973092056135872020481";
+
+/// BN254 base field modulus: every proof coordinate is below it
+pub const BN254_BASE_MODULUS: &str =
+    "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+
+/// Bits per u384 limb (Garaga's u384 is four 96-bit limbs, limb0 lowest)
+const LIMB_BITS: u32 = 96;
+
+/// Split a base field element into the four u384 limbs Garaga serializes, lowest first
+pub fn u384_limbs(value: &BigUint) -> [BigUint; 4] {
+    let mask = (BigUint::from(1u8) << LIMB_BITS) - 1u8;
+    std::array::from_fn(|i| (value >> (LIMB_BITS * i as u32)) & &mask)
+}
+
+/// Parse a proof coordinate, decimal or 0x-hex, as snarkjs and rapidsnark write them
+fn coordinate(value: Option<&Value>, name: &str) -> Result<BigUint, String> {
+    let value = value.ok_or_else(|| format!("Missing proof coordinate {}", name))?;
+    let parsed = match value {
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => BigUint::from_str_radix(hex, 16).ok(),
+            None => BigUint::from_str_radix(s, 10).ok(),
+        },
+        Value::Number(n) => n.as_u64().map(BigUint::from),
+        _ => None,
+    };
+    let parsed = parsed.ok_or_else(|| format!("Invalid proof coordinate {}: {}", name, value))?;
+    let modulus = BigUint::from_str_radix(BN254_BASE_MODULUS, 10).expect("valid modulus");
+    if parsed >= modulus {
+        return Err(format!("Proof coordinate {} is not below the BN254 base field modulus: {}", name, parsed));
+    }
+    Ok(parsed)
+}
+
+/// The Groth16 proof part of `full_proof_with_hints`, as decimal felts: A (x, y), B (x0, x1,
+/// y0, y1) and C (x, y), each coordinate as 4 u384 limbs, then the public inputs as a
+/// Span<u256> (length, then low and high of each). B's x0/y0 are snarkjs' pi_b[i][0]
+pub fn groth16_proof(proof: &Value, public_inputs: &[String]) -> Result<Vec<String>, String> {
+    if let Some(protocol) = proof.get("protocol") {
+        if protocol != "groth16" {
+            return Err(format!("Expected groth16 protocol, got {}", protocol));
+        }
+    }
+
+    let point = |key: &str| proof.get(key).ok_or_else(|| format!("Missing {}", key));
+    let pi_a = point("pi_a")?;
+    let pi_b = point("pi_b")?;
+    let pi_c = point("pi_c")?;
+
+    let coordinates = [
+        (pi_a.get(0), "pi_a[0]"),
+        (pi_a.get(1), "pi_a[1]"),
+        (pi_b.get(0).and_then(|x| x.get(0)), "pi_b[0][0]"),
+        (pi_b.get(0).and_then(|x| x.get(1)), "pi_b[0][1]"),
+        (pi_b.get(1).and_then(|y| y.get(0)), "pi_b[1][0]"),
+        (pi_b.get(1).and_then(|y| y.get(1)), "pi_b[1][1]"),
+        (pi_c.get(0), "pi_c[0]"),
+        (pi_c.get(1), "pi_c[1]"),
+    ];
+    let mut calldata = Vec::with_capacity(4 * coordinates.len() + 1 + 2 * public_inputs.len());
+    for (value, name) in coordinates {
+        calldata.extend(u384_limbs(&coordinate(value, name)?).iter().map(BigUint::to_string));
+    }
+
+    calldata.push(public_inputs.len().to_string());
+    let low_mask = (BigUint::from(1u8) << 128) - 1u8;
+    for (idx, input) in public_inputs.iter().enumerate() {
+        let value = BigUint::from_str_radix(input, 10)
+            .map_err(|e| format!("Invalid public input at index {}: {}", idx, e))?;
+        if value.bits() > 256 {
+            return Err(format!("Public input at index {} does not fit a u256: {}", idx, value));
+        }
+        calldata.push((&value & &low_mask).to_string());
+        calldata.push((value >> 128u32).to_string());
+    }
+    Ok(calldata)
+}
+
+/// Parse the felts `garaga calldata` prints: a `[a, b, ...]` array or whitespace separated
+/// values (the snforge fixture format), each decimal or 0x-hex. Returned as decimal strings
+pub fn parse_calldata(output: &str) -> Result<Vec<String>, String> {
+    let prime = BigUint::from_str_radix(STARKNET_PRIME, 10).expect("valid prime");
+    let body = output.trim();
+    let body = body
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(body);
+    body.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            let value = match token.strip_prefix("0x") {
+                Some(hex) => BigUint::from_str_radix(hex, 16),
+                None => BigUint::from_str_radix(token, 10),
+            }
+            .map_err(|e| format!("Invalid felt '{}' in Garaga calldata: {}", token, e))?;
+            if value >= prime {
+                return Err(format!("Garaga calldata value {} is not below the Starknet prime", value));
+            }
+            Ok(value.to_string())
+        })
+        .collect()
+}
+
+/// `garaga calldata` for a snarkjs proof, public signals and verification key
+fn calldata_command(garaga_bin: &Path, vk_file: &Path, proof_file: &Path, public_file: &Path) -> Command {
+    let mut command = Command::new(garaga_bin);
+    command
+        .arg("calldata")
+        .args(["--system", "groth16"])
+        .arg("--vk")
+        .arg(vk_file)
+        .arg("--proof")
+        .arg(proof_file)
+        .arg("--public-inputs")
+        .arg(public_file)
+        .args(["--format", "array"])
+        .kill_on_drop(true);
+    command
+}
+
+/// `full_proof_with_hints` for a proof, without the Span length prefix: the felts the contract
+/// passes on to `verify_groth16_proof_bn254`. The hints come from the garaga CLI; its output
+/// must start with `groth16_proof(proof, public_inputs)`
+pub async fn full_proof_with_hints(
+    garaga_bin: &Path,
+    vk_file: &Path,
+    proof_file: &Path,
+    public_file: &Path,
+    public_inputs: &[String],
+) -> Result<Vec<String>, String> {
+    let proof: Value = serde_json::from_str(
+        &std::fs::read_to_string(proof_file).map_err(|e| format!("Failed to read proof file: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to parse proof file: {}", e))?;
+    let expected = groth16_proof(&proof, public_inputs)?;
+
+    let output = calldata_command(garaga_bin, vk_file, proof_file, public_file)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {:?} (install garaga or set GARAGA_BIN): {}", garaga_bin, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "garaga calldata failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let calldata = parse_calldata(&String::from_utf8_lossy(&output.stdout))?;
+    check_full_proof_with_hints(strip_length_prefix(calldata), &expected)
+}
+
+/// Drop a leading Span length: the array format prints one, the snforge fixtures do not
+fn strip_length_prefix(mut calldata: Vec<String>) -> Vec<String> {
+    if calldata.first().map(String::as_str) == Some((calldata.len().saturating_sub(1)).to_string().as_str()) {
+        calldata.remove(0);
+    }
+    calldata
+}
+
+/// The hints follow the proof the ASP generated: same points, same public inputs
+fn check_full_proof_with_hints(calldata: Vec<String>, expected: &[String]) -> Result<Vec<String>, String> {
+    if calldata.len() <= expected.len() {
+        return Err(format!(
+            "Garaga calldata has {} felts, expected the {} proof felts followed by the MPCheck and MSM hints",
+            calldata.len(),
+            expected.len()
+        ));
+    }
+    if let Some(idx) = (0..expected.len()).find(|&i| calldata[i] != expected[i]) {
+        return Err(format!(
+            "Garaga calldata does not encode the generated proof: felt {} is {}, expected {}",
+            idx, calldata[idx], expected[idx]
+        ));
+    }
+    Ok(calldata)
+}
+
+/// Rebuild a snarkjs proof and its public signals from the Groth16 part of
+/// `full_proof_with_hints`, the inverse of `groth16_proof`
+#[cfg(test)]
+fn decode_groth16_proof(calldata: &[String]) -> Result<(Value, Vec<String>), String> {
+    let felt = |idx: usize| -> Result<BigUint, String> {
+        let value = calldata.get(idx).ok_or_else(|| format!("Calldata ends at felt {}", idx))?;
+        BigUint::from_str_radix(value, 10).map_err(|e| format!("Invalid felt {}: {}", idx, e))
+    };
+    let coordinate = |i: usize| -> Result<String, String> {
+        let mut value = BigUint::from(0u8);
+        for limb in (0..4).rev() {
+            value = (value << LIMB_BITS) + felt(4 * i + limb)?;
+        }
+        Ok(value.to_string())
+    };
+    let c = (0..8).map(coordinate).collect::<Result<Vec<_>, _>>()?;
+    let proof = serde_json::json!({
+        "pi_a": [c[0], c[1], "1"],
+        "pi_b": [[c[2], c[3]], [c[4], c[5]], ["1", "0"]],
+        "pi_c": [c[6], c[7], "1"],
+        "protocol": "groth16",
+        "curve": "bn128",
+    });
+    let n_public: usize = calldata.get(32).and_then(|n| n.parse().ok()).ok_or("Missing public input count")?;
+    let public_inputs = (0..n_public)
+        .map(|i| Ok(((felt(34 + 2 * i)? << 128u32) + felt(33 + 2 * i)?).to_string()))
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok((proof, public_inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    /// (-G1, G2, -G1) with pi_c's y shifted by one: the G1 coordinates are above the Starknet
+    /// prime, so a felt252 encoding would have to reduce them
+    fn test_proof() -> Value {
+        json!({
+            "pi_a": ["21888242871839275222246405745257275088696311157297823662689037894645226208582", "2", "1"],
+            "pi_b": [
+                ["10857046999023057135944570762232829481370756359578518086990519993285655852781",
+                 "11559732032986387107991004021392285783925812861821192530917403151452391805634"],
+                ["8495653923123431417604973247489272438418190587263600148770280649306958101930",
+                 "4082367875863433681332203403145435568316851327593401208105741076214120093531"],
+                ["1", "0"]
+            ],
+            "pi_c": ["1", "21888242871839275222246405745257275088696311157297823662689037894645226208581", "1"],
+            "protocol": "groth16",
+            "curve": "bn128"
+        })
+    }
+
+    #[test]
+    fn test_groth16_proof_encodes_u384_limbs() {
+        let public_inputs = vec!["7".to_string(), (BigUint::from(5u8) << 200u32).to_string()];
+        let calldata = groth16_proof(&test_proof(), &public_inputs).unwrap();
+        assert_eq!(calldata.len(), 8 * 4 + 1 + 2 * 2);
+
+        // pi_a.x = q - 1, in full: no reduction modulo the Starknet prime
+        assert_eq!(calldata[..4], [
+            "32324006162389411176778628422",
+            "57042285082623239461879769745",
+            "3486998266802970665",
+            "0",
+        ]);
+        assert_eq!(calldata[4..8], ["2", "0", "0", "0"]);
+        // B.x0 is snarkjs' pi_b[0][0]
+        assert_eq!(calldata[8..12], [
+            "76557470010646440223880443629",
+            "20554158673455205572365460180",
+            "1729627375292849782",
+            "0",
+        ]);
+        assert_eq!(calldata[24..28], ["1", "0", "0", "0"]);
+        // Public inputs as Span<u256>: length, then (low, high)
+        assert_eq!(calldata[32..], ["2", "7", "0", "0", &(BigUint::from(5u8) << 72u32).to_string()]);
+
+        let (decoded, decoded_inputs) = decode_groth16_proof(&calldata).unwrap();
+        assert_eq!(decoded["pi_b"], test_proof()["pi_b"]);
+        assert_eq!(decoded_inputs, public_inputs);
+
+        let mut plonk = test_proof();
+        plonk["protocol"] = json!("plonk");
+        assert!(groth16_proof(&plonk, &[]).is_err());
+        let mut truncated = test_proof();
+        truncated["pi_b"] = json!([["1"]]);
+        assert!(groth16_proof(&truncated, &[]).unwrap_err().contains("pi_b[0][1]"));
+        let mut outside = test_proof();
+        outside["pi_a"][0] = json!(BN254_BASE_MODULUS);
+        assert!(groth16_proof(&outside, &[]).unwrap_err().contains("pi_a[0]"));
+    }
+
+    #[test]
+    fn test_parse_calldata_and_hints_check() {
+        assert_eq!(parse_calldata("[3, 0x10, 255]\n").unwrap(), ["3", "16", "255"]);
+        assert_eq!(parse_calldata("0x1\n0x2\n").unwrap(), ["1", "2"]);
+        assert!(parse_calldata(STARKNET_PRIME).is_err());
+        assert!(parse_calldata("[1, two]").is_err());
+
+        assert_eq!(strip_length_prefix(vec!["2".into(), "5".into(), "6".into()]), ["5", "6"]);
+        assert_eq!(strip_length_prefix(vec!["5".into(), "6".into()]), ["5", "6"]);
+
+        let expected = groth16_proof(&test_proof(), &["7".to_string()]).unwrap();
+        let mut with_hints = expected.clone();
+        with_hints.extend(["11".to_string(), "12".to_string()]);
+        assert_eq!(check_full_proof_with_hints(with_hints.clone(), &expected).unwrap(), with_hints);
+        // Hints missing, or a different proof
+        assert!(check_full_proof_with_hints(expected.clone(), &expected).is_err());
+        let mut other = with_hints;
+        other[33] = "8".to_string();
+        assert!(check_full_proof_with_hints(other, &expected).unwrap_err().contains("felt 33"));
+
+    }
+
+    #[tokio::test]
+    async fn test_full_proof_with_hints_runs_garaga_cli() {
+        let dir = std::env::temp_dir().join(format!("garaga_cli_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let proof_file = dir.join("proof.json");
+        std::fs::write(&proof_file, test_proof().to_string()).unwrap();
+        let public_inputs = vec!["7".to_string()];
+        let mut printed = groth16_proof(&test_proof(), &public_inputs).unwrap();
+        printed.extend(["11".to_string(), "12".to_string()]);
+        printed.insert(0, printed.len().to_string());
+
+        // Stand-in for the CLI: checks the arguments and prints the array
+        let garaga = dir.join("garaga");
+        std::fs::write(
+            &garaga,
+            format!(
+                "#!/bin/sh\n[ \"$1 $2 $3 $4 $6 $8 ${{10}} ${{11}}\" = \"calldata --system groth16 --vk --proof --public-inputs --format array\" ] || exit 2\necho '[{}]'\n",
+                printed.join(", ")
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&garaga, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+
+        let (vk, public) = (dir.join("vk.json"), dir.join("public.json"));
+        let full = full_proof_with_hints(&garaga, &vk, &proof_file, &public, &public_inputs).await.unwrap();
+        assert_eq!(full, printed[1..]);
+        // Public inputs the CLI did not encode
+        let err = full_proof_with_hints(&garaga, &vk, &proof_file, &public, &["8".to_string()]).await.unwrap_err();
+        assert!(err.contains("does not encode the generated proof"), "{}", err);
+        let missing = full_proof_with_hints(&dir.join("missing"), &vk, &proof_file, &public, &public_inputs).await;
+        assert!(missing.unwrap_err().contains("GARAGA_BIN"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Golden test against the fixtures the verifiers' snforge tests read
+    /// (circuits/build/garaga/<circuit>_verifier/tests/proof_calldata.txt, written by
+    /// `garaga calldata --format snforge`): the proof part must be exactly what `groth16_proof`
+    /// produces, and the proof it carries must verify against the circuit's verification key
+    #[test]
+    #[ignore = "needs the garaga snforge fixtures (tests/proof_calldata.txt) in circuits/build/garaga"]
+    fn test_groth16_proof_matches_garaga_fixtures() {
+        let build = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../circuits/build");
+        for circuit in crate::artifacts::CIRCUITS {
+            let fixture = build.join("garaga").join(format!("{}_verifier", circuit)).join("tests/proof_calldata.txt");
+            let calldata = parse_calldata(&std::fs::read_to_string(&fixture).unwrap_or_else(|e| {
+                panic!("{:?}: {}", fixture, e)
+            }))
+            .unwrap();
+
+            let (proof, public_inputs) = decode_groth16_proof(&calldata).unwrap();
+            let expected = groth16_proof(&proof, &public_inputs).unwrap();
+            check_full_proof_with_hints(calldata, &expected).unwrap();
+
+            let vk = std::fs::read_to_string(build.join("vkeys").join(format!("{}_vk.json", circuit))).unwrap();
+            let key = crate::verifier::VerificationKey::from_snarkjs(circuit, &vk).unwrap();
+            key.verify(&proof, &json!(public_inputs), &json!({}))
+                .unwrap_or_else(|rejection| panic!("{} fixture: {}", circuit, rejection));
+        }
+    }
+}
@@ -0,0 +1,343 @@
+// Proof jobs - a bounded worker pool for proof generation
+// Proofs take from seconds (native prover) to minutes (snarkjs), so handlers hand the proving
+// work to the pool and either wait for it or return a job id the client polls. Cancelling a
+// job drops its future, which kills the node/rapidsnark/python child it is waiting on
+// (every prover command is spawned with kill_on_drop). Finished jobs are kept for a TTL
+
+use crate::proof::ProofError;
+use crate::verifier::ProofRejection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::AbortHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for a worker
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// Last stage the proof pipeline reported (witness, proving, verifying, formatting)
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub stage: &'static str,
+    pub percent: u8,
+}
+
+/// What GET /api/proof/jobs/:id returns
+#[derive(Debug, Clone, Serialize)]
+pub struct JobView {
+    pub id: String,
+    /// Endpoint that queued the job (swap, lp, withdraw, mint, burn, collect)
+    pub kind: &'static str,
+    pub status: JobStatus,
+    pub progress: JobProgress,
+    /// Unix timestamps
+    pub created_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    /// The response the endpoint would have returned synchronously
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Set when the proof failed verification against the circuit's verification key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejection: Option<Box<ProofRejection>>,
+}
+
+struct Job {
+    view: JobView,
+    /// Set while the job's task is queued or running
+    abort: Option<AbortHandle>,
+    /// When the job finished, for the TTL sweep
+    finished: Option<Instant>,
+    /// Flips to true once the job is finished
+    done: watch::Sender<bool>,
+}
+
+type JobMap = Arc<Mutex<HashMap<String, Job>>>;
+
+tokio::task_local! {
+    /// Job the current task is running, for `report`
+    static CURRENT_JOB: (JobMap, String);
+}
+
+/// Proof job queue shared by the handlers
+#[derive(Clone)]
+pub struct JobQueue {
+    jobs: JobMap,
+    workers: Arc<Semaphore>,
+    worker_count: usize,
+    ttl: Duration,
+}
+
+impl JobQueue {
+    /// Run at most `workers` jobs at once and keep finished ones for `ttl`
+    pub fn new(workers: usize, ttl: Duration) -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            workers: Arc::new(Semaphore::new(workers)),
+            worker_count: workers,
+            ttl,
+        }
+    }
+
+    /// Queue `job` and return right away; it starts once a worker is free
+    pub fn submit<F>(&self, kind: &'static str, job: F) -> JobView
+    where
+        F: Future<Output = Result<serde_json::Value, ProofError>> + Send + 'static,
+    {
+        self.sweep();
+        let id = hex::encode(rand::random::<[u8; 16]>());
+        let view = JobView {
+            id: id.clone(),
+            kind,
+            status: JobStatus::Queued,
+            progress: JobProgress { stage: "queued", percent: 0 },
+            created_at: crate::reconcile::unix_now(),
+            started_at: None,
+            finished_at: None,
+            result: None,
+            error: None,
+            rejection: None,
+        };
+        let (done, _) = watch::channel(false);
+        self.jobs.lock().unwrap().insert(
+            id.clone(),
+            Job { view: view.clone(), abort: None, finished: None, done },
+        );
+
+        let queue = self.clone();
+        let job_id = id.clone();
+        let handle = tokio::spawn(async move {
+            // Held until the job finishes or its task is aborted
+            let Ok(_permit) = queue.workers.clone().acquire_owned().await else {
+                return;
+            };
+            if !queue.start(&job_id) {
+                return;
+            }
+            let result = CURRENT_JOB.scope((queue.jobs.clone(), job_id.clone()), job).await;
+            queue.finish(&job_id, result);
+        });
+
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            if !job.view.status.is_finished() {
+                job.abort = Some(handle.abort_handle());
+            }
+        }
+        println!("[Proof] 📥 Job {} ({}) queued", id, kind);
+        view
+    }
+
+    /// Queue `job` and wait for it, for callers that answer synchronously. The job is
+    /// dropped from the queue once its result is handed back
+    pub async fn run<F>(&self, kind: &'static str, job: F) -> Result<serde_json::Value, ProofError>
+    where
+        F: Future<Output = Result<serde_json::Value, ProofError>> + Send + 'static,
+    {
+        let id = self.submit(kind, job).id;
+        let mut done = self
+            .jobs
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|job| job.done.subscribe())
+            .ok_or_else(|| format!("Proof job {} disappeared", id))?;
+        let _ = done.wait_for(|done| *done).await;
+
+        let job = self
+            .jobs
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .ok_or_else(|| format!("Proof job {} disappeared", id))?;
+        match job.view.status {
+            JobStatus::Succeeded => Ok(job.view.result.unwrap_or_default()),
+            JobStatus::Cancelled => Err(format!("Proof job {} was cancelled", id).into()),
+            _ => Err(match job.view.rejection {
+                Some(rejection) => ProofError::Rejected(rejection),
+                None => job.view.error.unwrap_or_else(|| "Proof job failed".to_string()).into(),
+            }),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobView> {
+        self.sweep();
+        self.jobs.lock().unwrap().get(id).map(|job| job.view.clone())
+    }
+
+    /// Cancel a queued or running job, killing its prover process, or discard a finished
+    /// job's result. Returns the job as it was left, None for an unknown id
+    pub fn cancel(&self, id: &str) -> Option<JobView> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.get(id)?.view.status.is_finished() {
+            return jobs.remove(id).map(|job| job.view);
+        }
+
+        let job = jobs.get_mut(id)?;
+        if let Some(abort) = job.abort.take() {
+            abort.abort();
+        }
+        job.view.status = JobStatus::Cancelled;
+        job.view.finished_at = Some(crate::reconcile::unix_now());
+        job.finished = Some(Instant::now());
+        job.done.send_replace(true);
+        println!("[Proof] 🛑 Job {} ({}) cancelled", id, job.view.kind);
+        Some(job.view.clone())
+    }
+
+    /// Worker count and jobs per status, for /health
+    pub fn stats(&self) -> serde_json::Value {
+        let jobs = self.jobs.lock().unwrap();
+        let count = |status: JobStatus| jobs.values().filter(|job| job.view.status == status).count();
+        serde_json::json!({
+            "workers": self.worker_count,
+            "idle_workers": self.workers.available_permits(),
+            "queued": count(JobStatus::Queued),
+            "running": count(JobStatus::Running),
+            "finished": jobs.values().filter(|job| job.view.status.is_finished()).count(),
+            "result_ttl_secs": self.ttl.as_secs(),
+        })
+    }
+
+    /// Mark a job running; false when it was cancelled while queued
+    fn start(&self, id: &str) -> bool {
+        let mut jobs = self.jobs.lock().unwrap();
+        match jobs.get_mut(id) {
+            Some(job) if job.view.status == JobStatus::Queued => {
+                job.view.status = JobStatus::Running;
+                job.view.started_at = Some(crate::reconcile::unix_now());
+                job.view.progress = JobProgress { stage: "started", percent: 0 };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn finish(&self, id: &str, result: Result<serde_json::Value, ProofError>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(id) else { return };
+        if job.view.status.is_finished() {
+            return;
+        }
+        match result {
+            Ok(value) => {
+                job.view.status = JobStatus::Succeeded;
+                job.view.progress = JobProgress { stage: "done", percent: 100 };
+                job.view.result = Some(value);
+            }
+            Err(e) => {
+                job.view.status = JobStatus::Failed;
+                job.view.error = Some(e.to_string());
+                if let ProofError::Rejected(rejection) = e {
+                    job.view.rejection = Some(rejection);
+                }
+            }
+        }
+        job.view.finished_at = Some(crate::reconcile::unix_now());
+        job.finished = Some(Instant::now());
+        job.abort = None;
+        job.done.send_replace(true);
+        println!("[Proof] 🏁 Job {} ({}) {:?}", id, job.view.kind, job.view.status);
+    }
+
+    /// Drop finished jobs older than the TTL
+    fn sweep(&self) {
+        let ttl = self.ttl;
+        self.jobs
+            .lock()
+            .unwrap()
+            .retain(|_, job| job.finished.is_none_or(|finished| finished.elapsed() < ttl));
+    }
+}
+
+/// Record the stage the current proof job reached; does nothing outside a job
+pub fn report(stage: &'static str, percent: u8) {
+    let _ = CURRENT_JOB.try_with(|(jobs, id)| {
+        if let Some(job) = jobs.lock().unwrap().get_mut(id) {
+            job.view.progress = JobProgress { stage, percent };
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_jobs_run_and_report_progress() {
+        let queue = JobQueue::new(1, Duration::from_secs(60));
+        let (release, wait) = tokio::sync::oneshot::channel::<()>();
+        let view = queue.submit("swap", async move {
+            report("proving", 30);
+            let _ = wait.await;
+            Ok(serde_json::json!({ "proof": [1, 2] }))
+        });
+        assert_eq!(view.status, JobStatus::Queued);
+
+        // The single worker is busy, so a second job waits in the queue
+        let second = queue.submit("lp", async { Err("bad witness".into()) });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let running = queue.get(&view.id).unwrap();
+        assert_eq!(running.status, JobStatus::Running);
+        assert_eq!(running.progress.stage, "proving");
+        assert_eq!(queue.get(&second.id).unwrap().status, JobStatus::Queued);
+
+        release.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let finished = queue.get(&view.id).unwrap();
+        assert_eq!(finished.status, JobStatus::Succeeded);
+        assert_eq!(finished.result.unwrap()["proof"][1], 2);
+        let failed = queue.get(&second.id).unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("bad witness"));
+
+        // Synchronous callers get the result and leave nothing behind
+        assert_eq!(queue.run("swap", async { Ok(serde_json::json!(7)) }).await.unwrap(), 7);
+        assert!(queue.run("swap", async { Err("no zkey".into()) }).await.unwrap_err().to_string().contains("no zkey"));
+        assert_eq!(queue.stats()["finished"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_ttl() {
+        let queue = JobQueue::new(1, Duration::from_millis(50));
+        let view = queue.submit("withdraw", async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            Ok(serde_json::Value::Null)
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(queue.cancel(&view.id).unwrap().status, JobStatus::Cancelled);
+        // The aborted task released its worker
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(queue.workers.available_permits(), 1);
+        assert_eq!(queue.get(&view.id).unwrap().status, JobStatus::Cancelled);
+
+        // Past the TTL the job is gone
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(queue.get(&view.id).is_none());
+        assert!(queue.cancel(&view.id).is_none());
+
+        // Deleting a finished job discards its result
+        let done = queue.submit("collect", async { Ok(serde_json::Value::Null) });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(queue.cancel(&done.id).unwrap().status, JobStatus::Succeeded);
+        assert!(queue.get(&done.id).is_none());
+    }
+}
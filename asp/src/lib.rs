@@ -0,0 +1,2 @@
+// Library target shared by the ASP server and the tools in src/bin
+pub mod config;
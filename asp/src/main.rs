@@ -1,32 +1,56 @@
 mod abi;
+mod artifacts;
 mod blockchain;
 mod calldata;
+mod clmm;
 mod commitment;
+mod events;
+mod fees;
+mod garaga;
+mod jobs;
 mod merkle;
+mod pool_index;
 mod proof;
+#[cfg(feature = "native-prover")]
+mod prover;
+mod reconcile;
+mod storage;
 mod syncer;
+mod ticks;
+mod validation;
+mod verifier;
 
 use axum::{
-    extract::{Path, State},
+    extract::{ConnectInfo, Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
+use artifacts::ArtifactRegistry;
 use blockchain::BlockchainClient;
 use calldata::{
-    build_approve_calldata, build_burn_liquidity_calldata, build_deposit_calldata,
-    build_initialize_calldata, build_mint_liquidity_calldata, build_swap_calldata,
-    build_withdraw_calldata, u256_to_low_high,
+    build_approve_calldata, build_deposit_calldata,
+    build_burn_liquidity_calldata, build_collect_calldata, build_initialize_calldata, build_mint_liquidity_calldata,
+    build_swap_calldata, build_withdraw_calldata, u256_to_low_high,
 };
 use num_bigint::BigUint;
-use std::str::FromStr;
+use primitive_types::U256;
 use commitment::{generate_commitment, generate_note};
+use zylith_asp::config::Config;
+use jobs::JobQueue;
 use merkle::{MerkleProof, MerkleTree, TREE_DEPTH};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
-use syncer::Syncer;
+use std::time::{Duration, Instant};
+use reconcile::SharedSyncStatus;
+use storage::TreeStore;
+use syncer::{SyncCommand, Syncer};
+use tokio::sync::mpsc;
 use tower_http::cors::{Any, CorsLayer};
+use validation::{parse_field_element, Validator};
 
 /// Application state with two Merkle trees and blockchain client
 #[derive(Clone)]
@@ -39,6 +63,20 @@ struct AppState {
     blockchain: Arc<BlockchainClient>,
     /// Zylith contract address
     zylith_address: String,
+    /// Persistent storage for the deposit tree and sync cursor
+    store: TreeStore,
+    /// Latest reconciliation of the deposit tree against the contract
+    sync_status: SharedSyncStatus,
+    /// Control channel to the running syncer (resync, pause, resume)
+    sync_control: mpsc::Sender<SyncCommand>,
+    /// Network profile and server settings
+    config: Arc<Config>,
+    /// Circuit wasm, zkeys and verification keys, resolved and hashed at startup
+    artifacts: Arc<ArtifactRegistry>,
+    /// When each client's last fallback chain scan for /deposit/index started (rate limit)
+    last_chain_scan: Arc<Mutex<HashMap<IpAddr, Instant>>>,
+    /// Bounded worker pool every proof runs on
+    jobs: JobQueue,
 }
 
 /// Response for tree info
@@ -47,6 +85,64 @@ struct TreeInfo {
     root: String,
     leaf_count: u32,
     depth: usize,
+    /// Leaves buried under the confirmation depth (deposit tree only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finalized_leaf_count: Option<u32>,
+}
+
+/// Blocks past the sync cursor scanned on a /deposit/index miss
+const CHAIN_SCAN_MAX_BLOCKS: u64 = 2000;
+
+/// Minimum time between two fallback chain scans of the same client
+const CHAIN_SCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Upper bound on commitments per /deposit/indices request
+const MAX_BATCH_COMMITMENTS: usize = 1000;
+
+/// Request to resolve many commitments to leaf indices
+#[derive(Deserialize)]
+struct BatchIndexRequest {
+    commitments: Vec<String>,
+}
+
+/// Upper bound on nullifiers per /api/nullifiers request
+const MAX_BATCH_NULLIFIERS: usize = 1000;
+
+/// Request to check the spent status of many nullifiers
+#[derive(Deserialize)]
+struct BatchNullifierRequest {
+    nullifiers: Vec<String>,
+}
+
+/// Upper bound on events returned by /api/pool/events
+const MAX_RECENT_POOL_EVENTS: u32 = 500;
+
+/// Query for the pool time-series endpoints
+#[derive(Deserialize)]
+struct SeriesQuery {
+    /// Bucket size: 1m, 5m, 15m, 1h (default), 4h or 1d
+    interval: Option<String>,
+    /// Unix timestamps; `to` defaults to now
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+impl SeriesQuery {
+    /// Interval in seconds and the bucket-aligned [from, to) range
+    fn range(&self) -> Result<(u64, u64, u64), String> {
+        let interval = pool_index::parse_interval(self.interval.as_deref().unwrap_or("1h"))?;
+        let to = self.to.unwrap_or_else(reconcile::unix_now);
+        if self.from.is_some_and(|from| from > to) {
+            return Err("'from' must not be after 'to'".to_string());
+        }
+        let (from, to) = pool_index::bucket_range(interval, self.from, to);
+        Ok((interval, from, to))
+    }
+}
+
+#[derive(Deserialize)]
+struct RecentEventsQuery {
+    limit: Option<u32>,
 }
 
 /// Request to insert into associated set
@@ -59,12 +155,14 @@ struct InsertRequest {
 async fn main() {
     tracing_subscriber::fmt::init();
 
-    // Get configuration from environment
-    let rpc_url = std::env::var("RPC_URL")
-        .unwrap_or_else(|_| "https://api.cartridge.gg/x/starknet/sepolia".to_string());
-    let contract_address = std::env::var("CONTRACT_ADDRESS").unwrap_or_else(|_| {
-        "0x05c1fecd5025d2e3d71482ac26ecf9138b08c9483f43761543cf71960373ce0b".to_string()
-    });
+    // Load the network profile (built-in defaults, ASP_CONFIG file, env overrides)
+    let config = Arc::new(Config::load().expect("Invalid ASP configuration"));
+    println!("✓ Configuration loaded for network '{}'", config.network);
+
+    let rpc_url = config.rpc_url().to_string();
+    let contract_address = config.profile.zylith_address.clone();
+    // Block the contract was deployed in - event scans never start before it
+    let deployment_block = config.profile.deployment_block;
 
     // Validate ABIs on startup
     let zylith_abi = abi::get_zylith_abi();
@@ -77,26 +175,76 @@ async fn main() {
 
     println!("✓ ABIs validated successfully");
 
+    // Resolve circuit artifacts once; a verification key the Garaga verifier would reject stops startup
+    let artifacts = Arc::new(
+        ArtifactRegistry::load(std::path::Path::new(&config.circuits_dir), std::path::Path::new(&config.garaga_bin))
+            .expect("Circuit artifact check failed"),
+    );
+    for (name, circuit) in artifacts.iter() {
+        if circuit.is_ready() {
+            println!("✓ Circuit '{}' artifacts loaded (zkey sha256 {})", name, circuit.zkey.sha256.as_deref().unwrap_or_default());
+        } else {
+            println!("[ASP] ⚠️  Circuit '{}' is missing its wasm, zkey or verification key, its proofs are disabled", name);
+        }
+    }
+
+    // Load proving keys and witness calculators once, so proofs skip node and rapidsnark
+    #[cfg(feature = "native-prover")]
+    {
+        let loaded = prover::init(&artifacts).expect("Failed to load native prover");
+        println!("✓ Native prover ready ({} circuit(s): {})", loaded.len(), loaded.join(", "));
+    }
+
     // Initialize blockchain client
     let blockchain = Arc::new(
         BlockchainClient::new(&rpc_url, &contract_address)
-            .expect("Failed to initialize blockchain client"),
+            .expect("Failed to initialize blockchain client")
+            .with_deployment_block(deployment_block),
+    );
+
+    // Open persistent storage and restore the deposit tree from it
+    let database_url = &config.database_url;
+    let store = TreeStore::open(database_url)
+        .await
+        .expect("Failed to open ASP database");
+    let (restored_tree, last_synced_block) = store
+        .load_tree(TREE_DEPTH)
+        .await
+        .expect("Failed to load deposit tree from database");
+    println!(
+        "✓ Deposit tree restored from {} ({} leaves, last synced block {})",
+        database_url,
+        restored_tree.get_leaf_count(),
+        last_synced_block
     );
 
     // Initialize both trees
-    let deposit_tree = Arc::new(Mutex::new(MerkleTree::new(TREE_DEPTH)));
+    let deposit_tree = Arc::new(Mutex::new(restored_tree));
     let associated_tree = Arc::new(Mutex::new(MerkleTree::new(TREE_DEPTH)));
+    let sync_status = SharedSyncStatus::default();
+    let (sync_control, sync_commands) = mpsc::channel(16);
 
     let state = AppState {
         deposit_tree: deposit_tree.clone(),
         associated_tree: associated_tree.clone(),
         blockchain: blockchain.clone(),
         zylith_address: contract_address.clone(),
+        store: store.clone(),
+        sync_status: sync_status.clone(),
+        sync_control,
+        config: config.clone(),
+        artifacts,
+        last_chain_scan: Arc::new(Mutex::new(HashMap::new())),
+        jobs: JobQueue::new(config.proof_workers, Duration::from_secs(config.proof_job_ttl_secs)),
     };
 
     // Initialize Syncer for deposit tree with blockchain client for root verification
-    let syncer = Syncer::new(&rpc_url, &contract_address, deposit_tree)
-        .with_blockchain_client(blockchain.clone());
+    let syncer = Syncer::new(&rpc_url, &contract_address, deposit_tree, store)
+        .with_blockchain_client(blockchain.clone())
+        .with_confirmation_depth(config.confirmation_depth)
+        .with_status(sync_status)
+        .with_deployment_block(deployment_block)
+        .with_commands(sync_commands);
     
     // Run syncer in background
     tokio::spawn(async move {
@@ -114,8 +262,12 @@ async fn main() {
         .route("/deposit/proof/:index", get(get_deposit_proof))
         .route("/deposit/root", get(get_deposit_root))
         .route("/deposit/info", get(get_deposit_info))
+        .route("/deposit/status", get(get_deposit_status))
         .route("/deposit/index/:commitment", get(get_deposit_index))
+        .route("/deposit/indices", post(get_deposit_indices))
         .route("/deposit/resync", post(force_resync))
+        .route("/deposit/pause", post(pause_sync))
+        .route("/deposit/resume", post(resume_sync))
         .route("/deposit/list", get(list_deposits))
         // Associated set tree endpoints
         .route("/associated/proof/:index", get(get_associated_proof))
@@ -129,29 +281,41 @@ async fn main() {
         .route("/api/pool/root", get(get_pool_root))
         .route("/api/pool/info", get(get_pool_info))
         .route("/api/nullifier/:nullifier", get(check_nullifier))
+        .route("/api/nullifiers", post(check_nullifiers))
         .route("/api/token/:address/balance/:owner", get(get_token_balance))
         .route("/api/token/:address/allowance/:owner/:spender", get(get_token_allowance))
         .route("/api/pool/initialized", get(check_pool_initialized))
+        .route("/api/pool/state", get(get_pool_state))
+        .route("/api/pool/ticks", get(get_pool_ticks))
+        .route("/api/pool/candles", get(get_pool_candles))
+        .route("/api/pool/volume", get(get_pool_volume))
+        .route("/api/pool/tvl", get(get_pool_tvl))
+        .route("/api/pool/events", get(get_recent_pool_events))
+        .route("/api/config", get(get_network_config))
         // Transaction preparation endpoints
         .route("/api/deposit/prepare", post(prepare_deposit))
+        .route("/api/swap/quote", post(quote_swap))
         .route("/api/swap/prepare", post(prepare_swap))
         .route("/api/withdraw/prepare", post(prepare_withdraw))
         .route("/api/liquidity/mint/prepare", post(prepare_mint_liquidity))
         .route("/api/liquidity/burn/prepare", post(prepare_burn_liquidity))
+        .route("/api/liquidity/collect/prepare", post(prepare_collect))
         .route("/api/initialize/prepare", post(prepare_initialize))
         // ZK Proof generation endpoints
         .route("/api/proof/swap", post(generate_swap_proof_endpoint))
         .route("/api/proof/lp-mint", post(generate_lp_proof_endpoint))
+        .route("/api/proof/membership", post(generate_membership_proof_endpoint))
+        .route("/api/proof/jobs/:id", get(get_proof_job).delete(cancel_proof_job))
         // Health check
         .route("/health", get(health_check))
         .layer(cors)
         .with_state(state);
 
-    let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
-    let addr = format!("0.0.0.0:{}", port);
+    let addr = format!("0.0.0.0:{}", config.port);
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     println!("ASP Server running on {}", addr);
+    println!("Network: {}", config.network);
     println!("Zylith Contract: {}", contract_address);
     println!("RPC URL: {}", rpc_url);
     println!("\nEndpoints:");
@@ -159,24 +323,43 @@ async fn main() {
     println!("  GET  /deposit/root          - Get current deposit tree root");
     println!("  GET  /deposit/info          - Get deposit tree info");
     println!("  GET  /deposit/index/:commitment - Get leaf index for commitment");
+    println!("  POST /deposit/indices       - Get leaf indices for many commitments");
+    println!("  GET  /deposit/status        - Reconciliation status against the contract");
     println!("  POST /deposit/resync        - Force re-sync from specific block");
+    println!("  POST /deposit/pause         - Pause the deposit syncer");
+    println!("  POST /deposit/resume        - Resume the deposit syncer");
     println!("  GET  /associated/proof/:index - Get Merkle proof for associated set");
     println!("  GET  /associated/root       - Get current associated set root");
     println!("  GET  /associated/info       - Get associated set tree info");
     println!("  POST /associated/insert     - Insert commitment into associated set");
     println!("  GET  /api/pool/root         - Get Merkle root on-chain");
     println!("  GET  /api/pool/info         - Get pool info");
+    println!("  GET  /api/pool/state        - Indexed price, tick, liquidity and contract balances");
+    println!("  GET  /api/pool/ticks        - Initialized ticks and their liquidity");
+    println!("  GET  /api/pool/candles      - OHLC candles (?interval=1h&from=&to=)");
+    println!("  GET  /api/pool/volume       - Swap volume per interval");
+    println!("  GET  /api/pool/tvl          - Contract balances and TVL per interval");
+    println!("  GET  /api/pool/events       - Most recent pool events");
     println!("  GET  /api/nullifier/:nullifier - Check if nullifier is spent");
+    println!("  POST /api/nullifiers        - Check many nullifiers at once");
     println!("  GET  /api/token/:address/balance/:owner - Get token balance");
     println!("  GET  /api/token/:address/allowance/:owner/:spender - Get token allowance");
     println!("  POST /api/deposit/prepare    - Prepare deposit transaction");
+    println!("  POST /api/swap/quote        - Simulate a swap and fill its public inputs");
     println!("  POST /api/swap/prepare      - Prepare swap transaction");
-    println!("  POST /api/withdraw/prepare  - Prepare withdraw transaction");
-    println!("  POST /api/liquidity/mint/prepare - Prepare mint liquidity transaction");
-    println!("  POST /api/liquidity/burn/prepare - Prepare burn liquidity transaction");
-    println!("  GET  /health                - Health check");
-
-    axum::serve(listener, app).await.unwrap();
+    println!("  POST /api/withdraw/prepare  - Prove a withdraw and prepare private_withdraw");
+    println!("  POST /api/liquidity/mint/prepare - Prove a mint and prepare private_mint_liquidity");
+    println!("  POST /api/liquidity/burn/prepare - Prove a burn and prepare private_burn_liquidity");
+    println!("  POST /api/liquidity/collect/prepare - Estimate owed fees and prepare private_collect");
+    println!("  POST /api/proof/swap        - Generate a swap proof");
+    println!("  POST /api/proof/lp-mint     - Generate an LP proof");
+    println!("  POST /api/proof/membership  - Prove a note is in the associated set");
+    println!("  GET  /api/proof/jobs/:id    - Status, progress and result of a proof job");
+    println!("  DELETE /api/proof/jobs/:id  - Cancel a proof job (or discard its result)");
+    println!("    (proving endpoints take ?async=true to answer 202 with a job id)");
+    println!("  GET  /health                - Health check and loaded circuit artifacts");
+
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
 
 // ==================== Deposit Tree Endpoints ====================
@@ -225,6 +408,13 @@ async fn get_deposit_root(State(state): State<AppState>) -> impl IntoResponse {
 
 async fn get_deposit_info(State(state): State<AppState>) -> impl IntoResponse {
     println!("[ASP] 📥 GET /deposit/info");
+    let finalized_leaf_count = match state.store.get_finalized_leaf_count().await {
+        Ok(count) => Some(count),
+        Err(e) => {
+            println!("[ASP] ⚠️  Failed to read finalized leaf count: {}", e);
+            None
+        }
+    };
     let tree = state.deposit_tree.lock().unwrap();
     let leaf_count = tree.get_leaf_count();
     
@@ -244,56 +434,75 @@ async fn get_deposit_info(State(state): State<AppState>) -> impl IntoResponse {
         root: format!("0x{:x}", tree.get_root()),
         leaf_count,
         depth: tree.depth,
+        finalized_leaf_count,
     })
 }
 
+/// Reconciliation state of the deposit tree (in_sync, catching_up or diverged)
+async fn get_deposit_status(State(state): State<AppState>) -> impl IntoResponse {
+    let status = state.sync_status.read().unwrap().clone();
+    Json(status)
+}
+
 /// Force re-sync from a specific block
-/// This will reset the syncer state and start syncing from the specified block
+/// The syncer rebuilds the tree from that block next to the live one and swaps it in
+/// once it has caught up; progress is reported on /deposit/status
 /// Body: { "from_block": 4438440 } (optional, defaults to contract deployment block)
 async fn force_resync(
+    State(state): State<AppState>,
     Json(payload): Json<serde_json::Value>,
 ) -> impl IntoResponse {
-    use std::fs;
-    
     println!("\n[ASP] ========================================");
     println!("[ASP] 🔄 POST /deposit/resync - Force re-sync requested");
     println!("[ASP] ========================================");
     
     let block_number = payload.get("from_block")
         .and_then(|v| v.as_u64())
-        .unwrap_or(4438440); // Default to contract deployment block
+        .unwrap_or(state.config.profile.deployment_block);
     
-    println!("[ASP] 📋 Resetting sync state to block {}", block_number);
+    println!("[ASP] 📋 Requesting rebuild from block {}", block_number);
     
-    let state = serde_json::json!({
-        "last_synced_block": block_number
-    });
-    
-    if let Ok(json) = serde_json::to_string(&state) {
-        if let Err(e) = fs::write("asp_state.json", json) {
-            println!("[ASP] ❌ Failed to write state: {}", e);
-            println!("[ASP] ========================================\n");
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write state: {}", e)).into_response();
-        }
-    } else {
-        println!("[ASP] ❌ Failed to serialize state");
+    if let Err(e) = state.sync_control.send(SyncCommand::Resync { from_block: block_number }).await {
+        println!("[ASP] ❌ Syncer is not running: {}", e);
         println!("[ASP] ========================================\n");
-        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to serialize state").into_response();
+        return (StatusCode::SERVICE_UNAVAILABLE, "Syncer is not running").into_response();
     }
     
-    println!("[ASP] ✅ State file updated successfully");
-    println!("[ASP] ⚠️  IMPORTANT: Restart the ASP server for changes to take effect");
+    println!("[ASP] ✅ Resync started - follow progress on /deposit/status");
     println!("[ASP] ========================================\n");
     
-    Json(serde_json::json!({
+    (StatusCode::ACCEPTED, Json(serde_json::json!({
         "success": true,
-        "message": format!("Re-sync will start from block {}", block_number),
-        "note": "Restart the ASP server for changes to take effect"
-    })).into_response()
+        "message": format!("Re-sync started from block {}", block_number),
+        "status_url": "/deposit/status"
+    }))).into_response()
+}
+
+/// Pause the deposit syncer (the tree keeps serving proofs)
+async fn pause_sync(State(state): State<AppState>) -> impl IntoResponse {
+    send_sync_command(&state, SyncCommand::Pause, "paused").await
+}
+
+/// Resume a paused deposit syncer
+async fn resume_sync(State(state): State<AppState>) -> impl IntoResponse {
+    send_sync_command(&state, SyncCommand::Resume, "resumed").await
+}
+
+async fn send_sync_command(state: &AppState, command: SyncCommand, action: &str) -> axum::response::Response {
+    println!("[ASP] 📥 Syncer control: {:?}", command);
+    match state.sync_control.send(command).await {
+        Ok(()) => Json(serde_json::json!({
+            "success": true,
+            "message": format!("Syncer {}", action)
+        })).into_response(),
+        Err(_) => (StatusCode::SERVICE_UNAVAILABLE, "Syncer is not running").into_response(),
+    }
 }
 
 async fn get_deposit_index(
     Path(commitment): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: axum::http::HeaderMap,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
     use num_bigint::BigUint;
@@ -313,75 +522,96 @@ async fn get_deposit_index(
     println!("[ASP] 🔍 GET /deposit/index/{}", commitment_str.chars().take(20).collect::<String>());
     println!("[ASP] ========================================");
     
-    // First, check local tree (fast path)
-    let (found_locally, leaf_count) = {
-        let tree = state.deposit_tree.lock().unwrap();
-        let leaf_count = tree.get_leaf_count();
-        let found = tree.find_commitment_index(&commitment_bigint).is_some();
-        (found, leaf_count)
-    };
-    
-    println!("[ASP] 📊 Local tree status: {} leaves, found locally: {}", leaf_count, found_locally);
-    
-    if found_locally {
-        let tree = state.deposit_tree.lock().unwrap();
-        if let Some(index) = tree.find_commitment_index(&commitment_bigint) {
-            println!("[ASP] ✅ Found commitment in local tree at index {}", index);
+    // Event index written by the syncer (block and tx hash included)
+    match state.store.find_deposit_event(&commitment_bigint).await {
+        Ok(Some(deposit)) => {
+            println!("[ASP] ✅ Found commitment in event index at index {}", deposit.leaf_index);
             println!("[ASP] ========================================\n");
             return Json(serde_json::json!({
-                "index": index,
+                "index": deposit.leaf_index,
                 "found": true,
-                "source": "local_tree"
+                "source": "event_index",
+                "block_number": deposit.block_number,
+                "tx_hash": deposit.tx_hash
             })).into_response();
         }
+        Ok(None) => {}
+        Err(e) => eprintln!("[ASP] ⚠️  Event index lookup failed: {}", e),
     }
+
+    // Local tree covers leaves synced before the event index existed
+    let (local_index, leaf_count) = {
+        let tree = state.deposit_tree.lock().unwrap();
+        (tree.find_commitment_index(&commitment_bigint), tree.get_leaf_count())
+    };
     
-    // Not found locally - search in contract events directly (fast lookup)
-    println!("[ASP] 🔍 Commitment not in local tree. Searching in contract events...");
+    println!("[ASP] 📊 Local tree status: {} leaves, found locally: {}", leaf_count, local_index.is_some());
     
-    match state.blockchain.find_commitment_in_events(&format!("0x{:x}", commitment_bigint)).await {
-        Ok(Some(index)) => {
-            println!("[ASP] ✅ Found commitment in events at index {}. Adding to local tree...", index);
-            
-            // Add to local tree for future queries
-            // Get zero_leaf first (before acquiring mutable lock)
-            let (current_count, zero_leaf) = {
-                let tree = state.deposit_tree.lock().unwrap();
-                (tree.get_leaf_count(), tree.zeros[0].clone())
-            };
-            
-            // Now acquire mutable lock and do all operations
-            let mut tree = state.deposit_tree.lock().unwrap();
-            
-            // Handle gaps if needed
-            if index > current_count {
-                let gaps = index - current_count;
-                println!("   Filling {} gap(s) before index {}", gaps, index);
-                for i in 0..gaps {
-                    tree.insert_at_index(current_count + i, zero_leaf.clone());
-                }
-            }
-            
-            // Insert the commitment
-            if index == tree.get_leaf_count() {
-                tree.insert(commitment_bigint.clone());
-            } else {
-                tree.insert_at_index(index, commitment_bigint.clone());
+    if let Some(index) = local_index {
+        println!("[ASP] ✅ Found commitment in local tree at index {}", index);
+        println!("[ASP] ========================================\n");
+        return Json(serde_json::json!({
+            "index": index,
+            "found": true,
+            "source": "local_tree"
+        })).into_response();
+    }
+
+    // Not synced yet - scan the unsynced tail of the chain, at most once per interval per client
+    let forwarded_for = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect::<Vec<_>>()
+        .join(",");
+    let client = state.config.client_ip(peer.ip(), Some(&forwarded_for));
+    {
+        let mut scans = state.last_chain_scan.lock().unwrap();
+        // Expired entries are dropped so the map only holds clients inside their interval
+        scans.retain(|_, started| started.elapsed() < CHAIN_SCAN_INTERVAL);
+        if let Some(started) = scans.get(&client) {
+            let elapsed = started.elapsed();
+            if elapsed < CHAIN_SCAN_INTERVAL {
+                let retry_after = (CHAIN_SCAN_INTERVAL - elapsed).as_secs() + 1;
+                println!("[ASP] ⏳ Chain scan rate limited for {}, retry in {}s", client, retry_after);
+                println!("[ASP] ========================================\n");
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(axum::http::header::RETRY_AFTER, retry_after.to_string())],
+                    "Commitment not synced yet and a chain scan just ran. Retry shortly.",
+                ).into_response();
             }
-            
+        }
+        scans.insert(client, Instant::now());
+    }
+
+    let last_synced_block = match state.store.get_last_synced_block().await {
+        Ok(block) => block,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    println!("[ASP] 🔍 Commitment not synced yet. Scanning blocks after {}...", last_synced_block);
+
+    match state
+        .blockchain
+        .find_commitment_in_events(&format!("0x{:x}", commitment_bigint), last_synced_block, CHAIN_SCAN_MAX_BLOCKS)
+        .await
+    {
+        Ok(Some(index)) => {
+            // The syncer inserts it once it reaches the block; the tree is not touched here
+            println!("[ASP] ✅ Found commitment in unsynced events at index {}", index);
             println!("[ASP] ========================================\n");
             Json(serde_json::json!({
                 "index": index,
                 "found": true,
-                "source": "contract_events"
+                "source": "chain_scan",
+                "synced": false
             })).into_response()
         },
         Ok(None) => {
-            println!("[ASP] ❌ Commitment not found in contract events");
+            println!("[ASP] ❌ Commitment not found in the index or the unsynced tail");
             println!("[ASP] 📋 This could mean:");
             println!("  - The commitment was never deposited");
             println!("  - The commitment format doesn't match (check BN254 vs Starknet Poseidon)");
-            println!("  - The syncer hasn't processed the event yet");
             println!("[ASP] ========================================\n");
             Json(serde_json::json!({
                 "found": false,
@@ -397,6 +627,57 @@ async fn get_deposit_index(
     }
 }
 
+/// Resolve many commitments to leaf indices in one call (local tree only)
+/// Commitments the syncer hasn't reached yet come back with `found: false`
+async fn get_deposit_indices(
+    State(state): State<AppState>,
+    Json(request): Json<BatchIndexRequest>,
+) -> impl IntoResponse {
+    use num_bigint::BigUint;
+    use num_traits::Num;
+
+    if request.commitments.len() > MAX_BATCH_COMMITMENTS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("At most {} commitments per request", MAX_BATCH_COMMITMENTS),
+        ).into_response();
+    }
+
+    let mut commitments = Vec::with_capacity(request.commitments.len());
+    for commitment in &request.commitments {
+        match BigUint::from_str_radix(commitment.trim_start_matches("0x"), 16) {
+            Ok(value) => commitments.push(value),
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid commitment format '{}': {}", commitment, e),
+                ).into_response()
+            }
+        }
+    }
+
+    let tree = state.deposit_tree.lock().unwrap();
+    let results: Vec<_> = request
+        .commitments
+        .iter()
+        .zip(&commitments)
+        .map(|(original, commitment)| {
+            let index = tree.find_commitment_index(commitment);
+            serde_json::json!({
+                "commitment": original,
+                "index": index,
+                "found": index.is_some()
+            })
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "results": results,
+        "found": results.iter().filter(|r| r["found"] == true).count(),
+        "tree_leaf_count": tree.get_leaf_count()
+    })).into_response()
+}
+
 /// List all deposits in the tree with their indices
 async fn list_deposits(State(state): State<AppState>) -> impl IntoResponse {
     let tree = state.deposit_tree.lock().unwrap();
@@ -445,6 +726,7 @@ async fn get_associated_info(State(state): State<AppState>) -> impl IntoResponse
         root: format!("0x{:x}", tree.get_root()),
         leaf_count: tree.get_leaf_count(),
         depth: tree.depth,
+        finalized_leaf_count: None,
     })
 }
 
@@ -490,6 +772,232 @@ async fn get_pool_root(State(state): State<AppState>) -> impl IntoResponse {
     }
 }
 
+// ==================== Pool Index Endpoints ====================
+
+/// Token balances of the Zylith contract, read with ERC20 balance_of
+/// They hold the pool's liquidity and the shielded notes alike: the contract keeps both
+async fn contract_balances(state: &AppState) -> Result<(BigUint, BigUint), String> {
+    let mut balances = Vec::with_capacity(2);
+    for token in [state.blockchain.get_pool_token0().await?, state.blockchain.get_pool_token1().await?] {
+        let (low, high) = state.blockchain.get_token_balance(&token, &state.zylith_address).await?;
+        balances.push((BigUint::from(high) << 128) + BigUint::from(low));
+    }
+    let balance1 = balances.pop().unwrap_or_default();
+    let balance0 = balances.pop().unwrap_or_default();
+    Ok((balance0, balance1))
+}
+
+/// Current price, tick and active liquidity from the pool event index, and the contract's
+/// token balances from the chain
+async fn get_pool_state(State(state): State<AppState>) -> impl IntoResponse {
+    let latest = match state.store.get_latest_pool_event().await {
+        Ok(Some(latest)) => latest,
+        Ok(None) => return (StatusCode::NOT_FOUND, "No pool events indexed yet").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let complete_history = match event_index_complete(&state).await {
+        Ok(complete) => complete,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    let pool = &latest.state;
+    // Events carry no reserves (see PoolState), so value locked comes from balance_of
+    let (balance0, balance1, tvl_token1) = match contract_balances(&state).await {
+        Ok((balance0, balance1)) => {
+            let tvl = pool.value_token1(&balance0, &balance1);
+            (Some(balance0.to_string()), Some(balance1.to_string()), Some(tvl))
+        }
+        Err(e) => {
+            eprintln!("[ASP] ⚠️  Failed to read contract token balances: {}", e);
+            (None, None, None)
+        }
+    };
+    Json(serde_json::json!({
+        "sqrt_price_x128": format!("0x{:x}", pool.sqrt_price_x128),
+        "price": pool.price(),
+        "tick": pool.tick,
+        "liquidity": pool.liquidity.to_string(),
+        "balance0": balance0,
+        "balance1": balance1,
+        "tvl_token1": tvl_token1,
+        "block_number": latest.block_number,
+        "updated_at": latest.block_timestamp,
+        "complete_history": complete_history
+    })).into_response()
+}
+
+/// The indexed tick map with the pool state it is checked against
+struct TickMapSnapshot {
+    latest: Option<pool_index::IndexedPoolEvent>,
+    ticks: ticks::TickMap,
+    complete_history: bool,
+    /// Why the map can't be trusted to match the contract's ticks, if it can't
+    unavailable: Option<String>,
+}
+
+async fn load_tick_snapshot(state: &AppState) -> Result<TickMapSnapshot, String> {
+    let latest = state.store.get_latest_pool_event().await?;
+    let ticks = state.store.load_tick_map().await?;
+    let complete_history = event_index_complete(state).await?;
+    let pool = latest.as_ref().map(|latest| (latest.state.tick, latest.state.liquidity));
+    let unavailable = ticks.unavailable_reason(pool, complete_history);
+    Ok(TickMapSnapshot { latest, ticks, complete_history, unavailable })
+}
+
+/// Initialized ticks with their liquidity, rebuilt from Mint and Burn events
+/// Answers 503 when the map can't be trusted rather than serving an empty or partial one
+async fn get_pool_ticks(State(state): State<AppState>) -> impl IntoResponse {
+    let TickMapSnapshot { latest, ticks, complete_history, unavailable } = match load_tick_snapshot(&state).await {
+        Ok(snapshot) => snapshot,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if let Some(reason) = unavailable {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
+            "error": format!("Tick map unavailable: {}", reason),
+            "available": false,
+            "complete_history": complete_history
+        }))).into_response();
+    }
+
+    // The ticks imply the active liquidity; it should match what the last swap reported
+    let pool = latest.map(|latest| {
+        let tick = latest.state.tick;
+        let implied = ticks.liquidity_at(tick);
+        serde_json::json!({
+            "tick": tick,
+            "sqrt_price_x128": format!("0x{:x}", latest.state.sqrt_price_x128),
+            "liquidity": latest.state.liquidity.to_string(),
+            "tick_liquidity": implied.to_string(),
+            "consistent": implied == latest.state.liquidity as i128,
+            "next_tick_below": ticks.next_initialized_tick(tick, ticks::MIN_TICK, true),
+            "next_tick_above": ticks.next_initialized_tick(tick, ticks::MAX_TICK, false),
+            "current_tick_net": ticks.get(tick).map(|info| info.liquidity_net.to_string())
+        })
+    });
+    let initialized: Vec<_> = ticks
+        .ticks()
+        .map(|(tick, info)| serde_json::json!({
+            "tick": tick,
+            "liquidity_gross": info.liquidity_gross.to_string(),
+            "liquidity_net": info.liquidity_net.to_string()
+        }))
+        .collect();
+
+    Json(serde_json::json!({
+        "available": true,
+        "tick_spacing": ticks::TICK_SPACING,
+        "pool": pool,
+        "ticks": initialized,
+        "complete_history": complete_history
+    })).into_response()
+}
+
+/// OHLC candles of the swap price
+async fn get_pool_candles(
+    State(state): State<AppState>,
+    Query(query): Query<SeriesQuery>,
+) -> impl IntoResponse {
+    let (interval, from, to) = match query.range() {
+        Ok(range) => range,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    match state.store.get_pool_events(from, to).await {
+        Ok(events) => Json(serde_json::json!({
+            "interval": interval,
+            "from": from,
+            "to": to,
+            "candles": pool_index::candles(&events, interval)
+        })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Swap volume per bucket
+async fn get_pool_volume(
+    State(state): State<AppState>,
+    Query(query): Query<SeriesQuery>,
+) -> impl IntoResponse {
+    let (interval, from, to) = match query.range() {
+        Ok(range) => range,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    match state.store.get_pool_events(from, to).await {
+        Ok(events) => {
+            let volume: Vec<_> = pool_index::candles(&events, interval)
+                .into_iter()
+                .map(|c| serde_json::json!({
+                    "time": c.time,
+                    "volume0": c.volume0,
+                    "volume1": c.volume1,
+                    "swaps": c.swaps
+                }))
+                .collect();
+            Json(serde_json::json!({
+                "interval": interval,
+                "from": from,
+                "to": to,
+                "volume": volume
+            })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Value locked per bucket, from the contract balances the syncer records after every block
+/// with Zylith events, valued at the pool price of that block
+async fn get_pool_tvl(
+    State(state): State<AppState>,
+    Query(query): Query<SeriesQuery>,
+) -> impl IntoResponse {
+    let (interval, from, to) = match query.range() {
+        Ok(range) => range,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    // Balances are only carried forward up to the bucket in progress
+    let to = to.min((reconcile::unix_now() / interval + 1) * interval);
+    match state.store.get_tvl_snapshots(from, to).await {
+        Ok(snapshots) => Json(serde_json::json!({
+            "interval": interval,
+            "from": from,
+            "to": to,
+            "tvl": pool_index::tvl_series(&snapshots, interval, from, to)
+        })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Most recent indexed pool events
+async fn get_recent_pool_events(
+    State(state): State<AppState>,
+    Query(query): Query<RecentEventsQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(50).min(MAX_RECENT_POOL_EVENTS);
+    match state.store.get_recent_pool_events(limit).await {
+        Ok(events) => {
+            let events: Vec<_> = events
+                .iter()
+                .map(|e| serde_json::json!({
+                    "id": e.id,
+                    "kind": e.kind,
+                    "block_number": e.block_number,
+                    "timestamp": e.block_timestamp,
+                    "tx_hash": e.tx_hash,
+                    "account": e.account,
+                    "tick_lower": e.tick_lower,
+                    "tick_upper": e.tick_upper,
+                    "liquidity_delta": e.liquidity_delta.map(|l| l.to_string()),
+                    "amount0": e.amount0.to_string(),
+                    "amount1": e.amount1.to_string(),
+                    "price": e.state.price(),
+                    "tick": e.state.tick
+                }))
+                .collect();
+            Json(serde_json::json!({ "events": events })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
 async fn check_pool_initialized(State(state): State<AppState>) -> impl IntoResponse {
     match state.blockchain.is_pool_initialized().await {
         Ok(initialized) => Json(serde_json::json!({ "initialized": initialized })).into_response(),
@@ -541,13 +1049,111 @@ async fn check_nullifier(
     Path(nullifier): Path<String>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
-    match state.blockchain.is_nullifier_spent(&nullifier).await {
-        Ok(spent) => Json(serde_json::json!({ "spent": spent })).into_response(),
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check nullifier: {}", e))
-                .into_response()
+    let (index_complete, synced_block) = match nullifier_index_coverage(&state).await {
+        Ok(coverage) => coverage,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    match lookup_nullifier(&state, &nullifier, index_complete).await {
+        Ok(mut result) => {
+            result["synced_block"] = serde_json::json!(synced_block);
+            Json(result).into_response()
+        }
+        Err(response) => response.into_response(),
+    }
+}
+
+/// Spent status of many nullifiers in one call, so wallets can find their spent notes
+async fn check_nullifiers(
+    State(state): State<AppState>,
+    Json(request): Json<BatchNullifierRequest>,
+) -> impl IntoResponse {
+    if request.nullifiers.len() > MAX_BATCH_NULLIFIERS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("At most {} nullifiers per request", MAX_BATCH_NULLIFIERS),
+        ).into_response();
+    }
+
+    let (index_complete, synced_block) = match nullifier_index_coverage(&state).await {
+        Ok(coverage) => coverage,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    let mut results = Vec::with_capacity(request.nullifiers.len());
+    for nullifier in &request.nullifiers {
+        match lookup_nullifier(&state, nullifier, index_complete).await {
+            Ok(result) => results.push(result),
+            Err(response) => return response.into_response(),
         }
     }
+
+    Json(serde_json::json!({
+        "results": results,
+        "spent": results.iter().filter(|r| r["spent"] == true).count(),
+        "synced_block": synced_block
+    })).into_response()
+}
+
+/// Whether the nullifier index covers every block since deployment, and the sync cursor
+async fn nullifier_index_coverage(state: &AppState) -> Result<(bool, u64), String> {
+    let complete = event_index_complete(state).await?;
+    let synced_block = state.store.get_last_synced_block().await?;
+    Ok((complete, synced_block))
+}
+
+/// Whether the nullifier and pool event indexes cover every block since deployment
+/// Databases synced before these indexes existed only cover recent blocks until a resync
+async fn event_index_complete(state: &AppState) -> Result<bool, String> {
+    let indexed_from = state.store.get_events_indexed_from().await?;
+    Ok(indexed_from.is_some_and(|from| from <= state.config.profile.deployment_block.max(1)))
+}
+
+/// Look up one nullifier in the local index, asking the contract only when the index
+/// has gaps and the nullifier isn't in it
+async fn lookup_nullifier(
+    state: &AppState,
+    nullifier: &str,
+    index_complete: bool,
+) -> Result<serde_json::Value, (StatusCode, String)> {
+    use num_traits::Num;
+
+    let value = BigUint::from_str_radix(nullifier.trim_start_matches("0x"), 16)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid nullifier format '{}': {}", nullifier, e)))?;
+
+    let indexed = state
+        .store
+        .find_nullifier_event(&value)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    if let Some(event) = indexed {
+        return Ok(serde_json::json!({
+            "nullifier": nullifier,
+            "spent": true,
+            "source": "nullifier_index",
+            "block_number": event.block_number,
+            "tx_hash": event.tx_hash
+        }));
+    }
+    if index_complete {
+        return Ok(serde_json::json!({
+            "nullifier": nullifier,
+            "spent": false,
+            "source": "nullifier_index"
+        }));
+    }
+
+    let spent = state
+        .blockchain
+        .is_nullifier_spent(&format!("0x{:x}", value))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check nullifier: {}", e)))?;
+    Ok(serde_json::json!({
+        "nullifier": nullifier,
+        "spent": spent,
+        "source": "contract"
+    }))
 }
 
 async fn get_token_balance(
@@ -624,7 +1230,7 @@ async fn prepare_deposit(
         }
     };
     
-    let (amount_low, amount_high) = u256_to_low_high(amount);
+    let (amount_low, amount_high) = u256_to_low_high(U256::from(amount));
 
     // Generate note (secret, nullifier)
     let (secret, nullifier) = generate_note();
@@ -690,9 +1296,117 @@ async fn prepare_deposit(
     .into_response()
 }
 
+#[derive(Deserialize)]
+struct SwapQuoteRequest {
+    amount_specified: String,
+    zero_for_one: bool,
+    /// Decimal, 0x-hex or "low,high"; defaults to the widest limit in the swap direction
+    sqrt_price_limit: Option<String>,
+}
+
+/// Public inputs of the swap circuit a quote can't fill: they depend on the caller's notes
+const SWAP_QUOTE_CLIENT_FIELDS: [&str; 2] = ["nullifier", "new_commitment"];
+
+/// Simulate a private swap against the indexed pool and fill the circuit's public inputs
+async fn quote_swap(
+    State(state): State<AppState>,
+    Json(payload): Json<SwapQuoteRequest>,
+) -> impl IntoResponse {
+    println!("[ASP] 📥 POST /api/swap/quote (amount: {}, zero_for_one: {})", payload.amount_specified, payload.zero_for_one);
+
+    let amount_specified = match payload.amount_specified.parse::<u128>() {
+        Ok(amount) if amount > 0 => amount,
+        _ => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "error": "amount_specified must be a positive u128"
+        }))).into_response(),
+    };
+    let sqrt_price_limit = match &payload.sqrt_price_limit {
+        Some(limit) => match clmm::parse_u256(limit) {
+            Ok(limit) => limit,
+            Err(e) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response(),
+        },
+        None if payload.zero_for_one => clmm::MIN_SQRT_RATIO,
+        None => clmm::MAX_SQRT_RATIO,
+    };
+
+    // Over an untrusted map the simulation would see no initialized tick and could quote a
+    // swap that crosses ticks the map doesn't know about, so no quote is made
+    let (latest, ticks) = match load_tick_snapshot(&state).await {
+        Ok(TickMapSnapshot { unavailable: Some(reason), .. }) => {
+            println!("[ASP] ⚠️  Quote refused: tick map unavailable ({})", reason);
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
+                "error": format!("Quote refused: tick map unavailable: {}", reason),
+                "tick_map_available": false
+            }))).into_response();
+        }
+        Ok(TickMapSnapshot { latest: Some(latest), ticks, .. }) => (latest, ticks),
+        Ok(TickMapSnapshot { latest: None, .. }) => return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
+            "error": "No pool state indexed yet"
+        }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e }))).into_response(),
+    };
+
+    let pool = &latest.state;
+    // private_swap reverts with NO_LIQUIDITY before swapping
+    if pool.liquidity == 0 {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "error": "Pool has no active liquidity"
+        }))).into_response();
+    }
+    let start = match clmm::u256_from_biguint(&pool.sqrt_price_x128) {
+        Ok(sqrt_price_x128) => clmm::PoolPrice { sqrt_price_x128, tick: pool.tick, liquidity: pool.liquidity },
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e }))).into_response(),
+    };
+
+    let quote = match clmm::simulate_swap(start, &ticks, payload.zero_for_one, amount_specified, sqrt_price_limit) {
+        Ok(quote) => quote,
+        Err(e) => {
+            println!("[ASP] ❌ Swap simulation failed: {}", e);
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                "error": format!("Swap would revert: {}", e)
+            }))).into_response();
+        }
+    };
+
+    let price_before = pool.price();
+    let price_after = pool_index::price_from_sqrt_x128(&clmm::biguint_from_u256(quote.end.sqrt_price_x128));
+    let price_impact_percent = if price_before > 0.0 { (price_after / price_before - 1.0) * 100.0 } else { 0.0 };
+    println!("[ASP] ✅ Quote: amount0={}, amount1={}, tick {} -> {}, {} tick(s) crossed",
+        quote.amount0, quote.amount1, start.tick, quote.end.tick, quote.ticks_crossed);
+    let root = format!("0x{:x}", state.deposit_tree.lock().unwrap().get_root());
+
+    Json(serde_json::json!({
+        // Public inputs, encoded as /api/proof/swap expects them; the caller adds the ones
+        // listed in client_fields from its own notes
+        "root": root,
+        "client_fields": SWAP_QUOTE_CLIENT_FIELDS,
+        "amount_specified": amount_specified.to_string(),
+        "zero_for_one": if payload.zero_for_one { "1" } else { "0" },
+        "amount0_delta": proof::i128_public_input(quote.amount0),
+        "amount1_delta": proof::i128_public_input(quote.amount1),
+        "new_sqrt_price_x128": quote.end.sqrt_price_x128.to_string(),
+        "new_tick": proof::i32_public_input(quote.end.tick),
+        // Pool inputs the circuit takes privately
+        "sqrt_price_old": start.sqrt_price_x128.to_string(),
+        "liquidity": start.liquidity.to_string(),
+        "amount0": quote.amount0.to_string(),
+        "amount1": quote.amount1.to_string(),
+        "tick_before": start.tick,
+        "tick_after": quote.end.tick,
+        "liquidity_after": quote.end.liquidity.to_string(),
+        "sqrt_price_limit_x128": quote.sqrt_price_limit_x128.to_string(),
+        "ticks_crossed": quote.ticks_crossed,
+        "steps": quote.steps,
+        "price_before": price_before,
+        "price_after": price_after,
+        "price_impact_percent": price_impact_percent,
+        "block_number": latest.block_number
+    })).into_response()
+}
+
 #[derive(Deserialize)]
 struct PrepareSwapRequest {
-    // Input note data (user must provide this)
+    // Input note data (user must provide this), checked against its leaf
     secret: String,
     nullifier: String,
     amount: String,
@@ -700,7 +1414,6 @@ struct PrepareSwapRequest {
     // Swap parameters
     amount_specified: String,
     zero_for_one: bool,
-    sqrt_price_limit: Option<String>, // Optional, format: "low,high"
     // Output note (will generate if not provided)
     new_secret: Option<String>,
     new_nullifier: Option<String>,
@@ -715,8 +1428,8 @@ struct SwapPrepareResponse {
 }
 
 async fn prepare_swap(
-    state: State<AppState>,
-    payload: Json<PrepareSwapRequest>,
+    State(state): State<AppState>,
+    Json(payload): Json<PrepareSwapRequest>,
 ) -> impl IntoResponse {
     println!("\n[ASP] ========================================");
     println!("[ASP] 📥 POST /api/swap/prepare - Request received");
@@ -729,25 +1442,31 @@ async fn prepare_swap(
     println!("[ASP]    Has new_nullifier: {}", payload.new_nullifier.is_some());
     println!("[ASP]    Has new_amount: {}", payload.new_amount.is_some());
     let start_time = std::time::Instant::now();
-    
-    // Get Merkle proof for input note
+
+    let mut v = Validator::new();
+    let secret = v.bn254("secret", &payload.secret);
+    let nullifier = v.bn254("nullifier", &payload.nullifier);
+    let amount = v.positive_u128("amount", &payload.amount);
+    v.u128("amount_specified", &payload.amount_specified);
+    check_output_note(&mut v, &payload.new_secret, &payload.new_nullifier);
+    if let Err(invalid) = v.finish() {
+        return invalid.into_response();
+    }
+
+    // Get Merkle proof for input note, refusing notes that are not the leaf or already spent
     println!("[ASP] 🔍 Fetching Merkle proof for index {}...", payload.note_index);
-    let deposit_tree = state.deposit_tree.lock().unwrap();
-    let merkle_proof = match deposit_tree.get_proof(payload.note_index) {
-        Some(proof) => {
-            println!("[ASP] ✅ Merkle proof found for index {}", payload.note_index);
-            println!("[ASP]    Root: {}", proof.root);
+    let merkle_proof = match verify_note(&state, &secret, &nullifier, amount, payload.note_index).await {
+        Ok(proof) => {
             println!("[ASP]    Path length: {}", proof.path.len());
             proof
         }
-        None => {
+        Err(response) => {
             let elapsed = start_time.elapsed().as_secs_f64();
-            println!("[ASP] ❌ Merkle proof not found for index {} (elapsed: {:.2}s)", payload.note_index, elapsed);
+            println!("[ASP] ❌ Input note rejected (elapsed: {:.2}s)", elapsed);
             println!("[ASP] ========================================\n");
-            return (StatusCode::NOT_FOUND, format!("Merkle proof not found for index {}", payload.note_index)).into_response();
+            return response;
         }
     };
-    drop(deposit_tree);
     
     // Generate output note if not provided
     let (new_secret, new_nullifier) = if let (Some(secret), Some(nullifier)) = (&payload.new_secret, &payload.new_nullifier) {
@@ -808,6 +1527,7 @@ struct SwapProofRequest {
     zero_for_one: String, // "0" or "1"
     amount0_delta: String,
     amount1_delta: String,
+    /// Decimal, 0x-hex or "low,high"
     new_sqrt_price_x128: String,
     new_tick: String,
     // Private inputs
@@ -820,14 +1540,19 @@ struct SwapProofRequest {
     path_elements: Vec<String>,
     #[serde(rename = "pathIndices")]
     path_indices: Vec<u32>,
+    /// Decimal, 0x-hex or "low,high"; must be the pool's current price
     sqrt_price_old: String,
     liquidity: String,
+    /// private_swap's sqrt_price_limit_x128 (decimal, 0x-hex or "low,high"); defaults to the
+    /// widest limit in the swap direction
+    sqrt_price_limit: Option<String>,
     // Note: pathElements and pathIndices are required (obtained from /api/swap/prepare)
     // Removed note_index fallback - frontend must call prepareSwap first
 }
 
 async fn generate_swap_proof_endpoint(
     state: State<AppState>,
+    Query(mode): Query<ProofModeQuery>,
     payload: Json<SwapProofRequest>,
 ) -> impl IntoResponse {
     println!("\n[ASP] ========================================");
@@ -835,226 +1560,202 @@ async fn generate_swap_proof_endpoint(
     println!("[ASP] ========================================");
     let start_time = std::time::Instant::now();
     
-    // Merkle proof must be provided in request (from prepareSwap)
-    // Frontend should call /api/swap/prepare first to get Merkle proof
-    if payload.path_elements.is_empty() || payload.path_indices.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "pathElements and pathIndices must be provided. Call /api/swap/prepare first to get Merkle proof."
-        }))).into_response();
-    }
-    
-    if payload.root.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "root must be provided. Call /api/swap/prepare first to get Merkle proof."
-        }))).into_response();
+    // Decode every field up front; out-of-range values would otherwise only fail in the witness
+    let mut v = Validator::new();
+    let nullifier = v.felt252("nullifier", &payload.nullifier);
+    let root = v.felt252("root", &payload.root);
+    let new_commitment = v.felt252("new_commitment", &payload.new_commitment);
+    let amount_specified = v.u128("amount_specified", &payload.amount_specified);
+    let zero_for_one = v.bit("zero_for_one", &payload.zero_for_one);
+    let amount0_delta = v.i128("amount0_delta", &payload.amount0_delta);
+    let amount1_delta = v.i128("amount1_delta", &payload.amount1_delta);
+    let new_sqrt_price_x128 = v.sqrt_price_x128("new_sqrt_price_x128", &payload.new_sqrt_price_x128);
+    let new_tick = v.tick("new_tick", &payload.new_tick);
+    let secret_in = v.bn254("secret_in", &payload.secret_in);
+    let amount_in = v.u128("amount_in", &payload.amount_in);
+    let secret_out = v.bn254("secret_out", &payload.secret_out);
+    let nullifier_out = v.bn254("nullifier_out", &payload.nullifier_out);
+    let amount_out = v.u128("amount_out", &payload.amount_out);
+    // Merkle proof from /api/swap/prepare
+    let (merkle_path, merkle_path_indices) = v.merkle_path(&payload.path_elements, &payload.path_indices);
+    let sqrt_price_old = v.sqrt_price_x128("sqrt_price_old", &payload.sqrt_price_old);
+    let liquidity = v.u128("liquidity", &payload.liquidity);
+    let sqrt_price_limit = match &payload.sqrt_price_limit {
+        Some(limit) => v.sqrt_price_x128("sqrt_price_limit", limit),
+        None if zero_for_one => clmm::MIN_SQRT_RATIO,
+        None => clmm::MAX_SQRT_RATIO,
+    };
+    if let Err(invalid) = v.finish() {
+        return invalid.into_response();
     }
-    
-    let merkle_path = payload.path_elements.clone();
-    let merkle_path_indices = payload.path_indices.clone();
-    let root = payload.root.clone();
-    
+
     println!("[ASP] ✅ Using Merkle proof from request (obtained via prepareSwap)");
     println!("[ASP]    Root: {}", root);
     println!("[ASP]    Path length: {}", merkle_path.len());
-    
-    // Parse amounts
-    let amount_in = match payload.amount_in.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid amount_in format"
+
+    // The swap starts from the pool's price and tick as indexed, never from the client's:
+    // the budget check below walks the tick map from there. A proof over another starting
+    // price would not verify against the pool anyway
+    // A map that can't be trusted would count no crossings, so the proof is refused instead
+    let (pool, ticks) = match load_tick_snapshot(&state).await {
+        Ok(TickMapSnapshot { unavailable: Some(reason), .. }) => {
+            println!("[ASP] ⚠️  Swap rejected: tick map unavailable ({})", reason);
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
+                "error": format!("Swap rejected: the tick budget can't be checked, tick map unavailable: {}", reason),
+                "tick_map_available": false
+            }))).into_response();
+        }
+        Ok(TickMapSnapshot { latest: Some(latest), ticks, .. }) => (latest.state, ticks),
+        Ok(TickMapSnapshot { latest: None, .. }) => return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
+            "error": "No pool state indexed yet"
+        }))).into_response(),
+        Err(e) => {
+            println!("[ASP] ❌ Failed to load tick map: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "error": format!("Failed to load tick map: {}", e)
             }))).into_response();
         }
     };
-    let amount_out = match payload.amount_out.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
+    let sqrt_price_from = match clmm::u256_from_biguint(&pool.sqrt_price_x128) {
+        Ok(sqrt_price) => sqrt_price,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e }))).into_response(),
+    };
+    if sqrt_price_old != sqrt_price_from {
+        println!("[ASP] ⚠️  Swap rejected: sqrt_price_old {} is not the pool price {}", sqrt_price_old, sqrt_price_from);
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "error": "Swap rejected: sqrt_price_old is not the pool's current price. Quote the swap again with /api/swap/quote.",
+            "sqrt_price_old": sqrt_price_old.to_string(),
+            "pool_sqrt_price_x128": sqrt_price_from.to_string(),
+            "pool_tick": pool.tick,
+        }))).into_response();
+    }
+    let sqrt_price_to = new_sqrt_price_x128;
+
+    // Validate swap complexity against the tick budget: count the initialized ticks between
+    // the pool's tick and the new price in the indexed tick map, as the contract's swap loop
+    // crosses them
+    let crossed = match clmm::crossed_ticks(&ticks, pool.tick, sqrt_price_to, zero_for_one) {
+        Ok(crossed) => crossed,
+        Err(e) => {
+            println!("[ASP] ⚠️  Swap rejected: {}", e);
             return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid amount_out format"
-            }))).into_response();
-        }
-    };
-    let amount_specified = match payload.amount_specified.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid amount_specified format"
-            }))).into_response();
-        }
-    };
-    
-    // Validate swap complexity before generating proof
-    // Calculate estimated ticks crossed based on price difference
-    let sqrt_price_old = match payload.sqrt_price_old.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid sqrt_price_old format"
+                "error": format!("Invalid sqrt price: {}", e)
             }))).into_response();
         }
     };
+    let tick_budget = state.config.swap_tick_budget;
+    let ticks_crossed = crossed.len();
 
-    let new_sqrt_price_x128 = match payload.new_sqrt_price_x128.parse::<u128>() {
-        Ok(v) => v,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid new_sqrt_price_x128 format"
-            }))).into_response();
-        }
-    };
-
-    // If frontend sends "0" (not yet implemented), use default Q128 (1:1 price)
-    // Q128 = 2^128 = 340282366920938463463374607431768211456
-    // BUT: u128::MAX = 2^128 - 1 = 340282366920938463463374607431768211455
-    // IMPORTANT: The circuit expects Q128 = 2^128, but Rust can't parse it
-    // Frontend sends U128_MAX string when value is Q128, we need to convert back to Q128 string for circuit
-    let q128: u128 = u128::MAX; // Use u128::MAX for Rust parsing
-    
-    let sqrt_price_old_final = if sqrt_price_old == 0 {
-        println!("[ASP] ⚠️  sqrt_price_old is zero, using default Q128 (1:1 price)");
-        q128
-    } else {
-        sqrt_price_old
-    };
-
-    let new_sqrt_price_x128_final = if new_sqrt_price_x128 == 0 {
-        println!("[ASP] ⚠️  new_sqrt_price_x128 is zero, using sqrt_price_old (no price change)");
-        sqrt_price_old_final
-    } else {
-        new_sqrt_price_x128
-    };
-    
-    // Convert u128::MAX back to Q128 string for circuit (circuit expects Q128 = 2^128)
-    // If value is u128::MAX, it means frontend sent Q128, so we send Q128 string to circuit
-    let sqrt_price_old_str = if sqrt_price_old_final == u128::MAX {
-        "340282366920938463463374607431768211456".to_string() // Q128 = 2^128
-    } else {
-        sqrt_price_old_final.to_string()
-    };
-    
-    let new_sqrt_price_x128_str = if new_sqrt_price_x128_final == u128::MAX {
-        "340282366920938463463374607431768211456".to_string() // Q128 = 2^128
-    } else {
-        new_sqrt_price_x128_final.to_string()
-    };
-
-    // Calculate price ratio to estimate ticks crossed
-    // tick = log(sqrt_price) / log(1.0001) ≈ log(sqrt_price) * 10000
-    // For quick estimation: price_ratio = new_price / old_price
-    let price_ratio = (new_sqrt_price_x128_final as f64) / (sqrt_price_old_final as f64);
-
-    // Estimate ticks: log(ratio) * 10000 / log(1.0001)
-    // Simplified: if ratio is 1.01, that's ~100 ticks
-    // For MVP: reject if price change > 5% (roughly >50 ticks)
-    let max_price_change_ratio = 1.05f64; // 5% max change
-    let min_price_change_ratio = 0.95f64; // -5% min change
-
-    if price_ratio > max_price_change_ratio || price_ratio < min_price_change_ratio {
-        let price_change_pct = if price_ratio > 1.0 {
-            (price_ratio - 1.0) * 100.0
-        } else {
-            (1.0 - price_ratio) * 100.0
+    if ticks_crossed > tick_budget as usize {
+        let split_limits = match clmm::tick_budget_limits(&crossed, tick_budget) {
+            Ok(limits) => limits,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e }))).into_response(),
         };
-        
-        println!("[ASP] ⚠️  Swap rejected: Price change too large ({:.2}%)", price_change_pct);
-        println!("[ASP]    sqrt_price_old: {}", sqrt_price_old_final);
-        println!("[ASP]    new_sqrt_price_x128: {}", new_sqrt_price_x128_final);
-        println!("[ASP]    Estimated ticks crossed: >50 (too many for MVP)");
-        
+        println!("[ASP] ⚠️  Swap rejected: crosses {} initialized ticks (budget: {})", ticks_crossed, tick_budget);
+        println!("[ASP]    sqrt_price_old: {}", sqrt_price_from);
+        println!("[ASP]    new_sqrt_price_x128: {}", sqrt_price_to);
+
         return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
             "error": format!(
-                "Swap rejected: Price change too large ({:.2}%). This swap would cross too many ticks (>50), making proof generation too slow. Please use a tighter sqrt_price_limit or split into smaller swaps.",
-                price_change_pct
+                "Swap rejected: it crosses {} initialized ticks and the swap circuit allows {} per proof. Use the suggested sqrt_price_limit or split it into {} swaps.",
+                ticks_crossed, tick_budget, split_limits.len() + 1
             ),
-            "price_change_percent": price_change_pct,
-            "sqrt_price_old": sqrt_price_old_final.to_string(),
-            "new_sqrt_price_x128": new_sqrt_price_x128_final.to_string(),
-            "suggestion": "Use a sqrt_price_limit closer to current price to limit ticks crossed"
+            "ticks_crossed": ticks_crossed,
+            "tick_budget": tick_budget,
+            "crossed_ticks": crossed,
+            "sqrt_price_old": sqrt_price_from.to_string(),
+            "new_sqrt_price_x128": sqrt_price_to.to_string(),
+            // A limit on the first tick past the budget stops the swap before crossing it
+            "suggested_sqrt_price_limit": split_limits[0].to_string(),
+            "suggested_splits": split_limits.len() + 1,
+            // Limit for each sub-swap but the last, which runs to the original price
+            "split_sqrt_price_limits": split_limits.iter().map(|limit| limit.to_string()).collect::<Vec<_>>(),
         }))).into_response();
     }
 
-    // Log estimated complexity
-    let estimated_ticks = (price_ratio.ln() * 10000.0).abs();
     println!("[ASP] 📊 Swap validation:");
-    println!("[ASP]    Price change: {:.2}%", (price_ratio - 1.0) * 100.0);
-    println!("[ASP]    Estimated ticks crossed: ~{:.0}", estimated_ticks);
+    println!("[ASP]    Ticks crossed: {} (budget: {}, {} initialized in map)", ticks_crossed, tick_budget, ticks.ticks().count());
     println!("[ASP]    Estimated proof time: {} minutes", 
-        if estimated_ticks < 5.0 { "1-2" } 
-        else if estimated_ticks < 10.0 { "2-4" } 
+        if ticks_crossed < 5 { "1-2" } 
+        else if ticks_crossed < 10 { "2-4" } 
         else { "4-10" });
     println!("[ASP]    Amount specified: {}", amount_specified);
-    println!("[ASP]    Zero for one: {}", payload.zero_for_one);
-    
-    // Get circuits path (relative to ASP directory, go up to project root)
-    let circuits_path = std::env::current_dir()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("circuits")
-        .to_str()
-        .unwrap()
-        .to_string();
+    println!("[ASP]    Zero for one: {}", zero_for_one);
     
-    // Build input JSON directly from request payload (frontend already formats it correctly)
-    // Update root and pathElements/pathIndices if we fetched them
+    // Build input JSON from the decoded fields; signed values in the two's complement
+    // encoding the contract decodes
     let input_json = serde_json::json!({
-        "nullifier": payload.nullifier,
-        "root": root,
-        "new_commitment": payload.new_commitment,
-        "amount_specified": payload.amount_specified,
-        "zero_for_one": payload.zero_for_one,
-        "amount0_delta": payload.amount0_delta,
-        "amount1_delta": payload.amount1_delta,
-        "new_sqrt_price_x128": new_sqrt_price_x128_str.clone(),
-        "new_tick": payload.new_tick,
-        "secret_in": payload.secret_in,
-        "amount_in": payload.amount_in,
-        "secret_out": payload.secret_out,
-        "nullifier_out": payload.nullifier_out,
-        "amount_out": payload.amount_out,
-        "pathElements": merkle_path,
+        "nullifier": nullifier.to_string(),
+        "root": root.to_string(),
+        "new_commitment": new_commitment.to_string(),
+        "amount_specified": amount_specified.to_string(),
+        "zero_for_one": if zero_for_one { "1" } else { "0" },
+        "amount0_delta": proof::i128_public_input(amount0_delta),
+        "amount1_delta": proof::i128_public_input(amount1_delta),
+        "new_sqrt_price_x128": sqrt_price_to.to_string(),
+        "new_tick": proof::i32_public_input(new_tick),
+        "secret_in": secret_in.to_string(),
+        "amount_in": amount_in.to_string(),
+        "secret_out": secret_out.to_string(),
+        "nullifier_out": nullifier_out.to_string(),
+        "amount_out": amount_out.to_string(),
+        "pathElements": merkle_path.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
         "pathIndices": merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
-        "sqrt_price_old": sqrt_price_old_str.clone(),
-        "liquidity": payload.liquidity,
+        "sqrt_price_old": sqrt_price_from.to_string(),
+        "liquidity": liquidity.to_string(),
     });
     
     println!("[ASP] 🔧 Generating ZK proof...");
-    println!("[ASP]    Circuits path: {}", circuits_path);
+    println!("[ASP]    Circuits path: {:?}", state.artifacts.circuits_dir());
     
     // Generate proof - pass JSON directly to proof generator
-    match proof::generate_swap_proof(&circuits_path, input_json).await {
-        Ok(swap_proof) => {
-            let elapsed = start_time.elapsed().as_secs_f64();
-            println!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
-            println!("[ASP]    Proof length: {}, Public inputs: {}", 
-                swap_proof.proof.len(), swap_proof.public_inputs.len());
-            
-            // Log the actual values being returned
-            println!("[ASP] 📋 Returning proof with {} elements:", swap_proof.proof.len());
-            for (i, val) in swap_proof.proof.iter().enumerate() {
-                println!("[ASP]    proof[{}]: {}", i, val);
-            }
-            println!("[ASP] 📋 Returning public_inputs with {} elements:", swap_proof.public_inputs.len());
-            for (i, val) in swap_proof.public_inputs.iter().enumerate() {
-                println!("[ASP]    public_inputs[{}]: {}", i, val);
-            }
-            
-            println!("[ASP] ========================================\n");
-            
-            Json(serde_json::json!({
-                "full_proof_with_hints": swap_proof.proof,
-                "public_inputs": swap_proof.public_inputs,
-            })).into_response()
-        }
-        Err(e) => {
+    let (artifacts, zylith_address) = (state.artifacts.clone(), state.zylith_address.clone());
+    let new_commitment = format!("0x{:x}", new_commitment);
+    run_proof_job(&state, "swap", &mode, async move {
+        let swap_proof = proof::generate_swap_proof(&artifacts, input_json).await.map_err(|e| {
             let elapsed = start_time.elapsed().as_secs_f64();
             println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
             println!("[ASP] ========================================\n");
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Proof generation failed: {}", e)
-            }))).into_response()
+            e.context("Proof generation failed")
+        })?;
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
+        println!("[ASP]    Proof length: {}, Public inputs: {}", 
+            swap_proof.proof.len(), swap_proof.public_inputs.len());
+        
+        // Log the actual values being returned
+        println!("[ASP] 📋 Returning proof with {} elements:", swap_proof.proof.len());
+        for (i, val) in swap_proof.proof.iter().enumerate() {
+            println!("[ASP]    proof[{}]: {}", i, val);
         }
-    }
+        println!("[ASP] 📋 Returning public_inputs with {} elements:", swap_proof.public_inputs.len());
+        for (i, val) in swap_proof.public_inputs.iter().enumerate() {
+            println!("[ASP]    public_inputs[{}]: {}", i, val);
+        }
+        
+        let calldata = build_swap_calldata(
+            &swap_proof.proof,
+            &swap_proof.public_inputs,
+            zero_for_one,
+            amount_specified,
+            &sqrt_price_limit.to_string(),
+            &new_commitment,
+        )
+        .map_err(|e| format!("Failed to build calldata: {}", e))?;
+
+        println!("[ASP] ========================================\n");
+        
+        Ok(serde_json::json!({
+            "full_proof_with_hints": swap_proof.proof,
+            "public_inputs": swap_proof.public_inputs,
+            "transactions": [PreparedTransaction {
+                contract_address: zylith_address,
+                entry_point: "private_swap".to_string(),
+                calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+            }],
+        }))
+    })
+    .await
 }
 
 #[derive(Deserialize)]
@@ -1080,7 +1781,8 @@ struct LpProofRequest {
 }
 
 async fn generate_lp_proof_endpoint(
-    _state: State<AppState>,
+    state: State<AppState>,
+    Query(mode): Query<ProofModeQuery>,
     payload: Json<LpProofRequest>,
 ) -> impl IntoResponse {
     println!("\n[ASP] ========================================");
@@ -1088,92 +1790,171 @@ async fn generate_lp_proof_endpoint(
     println!("[ASP] ========================================");
     let start_time = std::time::Instant::now();
     
-    // Merkle proof must be provided in request
-    if payload.path_elements.is_empty() || payload.path_indices.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "pathElements and pathIndices must be provided."
-        }))).into_response();
+    let mut v = Validator::new();
+    let nullifier = v.felt252("nullifier", &payload.nullifier);
+    let root = v.felt252("root", &payload.root);
+    let tick_lower = v.tick("tick_lower", &payload.tick_lower);
+    let tick_upper = v.tick("tick_upper", &payload.tick_upper);
+    if tick_lower > tick_upper {
+        // The circuit range-checks tick_upper - tick_lower
+        v.reject("tick_upper", tick_upper, format!("must not be below tick_lower {}", tick_lower));
     }
-    
-    if payload.root.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "root must be provided."
-        }))).into_response();
+    let liquidity = v.u128("liquidity", &payload.liquidity);
+    let new_commitment = v.felt252("new_commitment", &payload.new_commitment);
+    let position_commitment = v.felt252("position_commitment", &payload.position_commitment);
+    let secret_in = v.bn254("secret_in", &payload.secret_in);
+    let amount_in = v.u128("amount_in", &payload.amount_in);
+    if amount_in < liquidity {
+        // ... and amount_in - liquidity
+        v.reject("liquidity", liquidity, format!("exceeds amount_in {}", amount_in));
+    }
+    let secret_out = v.bn254("secret_out", &payload.secret_out);
+    let nullifier_out = v.bn254("nullifier_out", &payload.nullifier_out);
+    let amount_out = v.u128("amount_out", &payload.amount_out);
+    let (merkle_path, merkle_path_indices) = v.merkle_path(&payload.path_elements, &payload.path_indices);
+    if let Err(invalid) = v.finish() {
+        return invalid.into_response();
     }
-    
-    let merkle_path = payload.path_elements.clone();
-    let merkle_path_indices = payload.path_indices.clone();
-    let root = payload.root.clone();
     
     println!("[ASP] ✅ Using Merkle proof from request");
     println!("[ASP]    Root: {}", root);
     println!("[ASP]    Path length: {}", merkle_path.len());
     
-    // Get circuits path (relative to ASP directory, go up to project root)
-    let circuits_path = std::env::current_dir()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("circuits")
-        .to_str()
-        .unwrap()
-        .to_string();
-    
-    // Build input JSON directly from request payload
+    // Build input JSON from the decoded fields, ticks as signed decimals
     let input_json = serde_json::json!({
-        "nullifier": payload.nullifier,
-        "root": root,
-        "tick_lower": payload.tick_lower,
-        "tick_upper": payload.tick_upper,
-        "liquidity": payload.liquidity,
-        "new_commitment": payload.new_commitment,
-        "position_commitment": payload.position_commitment,
-        "secret_in": payload.secret_in,
-        "amount_in": payload.amount_in,
-        "secret_out": payload.secret_out,
-        "nullifier_out": payload.nullifier_out,
-        "amount_out": payload.amount_out,
-        "pathElements": merkle_path,
+        "nullifier": nullifier.to_string(),
+        "root": root.to_string(),
+        "tick_lower": tick_lower.to_string(),
+        "tick_upper": tick_upper.to_string(),
+        "liquidity": liquidity.to_string(),
+        "new_commitment": new_commitment.to_string(),
+        "position_commitment": position_commitment.to_string(),
+        "secret_in": secret_in.to_string(),
+        "amount_in": amount_in.to_string(),
+        "secret_out": secret_out.to_string(),
+        "nullifier_out": nullifier_out.to_string(),
+        "amount_out": amount_out.to_string(),
+        "pathElements": merkle_path.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
         "pathIndices": merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
     });
     
     println!("[ASP] 🔧 Generating ZK proof with rapidsnark...");
-    println!("[ASP]    Circuits path: {}", circuits_path);
+    println!("[ASP]    Circuits path: {:?}", state.artifacts.circuits_dir());
     
     // Generate proof using rapidsnark
-    match proof::generate_lp_proof(&circuits_path, input_json).await {
-        Ok(lp_proof) => {
+    let artifacts = state.artifacts.clone();
+    run_proof_job(&state, "lp", &mode, async move {
+        let lp_proof = proof::generate_lp_proof(&artifacts, input_json).await.map_err(|e| {
             let elapsed = start_time.elapsed().as_secs_f64();
-            println!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
-            println!("[ASP]    Proof length: {}, Public inputs: {}", 
-                lp_proof.proof.len(), lp_proof.public_inputs.len());
-            
-            // Log the actual values being returned
-            println!("[ASP] 📋 Returning proof with {} elements:", lp_proof.proof.len());
-            for (i, val) in lp_proof.proof.iter().enumerate() {
-                println!("[ASP]    proof[{}]: {}", i, val);
-            }
-            println!("[ASP] 📋 Returning public_inputs with {} elements:", lp_proof.public_inputs.len());
-            for (i, val) in lp_proof.public_inputs.iter().enumerate() {
-                println!("[ASP]    public_inputs[{}]: {}", i, val);
-            }
-            
+            println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
             println!("[ASP] ========================================\n");
-            
-            Json(serde_json::json!({
-                "full_proof_with_hints": lp_proof.proof,
-                "public_inputs": lp_proof.public_inputs,
-            })).into_response()
+            e.context("Proof generation failed")
+        })?;
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[ASP] ✅ ZK proof generated successfully in {:.2}s", elapsed);
+        println!("[ASP]    Proof length: {}, Public inputs: {}", 
+            lp_proof.proof.len(), lp_proof.public_inputs.len());
+        
+        // Log the actual values being returned
+        println!("[ASP] 📋 Returning proof with {} elements:", lp_proof.proof.len());
+        for (i, val) in lp_proof.proof.iter().enumerate() {
+            println!("[ASP]    proof[{}]: {}", i, val);
         }
-        Err(e) => {
+        println!("[ASP] 📋 Returning public_inputs with {} elements:", lp_proof.public_inputs.len());
+        for (i, val) in lp_proof.public_inputs.iter().enumerate() {
+            println!("[ASP]    public_inputs[{}]: {}", i, val);
+        }
+        
+        println!("[ASP] ========================================\n");
+        
+        Ok(serde_json::json!({
+            "full_proof_with_hints": lp_proof.proof,
+            "public_inputs": lp_proof.public_inputs,
+        }))
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct MembershipProofRequest {
+    // Note whose commitment is in the associated set
+    secret: String,
+    nullifier: String,
+    amount: String,
+}
+
+/// Prove that a note's commitment is in the associated set under the current associated root,
+/// and return the calldata of a direct call to the membership verifier
+async fn generate_membership_proof_endpoint(
+    State(state): State<AppState>,
+    Query(mode): Query<ProofModeQuery>,
+    Json(payload): Json<MembershipProofRequest>,
+) -> impl IntoResponse {
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 📥 POST /api/proof/membership - ZK Proof generation request");
+    println!("[ASP] ========================================");
+    let start_time = std::time::Instant::now();
+
+    let mut v = Validator::new();
+    let secret = v.bn254("secret", &payload.secret);
+    let nullifier = v.bn254("nullifier", &payload.nullifier);
+    let amount = v.u128("amount", &payload.amount);
+    if let Err(invalid) = v.finish() {
+        return invalid.into_response();
+    }
+
+    let commitment = match generate_commitment(&format!("0x{:x}", secret), &format!("0x{:x}", nullifier), amount)
+        .and_then(|commitment| parse_field_element("commitment", &commitment))
+    {
+        Ok(commitment) => commitment,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "error": format!("Failed to compute note commitment: {}", e)
+        }))).into_response(),
+    };
+
+    // Path to the current associated root
+    let merkle_proof = {
+        let tree = state.associated_tree.lock().unwrap();
+        tree.find_commitment_index(&commitment).and_then(|index| tree.get_proof(index))
+    };
+    let Some(merkle_proof) = merkle_proof else {
+        println!("[ASP] ❌ Commitment 0x{:x} is not in the associated set", commitment);
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "error": "Note commitment is not in the associated set",
+            "commitment": format!("0x{:x}", commitment),
+        }))).into_response();
+    };
+    println!("[ASP] ✅ Commitment found in the associated set (root {})", merkle_proof.root);
+
+    let inputs = proof::MembershipProofInputs {
+        secret: secret.to_string(),
+        nullifier: nullifier.to_string(),
+        amount,
+        commitment: commitment.to_string(),
+        merkle_path: merkle_proof.path.clone(),
+        merkle_path_indices: merkle_proof.path_indices.clone(),
+        root: merkle_proof.root.clone(),
+    };
+    let artifacts = state.artifacts.clone();
+    run_proof_job(&state, "membership", &mode, async move {
+        let membership_proof = proof::generate_membership_proof(&artifacts, inputs).await.map_err(|e| {
             let elapsed = start_time.elapsed().as_secs_f64();
             println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
             println!("[ASP] ========================================\n");
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Proof generation failed: {}", e)
-            }))).into_response()
-        }
-    }
+            e.context("Proof generation failed")
+        })?;
+
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[ASP] ✅ Membership proof generated in {:.2}s", elapsed);
+        println!("[ASP] ========================================\n");
+
+        // The proof and its public inputs [root, commitment] only: no verifier calldata
+        Ok(serde_json::json!({
+            "proof": membership_proof.proof,
+            "public_inputs": membership_proof.public_inputs,
+        }))
+    })
+    .await
 }
 
 #[derive(Deserialize)]
@@ -1181,19 +1962,167 @@ struct PrepareWithdrawRequest {
     // Input note data (user must provide this)
     secret: String,
     nullifier: String,
+    /// The note's full amount: the withdraw circuit has no change note, so a withdrawal
+    /// always empties the note
     amount: String,
     note_index: u32, // For getting Merkle proof
     // Withdraw parameters
     recipient: String,
-    token_address: Option<String>, // Optional, will use note's token if not provided
+    /// Token the note was deposited in (the ASP does not track note tokens); must be one of
+    /// the pool's tokens
+    token_address: String,
 }
 
+/// Merkle proof of an unspent note the caller owns: the note's commitment must be the leaf at
+/// `note_index` and its nullifier must not be spent (either would only fail on-chain after
+/// minutes of proving)
+async fn verify_note(
+    state: &AppState,
+    secret: &BigUint,
+    nullifier: &BigUint,
+    amount: u128,
+    note_index: u32,
+) -> Result<MerkleProof, axum::response::Response> {
+    let error = |status: StatusCode, error: String| (status, Json(serde_json::json!({ "error": error }))).into_response();
+
+    let merkle_proof = match state.deposit_tree.lock().unwrap().get_proof(note_index) {
+        Some(proof) => proof,
+        None => {
+            println!("[ASP] ❌ Merkle proof not found for index {}", note_index);
+            return Err(error(StatusCode::NOT_FOUND, format!("Merkle proof not found for index {}", note_index)));
+        }
+    };
+    let commitment = generate_commitment(&format!("0x{:x}", secret), &format!("0x{:x}", nullifier), amount)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, format!("Failed to compute note commitment: {}", e)))?;
+    let matches_leaf = parse_field_element("commitment", &commitment)
+        .and_then(|commitment| Ok(commitment == parse_field_element("leaf", &merkle_proof.leaf)?))
+        .unwrap_or(false);
+    if !matches_leaf {
+        return Err(error(StatusCode::BAD_REQUEST, format!(
+            "Note does not match the commitment at index {} (expected {}, got {})",
+            note_index, merkle_proof.leaf, commitment
+        )));
+    }
+
+    let index_complete = event_index_complete(state)
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let status = lookup_nullifier(state, &format!("0x{:x}", nullifier), index_complete)
+        .await
+        .map_err(|(status, e)| error(status, e))?;
+    if status["spent"] == true {
+        return Err((StatusCode::CONFLICT, Json(serde_json::json!({
+            "error": "Note already spent",
+            "nullifier": status["nullifier"],
+        }))).into_response());
+    }
+
+    println!("[ASP] ✅ Note verified against leaf {} (root {})", note_index, merkle_proof.root);
+    Ok(merkle_proof)
+}
+
+/// Range checks of a caller-supplied output note; a missing half means one is generated
+fn check_output_note(v: &mut Validator, new_secret: &Option<String>, new_nullifier: &Option<String>) {
+    if let (Some(new_secret), Some(new_nullifier)) = (new_secret, new_nullifier) {
+        v.bn254("new_secret", new_secret);
+        v.bn254("new_nullifier", new_nullifier);
+    }
+}
+
+/// Prove a full-note withdraw and return ready-to-sign private_withdraw calldata
 async fn prepare_withdraw(
-    _state: State<AppState>,
-    _payload: Json<PrepareWithdrawRequest>,
+    State(state): State<AppState>,
+    Query(mode): Query<ProofModeQuery>,
+    Json(payload): Json<PrepareWithdrawRequest>,
 ) -> impl IntoResponse {
-    // TODO: Implement withdraw preparation with ZK proof generation
-    (StatusCode::NOT_IMPLEMENTED, "ZK proof generation not yet implemented")
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 📥 POST /api/withdraw/prepare - Request received");
+    println!("[ASP] ========================================");
+    println!("[ASP]    Note index: {}", payload.note_index);
+    println!("[ASP]    Recipient: {}", payload.recipient);
+    let start_time = std::time::Instant::now();
+
+    let mut v = Validator::new();
+    let secret = v.bn254("secret", &payload.secret);
+    let nullifier = v.bn254("nullifier", &payload.nullifier);
+    let amount = v.positive_u128("amount", &payload.amount);
+    let recipient = v.felt252("recipient", &payload.recipient);
+    if recipient == BigUint::from(0u8) && !v.rejected("recipient") {
+        v.reject("recipient", &payload.recipient, "must not be zero");
+    }
+    let token_address = v.felt252("token_address", &payload.token_address);
+    if !v.rejected("token_address") {
+        let is_pool_token = |token: &zylith_asp::config::TokenConfig| {
+            parse_field_element("token", &token.address).as_ref() == Ok(&token_address)
+        };
+        match state.config.pool_tokens() {
+            Some((token0, token1)) if is_pool_token(token0) || is_pool_token(token1) => {}
+            Some((token0, token1)) => v.reject(
+                "token_address",
+                &payload.token_address,
+                format!(
+                    "must be one of the pool's tokens ({} {} or {} {})",
+                    token0.symbol, token0.address, token1.symbol, token1.address
+                ),
+            ),
+            None => v.reject("token_address", &payload.token_address, "can't be checked: the network profile has no token pair"),
+        }
+    }
+    if let Err(invalid) = v.finish() {
+        return invalid.into_response();
+    }
+
+    let merkle_proof = match verify_note(&state, &secret, &nullifier, amount, payload.note_index).await {
+        Ok(proof) => proof,
+        Err(response) => return response,
+    };
+
+    println!("[ASP] 🔧 Generating ZK proof...");
+
+    let inputs = proof::WithdrawProofInputs {
+        secret: secret.to_string(),
+        nullifier: nullifier.to_string(),
+        amount,
+        merkle_path: merkle_proof.path.clone(),
+        merkle_path_indices: merkle_proof.path_indices.clone(),
+        root: merkle_proof.root.clone(),
+        recipient: recipient.to_string(),
+    };
+    let (artifacts, zylith_address) = (state.artifacts.clone(), state.zylith_address.clone());
+    run_proof_job(&state, "withdraw", &mode, async move {
+        let withdraw_proof = proof::generate_withdraw_proof(&artifacts, inputs).await.map_err(|e| {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
+            println!("[ASP] ========================================\n");
+            e.context("Proof generation failed")
+        })?;
+
+        let calldata = build_withdraw_calldata(
+            &withdraw_proof.proof,
+            &withdraw_proof.public_inputs,
+            &payload.token_address,
+            &format!("0x{:x}", recipient),
+            amount,
+        )
+        .map_err(|e| format!("Failed to build withdraw calldata: {}", e))?;
+
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[ASP] ✅ Withdraw prepared in {:.2}s", elapsed);
+        println!("[ASP] ========================================\n");
+
+        Ok(serde_json::json!({
+            "transactions": [PreparedTransaction {
+                contract_address: zylith_address,
+                entry_point: "private_withdraw".to_string(),
+                calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+            }],
+            "nullifier": format!("0x{:x}", nullifier),
+            "root": merkle_proof.root,
+            "proof": withdraw_proof.proof,
+            "public_inputs": withdraw_proof.public_inputs,
+        }))
+    })
+    .await
 }
 
 #[derive(Deserialize)]
@@ -1210,23 +2139,415 @@ struct PrepareLiquidityRequest {
     // Output note
     new_secret: Option<String>,
     new_nullifier: Option<String>,
+    /// Must be amount - liquidity (the LP circuit's conservation rule) when given
     new_amount: Option<String>,
 }
 
+#[derive(Serialize)]
+struct LiquidityPrepareResponse {
+    transactions: Vec<PreparedTransaction>,
+    new_commitment: String,
+    output_note_data: NoteData,
+    position_commitment: String,
+    liquidity: String,
+    /// Tokens the contract accounts for the position (mint) or returns (burn, before protocol fees)
+    amount0: String,
+    amount1: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LiquidityOperation {
+    Mint,
+    Burn,
+}
+
+impl LiquidityOperation {
+    /// Path segment and proof job kind
+    fn name(self) -> &'static str {
+        match self {
+            LiquidityOperation::Mint => "mint",
+            LiquidityOperation::Burn => "burn",
+        }
+    }
+
+    fn entry_point(self) -> &'static str {
+        match self {
+            LiquidityOperation::Mint => "private_mint_liquidity",
+            LiquidityOperation::Burn => "private_burn_liquidity",
+        }
+    }
+}
+
 async fn prepare_mint_liquidity(
-    _state: State<AppState>,
-    _payload: Json<PrepareLiquidityRequest>,
+    State(state): State<AppState>,
+    Query(mode): Query<ProofModeQuery>,
+    Json(payload): Json<PrepareLiquidityRequest>,
 ) -> impl IntoResponse {
-    // TODO: Implement mint liquidity preparation with ZK proof generation
-    (StatusCode::NOT_IMPLEMENTED, "ZK proof generation not yet implemented")
+    prepare_liquidity(&state, &mode, payload, LiquidityOperation::Mint).await
 }
 
 async fn prepare_burn_liquidity(
-    _state: State<AppState>,
-    _payload: Json<PrepareLiquidityRequest>,
+    State(state): State<AppState>,
+    Query(mode): Query<ProofModeQuery>,
+    Json(payload): Json<PrepareLiquidityRequest>,
 ) -> impl IntoResponse {
-    // TODO: Implement burn liquidity preparation with ZK proof generation
-    (StatusCode::NOT_IMPLEMENTED, "ZK proof generation not yet implemented")
+    prepare_liquidity(&state, &mode, payload, LiquidityOperation::Burn).await
+}
+
+/// Price the position, build the change note, prove the LP circuit and return
+/// private_mint_liquidity / private_burn_liquidity calldata
+async fn prepare_liquidity(
+    state: &AppState,
+    mode: &ProofModeQuery,
+    payload: PrepareLiquidityRequest,
+    operation: LiquidityOperation,
+) -> axum::response::Response {
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 📥 POST /api/liquidity/{}/prepare - Request received", operation.name());
+    println!("[ASP] ========================================");
+    println!("[ASP]    Note index: {}", payload.note_index);
+    println!("[ASP]    Range: [{}, {}], liquidity: {}", payload.tick_lower, payload.tick_upper, payload.liquidity);
+    let start_time = std::time::Instant::now();
+
+    let error = |status: StatusCode, error: String| (status, Json(serde_json::json!({ "error": error }))).into_response();
+    let bad_request = |e: String| error(StatusCode::BAD_REQUEST, e);
+
+    let mut v = Validator::new();
+    let secret = v.bn254("secret", &payload.secret);
+    let nullifier = v.bn254("nullifier", &payload.nullifier);
+    let amount = v.u128("amount", &payload.amount);
+    let liquidity = v.positive_u128("liquidity", &payload.liquidity);
+    // The LP circuit spends `liquidity` from the note and keeps the rest as change
+    let new_amount = amount.checked_sub(liquidity).unwrap_or_else(|| {
+        v.reject("liquidity", liquidity, format!("exceeds the note amount {}", amount));
+        0
+    });
+    if let Some(requested) = &payload.new_amount {
+        if requested.parse::<u128>().ok() != Some(new_amount) {
+            v.reject("new_amount", requested, format!("must be amount - liquidity ({})", new_amount));
+        }
+    }
+    v.check_tick("tick_lower", payload.tick_lower);
+    v.check_tick("tick_upper", payload.tick_upper);
+    check_output_note(&mut v, &payload.new_secret, &payload.new_nullifier);
+    if let Err(invalid) = v.finish() {
+        return invalid.into_response();
+    }
+    let (tick_lower, tick_upper) = (payload.tick_lower, payload.tick_upper);
+
+    // Token amounts, checking the range like the contract does
+    let amounts = match operation {
+        LiquidityOperation::Mint => {
+            let latest = match state.store.get_latest_pool_event().await {
+                Ok(Some(latest)) => latest,
+                Ok(None) => return error(StatusCode::SERVICE_UNAVAILABLE, "No pool state indexed yet".to_string()),
+                Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+            };
+            clmm::u256_from_biguint(&latest.state.sqrt_price_x128).and_then(|sqrt_price_x128| {
+                clmm::get_private_mint_amounts(latest.state.tick, sqrt_price_x128, tick_lower, tick_upper, liquidity)
+            })
+        }
+        LiquidityOperation::Burn => clmm::get_private_burn_amounts(tick_lower, tick_upper, liquidity),
+    };
+    let (amount0, amount1) = match amounts {
+        Ok(amounts) => amounts,
+        Err(e) => return bad_request(e),
+    };
+    println!("[ASP]    Token amounts: amount0={}, amount1={}", amount0, amount1);
+
+    let merkle_proof = match verify_note(state, &secret, &nullifier, amount, payload.note_index).await {
+        Ok(proof) => proof,
+        Err(response) => return response,
+    };
+
+    // Change note (generated unless the caller brings its own secrets)
+    let (new_secret, new_nullifier) = match (&payload.new_secret, &payload.new_nullifier) {
+        (Some(new_secret), Some(new_nullifier)) => (new_secret.clone(), new_nullifier.clone()),
+        _ => generate_note(),
+    };
+    let new_note = parse_field_element("new_secret", &new_secret)
+        .and_then(|new_secret| Ok((new_secret, parse_field_element("new_nullifier", &new_nullifier)?)));
+    let (new_secret_value, new_nullifier_value) = match new_note {
+        Ok(new_note) => new_note,
+        Err(e) => return bad_request(e),
+    };
+    let new_commitment = match generate_commitment(
+        &format!("0x{:x}", new_secret_value),
+        &format!("0x{:x}", new_nullifier_value),
+        new_amount,
+    ) {
+        Ok(commitment) => commitment,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate change commitment: {}", e)),
+    };
+    let secret_hex = format!("0x{:x}", secret);
+    let position_commitment = match tokio::task::spawn_blocking(move || {
+        commitment::generate_position_commitment(&secret_hex, tick_lower, tick_upper)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result)
+    {
+        Ok(commitment) => commitment,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate position commitment: {}", e)),
+    };
+
+    println!("[ASP] 🔧 Generating ZK proof...");
+    let inputs = proof::MintProofInputs {
+        secret: secret.to_string(),
+        nullifier: nullifier.to_string(),
+        amount,
+        merkle_path: merkle_proof.path,
+        merkle_path_indices: merkle_proof.path_indices,
+        root: merkle_proof.root,
+        tick_lower,
+        tick_upper,
+        liquidity,
+        new_secret: new_secret_value.to_string(),
+        new_nullifier: new_nullifier_value.to_string(),
+        new_amount,
+        new_commitment: new_commitment.clone(),
+        position_commitment: position_commitment.clone(),
+    };
+    let (artifacts, zylith_address) = (state.artifacts.clone(), state.zylith_address.clone());
+    run_proof_job(state, operation.name(), mode, async move {
+        let proved = match operation {
+            LiquidityOperation::Mint => proof::generate_mint_liquidity_proof(&artifacts, inputs).await,
+            LiquidityOperation::Burn => proof::generate_burn_liquidity_proof(&artifacts, inputs).await,
+        };
+        let lp_proof = proved.map_err(|e| {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
+            println!("[ASP] ========================================\n");
+            e.context("Proof generation failed")
+        })?;
+
+        let calldata = match operation {
+            LiquidityOperation::Mint => build_mint_liquidity_calldata,
+            LiquidityOperation::Burn => build_burn_liquidity_calldata,
+        }(&lp_proof.proof, &lp_proof.public_inputs, tick_lower, tick_upper, liquidity, &new_commitment)
+        .map_err(|e| format!("Failed to build calldata: {}", e))?;
+
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[ASP] ✅ {} prepared in {:.2}s", operation.entry_point(), elapsed);
+        println!("[ASP] ========================================\n");
+
+        serde_json::to_value(LiquidityPrepareResponse {
+            transactions: vec![PreparedTransaction {
+                contract_address: zylith_address,
+                entry_point: operation.entry_point().to_string(),
+                calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+            }],
+            new_commitment,
+            output_note_data: NoteData {
+                secret: new_secret,
+                nullifier: new_nullifier,
+                amount: new_amount.to_string(),
+            },
+            position_commitment,
+            liquidity: liquidity.to_string(),
+            amount0: amount0.to_string(),
+            amount1: amount1.to_string(),
+        })
+        .map_err(|e| format!("Failed to serialize response: {}", e).into())
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct PrepareCollectRequest {
+    // Input note data; its secret keys the position
+    secret: String,
+    nullifier: String,
+    amount: String,
+    note_index: u32,
+    // Position
+    tick_lower: i32,
+    tick_upper: i32,
+    // Output note (the input note re-issued whole)
+    new_secret: Option<String>,
+    new_nullifier: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CollectPrepareResponse {
+    transactions: Vec<PreparedTransaction>,
+    new_commitment: String,
+    output_note_data: NoteData,
+    position_commitment: String,
+    /// Liquidity the contract holds for the position
+    position_liquidity: String,
+    /// What private_collect pays at state_block: tokens_owed plus the fees earned since the
+    /// position's last update, from the contract's storage
+    fees_owed0: String,
+    fees_owed1: String,
+    state_block: u64,
+}
+
+/// Read the fees a position is owed from contract storage, prove the LP circuit for a collect
+/// and return private_collect calldata
+async fn prepare_collect(
+    State(state): State<AppState>,
+    Query(mode): Query<ProofModeQuery>,
+    Json(payload): Json<PrepareCollectRequest>,
+) -> impl IntoResponse {
+    println!("\n[ASP] ========================================");
+    println!("[ASP] 📥 POST /api/liquidity/collect/prepare - Request received");
+    println!("[ASP] ========================================");
+    println!("[ASP]    Note index: {}", payload.note_index);
+    println!("[ASP]    Range: [{}, {}]", payload.tick_lower, payload.tick_upper);
+    let start_time = std::time::Instant::now();
+
+    let error = |status: StatusCode, error: String| (status, Json(serde_json::json!({ "error": error }))).into_response();
+    let bad_request = |e: String| error(StatusCode::BAD_REQUEST, e);
+
+    // A contract deployed before the private_collect fix reads the LP proof's inputs at the
+    // wrong indexes and reverts, so don't prove a collect that can only fail on-chain
+    if !state.config.profile.private_collect_lp_layout {
+        println!("[ASP] ⚠️  Collect refused: the deployed private_collect predates the LP input layout");
+        return error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Collect unavailable: the deployed Zylith contract's private_collect reads the old 6-input layout and would revert. Redeploy the contract and set PRIVATE_COLLECT_LP_LAYOUT=true".to_string(),
+        );
+    }
+
+    let mut v = Validator::new();
+    let secret = v.bn254("secret", &payload.secret);
+    let nullifier = v.bn254("nullifier", &payload.nullifier);
+    let amount = v.u128("amount", &payload.amount);
+    v.check_tick("tick_lower", payload.tick_lower);
+    v.check_tick("tick_upper", payload.tick_upper);
+    check_output_note(&mut v, &payload.new_secret, &payload.new_nullifier);
+    if let Err(invalid) = v.finish() {
+        return invalid.into_response();
+    }
+    let (tick_lower, tick_upper) = (payload.tick_lower, payload.tick_upper);
+    // Same bounds checks as mint: a range the contract never accepted holds no position
+    if let Err(e) = clmm::get_private_burn_amounts(tick_lower, tick_upper, 0) {
+        return bad_request(e);
+    }
+
+    let merkle_proof = match verify_note(&state, &secret, &nullifier, amount, payload.note_index).await {
+        Ok(proof) => proof,
+        Err(response) => return response,
+    };
+
+    // The LP circuit conserves amount_out = amount_in - liquidity, so with no liquidity the
+    // note comes back whole; the fees stay in the contract
+    let (new_secret, new_nullifier) = match (&payload.new_secret, &payload.new_nullifier) {
+        (Some(new_secret), Some(new_nullifier)) => (new_secret.clone(), new_nullifier.clone()),
+        _ => generate_note(),
+    };
+    let new_note = parse_field_element("new_secret", &new_secret)
+        .and_then(|new_secret| Ok((new_secret, parse_field_element("new_nullifier", &new_nullifier)?)));
+    let (new_secret_value, new_nullifier_value) = match new_note {
+        Ok(new_note) => new_note,
+        Err(e) => return bad_request(e),
+    };
+    let new_commitment = match generate_commitment(
+        &format!("0x{:x}", new_secret_value),
+        &format!("0x{:x}", new_nullifier_value),
+        amount,
+    ) {
+        Ok(commitment) => commitment,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate output commitment: {}", e)),
+    };
+    let secret_hex = format!("0x{:x}", secret);
+    let position_commitment = match tokio::task::spawn_blocking(move || {
+        commitment::generate_position_commitment(&secret_hex, tick_lower, tick_upper)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result)
+    {
+        Ok(commitment) => commitment,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate position commitment: {}", e)),
+    };
+
+    // Owed fees from the position and pool state private_collect itself reads
+    let position_key = match parse_field_element("position_commitment", &position_commitment) {
+        Ok(key) => format!("0x{:x}", key),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let (state_block, position) = match state
+        .blockchain
+        .get_position_fee_state(&position_key, tick_lower, tick_upper)
+        .await
+    {
+        Ok(position) => position,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read position state: {}", e)),
+    };
+    if position.liquidity == 0 && position.tokens_owed0 == 0 && position.tokens_owed1 == 0 {
+        return error(StatusCode::NOT_FOUND, format!(
+            "No position for this note over [{}, {}] (position_commitment {})",
+            tick_lower, tick_upper, position_commitment
+        ));
+    }
+    let (fees_owed0, fees_owed1) = match fees::collect_amounts(&position, tick_lower, tick_upper) {
+        Ok(fees) => fees,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to compute owed fees: {}", e)),
+    };
+    println!("[ASP]    Fees owed at block {}: token0={}, token1={}", state_block, fees_owed0, fees_owed1);
+
+    println!("[ASP] 🔧 Generating ZK proof...");
+    let inputs = proof::CollectProofInputs {
+        secret: secret.to_string(),
+        nullifier: nullifier.to_string(),
+        amount,
+        merkle_path: merkle_proof.path,
+        merkle_path_indices: merkle_proof.path_indices,
+        root: merkle_proof.root,
+        tick_lower,
+        tick_upper,
+        liquidity: 0,
+        new_secret: new_secret_value.to_string(),
+        new_nullifier: new_nullifier_value.to_string(),
+        new_amount: amount,
+        new_commitment: new_commitment.clone(),
+        position_commitment: position_commitment.clone(),
+    };
+    let (artifacts, zylith_address) = (state.artifacts.clone(), state.zylith_address.clone());
+    run_proof_job(&state, "collect", &mode, async move {
+        let collect_proof = proof::generate_collect_proof(&artifacts, inputs).await.map_err(|e| {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            println!("[ASP] ❌ ZK proof generation failed (elapsed: {:.2}s): {}", elapsed, e);
+            println!("[ASP] ========================================\n");
+            e.context("Proof generation failed")
+        })?;
+
+        let calldata = build_collect_calldata(
+            &collect_proof.proof,
+            &collect_proof.public_inputs,
+            tick_lower,
+            tick_upper,
+            &new_commitment,
+        )
+        .map_err(|e| format!("Failed to build calldata: {}", e))?;
+
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[ASP] ✅ private_collect prepared in {:.2}s", elapsed);
+        println!("[ASP] ========================================\n");
+
+        serde_json::to_value(CollectPrepareResponse {
+            transactions: vec![PreparedTransaction {
+                contract_address: zylith_address,
+                entry_point: "private_collect".to_string(),
+                calldata: calldata.iter().map(|f| format!("0x{:x}", f)).collect(),
+            }],
+            new_commitment,
+            output_note_data: NoteData {
+                secret: new_secret,
+                nullifier: new_nullifier,
+                amount: amount.to_string(),
+            },
+            position_commitment,
+            position_liquidity: position.liquidity.to_string(),
+            fees_owed0: fees_owed0.to_string(),
+            fees_owed1: fees_owed1.to_string(),
+            state_block,
+        })
+        .map_err(|e| format!("Failed to serialize response: {}", e).into())
+    })
+    .await
 }
 
 /// Request to prepare initialize transaction
@@ -1236,7 +2557,9 @@ struct PrepareInitializeRequest {
     token1: Option<String>,
     fee: Option<u128>,
     tick_spacing: Option<i32>,
-    sqrt_price_x128: Option<String>, // u256 as string
+    /// Decimal, 0x-hex or "low,high"; defaults to Q128 (1:1 price)
+    #[serde(default, deserialize_with = "clmm::deserialize_optional_u256")]
+    sqrt_price_x128: Option<U256>,
 }
 
 /// Prepare initialize transaction
@@ -1245,47 +2568,31 @@ async fn prepare_initialize(
     State(state): State<AppState>,
     Json(payload): Json<PrepareInitializeRequest>,
 ) -> impl IntoResponse {
-    // Use default values if not provided
-    let token0 = payload.token0.unwrap_or_else(|| {
-        "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7".to_string() // ETH
-    });
-    let token1 = payload.token1.unwrap_or_else(|| {
-        "0x0512feAc6339Ff7889822cb5aA2a86C848e9D392bB0E3E237C008674feeD8343".to_string() // USDC
-    });
+    // Use default values if not provided (first two tokens of the network profile)
+    let default_tokens = &state.config.profile.tokens;
+    let token0 = match payload.token0.or_else(|| default_tokens.first().map(|t| t.address.clone())) {
+        Some(token) => token,
+        None => return (StatusCode::BAD_REQUEST, "token0 is required: the network profile has no tokens").into_response(),
+    };
+    let token1 = match payload.token1.or_else(|| default_tokens.get(1).map(|t| t.address.clone())) {
+        Some(token) => token,
+        None => return (StatusCode::BAD_REQUEST, "token1 is required: the network profile has fewer than two tokens").into_response(),
+    };
     let fee = payload.fee.unwrap_or(3000); // 0.3%
     let tick_spacing = payload.tick_spacing.unwrap_or(60);
     
-    // Calculate sqrt_price_x128 (Q128 = 2^128 for 1:1 price)
-    let sqrt_price = if let Some(price_str) = payload.sqrt_price_x128 {
-        match BigUint::from_str(&price_str) {
-            Ok(p) => p,
-            Err(e) => {
-                return (StatusCode::BAD_REQUEST, format!("Invalid sqrt_price_x128: {}", e))
-                    .into_response();
-            }
-        }
-    } else {
-        // Default to Q128 (1:1 price)
-        match BigUint::from_str("340282366920938463463374607431768211456") {
-            Ok(p) => p,
-            Err(e) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to parse Q128: {}", e))
-                    .into_response();
-            }
-        }
-    };
-    
-    let (sqrt_price_low, sqrt_price_high) = u256_to_low_high_bigint(&sqrt_price);
+    let sqrt_price_x128 = payload.sqrt_price_x128.unwrap_or(clmm::Q128);
+    if !(clmm::MIN_SQRT_RATIO..=clmm::MAX_SQRT_RATIO).contains(&sqrt_price_x128) {
+        return (StatusCode::BAD_REQUEST, format!(
+            "sqrt_price_x128 {} is outside [{}, {}]",
+            sqrt_price_x128, clmm::MIN_SQRT_RATIO, clmm::MAX_SQRT_RATIO
+        ))
+            .into_response();
+    }
+    let (sqrt_price_low, sqrt_price_high) = u256_to_low_high(sqrt_price_x128);
     
     // Build calldata
-    let calldata = match build_initialize_calldata(
-        &token0,
-        &token1,
-        fee,
-        tick_spacing,
-        sqrt_price_low,
-        sqrt_price_high,
-    ) {
+    let calldata = match build_initialize_calldata(&token0, &token1, fee, tick_spacing, sqrt_price_x128) {
         Ok(c) => c,
         Err(e) => {
             return (StatusCode::BAD_REQUEST, format!("Failed to build calldata: {}", e))
@@ -1318,24 +2625,86 @@ async fn prepare_initialize(
     }))).into_response()
 }
 
-/// Convert u256 (BigUint) to low and high u128
-fn u256_to_low_high_bigint(value: &BigUint) -> (u128, u128) {
-    use num_traits::ToPrimitive;
-    let mask_128 = BigUint::from(1u128) << 128u32;
-    let low = value % &mask_128;
-    let high = value >> 128u32;
-    
-    let low_val = low.to_u128().unwrap_or(0);
-    let high_val = high.to_u128().unwrap_or(0);
-    
-    (low_val, high_val)
+/// Network profile the ASP is running against (addresses, tokens, verifiers)
+async fn get_network_config(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "network": state.config.network,
+        "zylith_address": state.config.profile.zylith_address,
+        "deployment_block": state.config.profile.deployment_block,
+        "tokens": state.config.profile.tokens,
+        "verifiers": state.config.profile.verifiers,
+        "explorer_url": state.config.profile.explorer_url,
+    }))
+}
+
+// ==================== Proof Jobs ====================
+
+/// `?async=true` on a proving endpoint answers 202 with a job id instead of waiting for the proof
+#[derive(Deserialize)]
+struct ProofModeQuery {
+    #[serde(rename = "async", default)]
+    run_async: bool,
+}
+
+/// Run the proving part of a request on the job pool: queued and answered with 202 and the
+/// job id when the caller asked for `?async=true`, otherwise awaited and answered with the
+/// result. A proof its verification key rejects is a 422 naming the public input at fault,
+/// any other failure a 500
+async fn run_proof_job<F>(state: &AppState, kind: &'static str, mode: &ProofModeQuery, job: F) -> axum::response::Response
+where
+    F: std::future::Future<Output = Result<serde_json::Value, proof::ProofError>> + Send + 'static,
+{
+    if mode.run_async {
+        let view = state.jobs.submit(kind, job);
+        return (StatusCode::ACCEPTED, Json(serde_json::json!({
+            "job_id": view.id,
+            "status": view.status,
+            "status_url": format!("/api/proof/jobs/{}", view.id),
+        }))).into_response();
+    }
+    match state.jobs.run(kind, job).await {
+        Ok(result) => Json(result).into_response(),
+        Err(proof::ProofError::Rejected(rejection)) => (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({
+            "error": rejection.message,
+            "rejection": rejection,
+        }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+async fn get_proof_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.jobs.get(&id) {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "error": format!("Unknown or expired proof job {}", id)
+        }))).into_response(),
+    }
+}
+
+/// Cancel a queued or running job (killing its prover), or discard a finished job's result
+async fn cancel_proof_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.jobs.cancel(&id) {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "error": format!("Unknown or expired proof job {}", id)
+        }))).into_response(),
+    }
 }
 
 // ==================== Health Check ====================
 
-async fn health_check() -> impl IntoResponse {
+async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "ok",
-        "version": "0.1.0"
+        "version": "0.1.0",
+        // Paths and SHA-256 of the circuit artifacts this instance proves with
+        "artifacts": state.artifacts.summary(),
+        "proof_jobs": state.jobs.stats(),
     }))
 }
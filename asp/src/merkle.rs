@@ -4,7 +4,7 @@ use light_poseidon::{Poseidon, PoseidonHasher};
 use num_bigint::BigUint;
 use num_traits::Num;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Merkle Tree Depth (matches Cairo contract)
 /// Contract uses depth 25
@@ -23,6 +23,7 @@ pub struct MerkleProof {
 }
 
 /// Merkle Tree with proper intermediate node storage for correct proof generation
+#[derive(Clone)]
 pub struct MerkleTree {
     pub depth: usize,
     pub next_index: u32,
@@ -35,6 +36,10 @@ pub struct MerkleTree {
     pub current_root: BigUint,
     /// Mask for BN254 -> felt252 conversion
     pub mask: BigUint,
+    /// Nodes modified since the last call to `take_dirty_nodes` (used for persistence)
+    dirty: HashSet<(usize, u32)>,
+    /// Reverse index of the leaves: commitment -> leaf index (zero leaves are not indexed)
+    leaf_indices: HashMap<BigUint, u32>,
 }
 
 impl MerkleTree {
@@ -55,9 +60,25 @@ impl MerkleTree {
             zeros,
             current_root: initial_root,
             mask,
+            dirty: HashSet::new(),
+            leaf_indices: HashMap::new(),
         }
     }
 
+    /// Rebuild a tree from persisted nodes without rehashing
+    /// The root is read from the node at (depth, 0), or 0 if the tree is empty
+    pub fn from_nodes(depth: usize, next_index: u32, nodes: HashMap<(usize, u32), BigUint>) -> Self {
+        let mut tree = Self::new(depth);
+        tree.current_root = nodes
+            .get(&(depth, 0))
+            .cloned()
+            .unwrap_or_else(|| BigUint::from(0u8));
+        tree.next_index = next_index;
+        tree.nodes = nodes;
+        tree.reindex_leaves();
+        tree
+    }
+
     /// Insert a leaf at the next available index and update the tree, returning the new root
     pub fn insert(&mut self, leaf: BigUint) -> BigUint {
         let index = self.next_index;
@@ -74,7 +95,13 @@ impl MerkleTree {
         }
 
         // Store leaf at level 0
-        self.nodes.insert((0, index), leaf.clone());
+        if let Some(previous) = self.nodes.insert((0, index), leaf.clone()) {
+            if self.leaf_indices.get(&previous) == Some(&index) {
+                self.leaf_indices.remove(&previous);
+            }
+        }
+        self.index_leaf(&leaf, index);
+        self.dirty.insert((0, index));
 
         // Update path from leaf to root
         let mut current_hash = leaf;
@@ -82,7 +109,7 @@ impl MerkleTree {
 
         for level in 0..self.depth {
             // Determine left and right children for current position
-            let (left, right) = if current_idx % 2 == 0 {
+            let (left, right) = if current_idx.is_multiple_of(2) {
                 // Current node is left child
                 let right_idx = current_idx + 1;
                 // CRITICAL: Use 0 for missing siblings (matching Cairo contract)
@@ -110,6 +137,7 @@ impl MerkleTree {
             // Move to parent level
             let parent_idx = current_idx / 2;
             self.nodes.insert((level + 1, parent_idx), current_hash.clone());
+            self.dirty.insert((level + 1, parent_idx));
             current_idx = parent_idx;
         }
 
@@ -117,6 +145,73 @@ impl MerkleTree {
         current_hash
     }
 
+    /// Roll the tree back to its first `new_count` leaves, returning the new root
+    /// Nodes that only covered removed leaves are dropped and the path of the new
+    /// last leaf is recomputed, so the root matches the one the tree had at that size
+    pub fn truncate(&mut self, new_count: u32) -> BigUint {
+        if new_count >= self.next_index {
+            return self.current_root.clone();
+        }
+
+        // A node at (level, index) covers leaves starting at index << level
+        let keep = |&(level, index): &(usize, u32)| ((index as u64) << level) < new_count as u64;
+        self.nodes.retain(|key, _| keep(key));
+        self.dirty.retain(keep);
+        self.next_index = new_count;
+        if self.leaf_indices.values().any(|&index| index >= new_count) {
+            // A removed duplicate may have shadowed a surviving leaf, so rebuild
+            self.reindex_leaves();
+        }
+
+        if new_count == 0 {
+            self.current_root = BigUint::from(0u8);
+            return self.current_root.clone();
+        }
+
+        let last_leaf = self
+            .nodes
+            .get(&(0, new_count - 1))
+            .cloned()
+            .unwrap_or_else(|| BigUint::from(0u8));
+        self.insert_at_index(new_count - 1, last_leaf)
+    }
+
+    /// Root the tree had when it held only its first `count` leaves
+    /// Left siblings on the path of leaf `count - 1` are complete subtrees that later
+    /// inserts never touch, and right siblings were still empty, so no rehash is needed
+    pub fn root_at(&self, count: u32) -> BigUint {
+        if count == 0 {
+            return BigUint::from(0u8);
+        }
+        if count >= self.next_index {
+            return self.current_root.clone();
+        }
+
+        let mut current_idx = count - 1;
+        let mut current_hash = self
+            .nodes
+            .get(&(0, current_idx))
+            .cloned()
+            .unwrap_or_else(|| BigUint::from(0u8));
+
+        for level in 0..self.depth {
+            let (left, right) = if current_idx.is_multiple_of(2) {
+                (current_hash, BigUint::from(0u8))
+            } else {
+                let left = self
+                    .nodes
+                    .get(&(level, current_idx - 1))
+                    .cloned()
+                    .unwrap_or_else(|| BigUint::from(0u8));
+                (left, current_hash)
+            };
+            current_hash = Self::hash_and_mask(&[left, right], &self.mask);
+            current_idx /= 2;
+        }
+
+        current_hash
+    }
+
     /// Generate a Merkle proof for a leaf at the given index
     pub fn get_proof(&self, index: u32) -> Option<MerkleProof> {
         // Check if leaf exists
@@ -128,14 +223,14 @@ impl MerkleTree {
 
         for level in 0..self.depth {
             // Determine sibling index
-            let sibling_idx = if current_idx % 2 == 0 {
+            let sibling_idx = if current_idx.is_multiple_of(2) {
                 current_idx + 1
             } else {
                 current_idx - 1
             };
 
             // Path index: 0 if current is left (sibling on right), 1 if current is right (sibling on left)
-            path_indices.push((current_idx % 2) as u32);
+            path_indices.push(current_idx % 2);
             
             // Get sibling (use 0 if not present - matching Cairo contract)
             // CRITICAL: Cairo contract uses 0 for missing siblings, not recursive hash
@@ -169,18 +264,53 @@ impl MerkleTree {
         self.next_index
     }
 
+    /// Drain the set of nodes modified since the last call, with their current hashes
+    /// Level 0 entries are leaves
+    pub fn take_dirty_nodes(&mut self) -> Vec<((usize, u32), BigUint)> {
+        let dirty = std::mem::take(&mut self.dirty);
+        dirty
+            .into_iter()
+            .filter_map(|key| self.nodes.get(&key).map(|hash| (key, hash.clone())))
+            .collect()
+    }
+
+    /// Mark nodes as dirty again (e.g. after a failed write to storage)
+    pub fn restore_dirty_nodes(&mut self, nodes: &[((usize, u32), BigUint)]) {
+        self.dirty.extend(nodes.iter().map(|(key, _)| *key));
+    }
+
     /// Find the index of a commitment in the tree
     /// Returns None if the commitment is not found
+    /// If the same commitment was inserted twice, the lowest index is returned
     pub fn find_commitment_index(&self, commitment: &BigUint) -> Option<u32> {
-        // Search through all leaves (level 0)
-        for index in 0..self.next_index {
-            if let Some(leaf) = self.nodes.get(&(0, index)) {
-                if leaf == commitment {
-                    return Some(index);
-                }
-            }
+        self.leaf_indices.get(commitment).copied()
+    }
+
+    /// Record `index` for `leaf` in the reverse index, keeping the lowest index on duplicates
+    fn index_leaf(&mut self, leaf: &BigUint, index: u32) {
+        if *leaf == BigUint::from(0u8) {
+            return;
+        }
+        self.leaf_indices
+            .entry(leaf.clone())
+            .and_modify(|existing| *existing = (*existing).min(index))
+            .or_insert(index);
+    }
+
+    /// Rebuild the reverse index from the leaves currently in the tree
+    fn reindex_leaves(&mut self) {
+        let mut leaves: Vec<(u32, BigUint)> = self
+            .nodes
+            .iter()
+            .filter(|((level, _), _)| *level == 0)
+            .map(|((_, index), leaf)| (*index, leaf.clone()))
+            .collect();
+        leaves.sort_unstable_by_key(|(index, _)| *index);
+
+        self.leaf_indices = HashMap::with_capacity(leaves.len());
+        for (index, leaf) in leaves {
+            self.index_leaf(&leaf, index);
         }
-        None
     }
 
     /// Hash two nodes using Poseidon BN254 and mask to felt252
@@ -270,4 +400,64 @@ mod tests {
 
         assert_eq!(format!("0x{:x}", current_hash), proof.root);
     }
+
+    #[test]
+    fn test_truncate_restores_previous_root() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+        tree.insert(BigUint::from(1u64));
+        tree.insert(BigUint::from(2u64));
+        let root_at_two = tree.insert(BigUint::from(3u64));
+        tree.insert(BigUint::from(4u64));
+        tree.insert(BigUint::from(5u64));
+
+        assert_eq!(tree.truncate(3), root_at_two);
+        assert_eq!(tree.get_leaf_count(), 3);
+        assert!(tree.get_proof(3).is_none());
+
+        // Re-inserting after a rollback must behave like a fresh insert
+        let mut expected = MerkleTree::new(TREE_DEPTH);
+        for leaf in [1u64, 2, 3, 6] {
+            expected.insert(BigUint::from(leaf));
+        }
+        assert_eq!(tree.insert(BigUint::from(6u64)), expected.get_root());
+
+        assert_eq!(tree.truncate(0), BigUint::from(0u8));
+        assert_eq!(tree.get_leaf_count(), 0);
+    }
+
+    #[test]
+    fn test_root_at_matches_history() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+        let mut history = vec![tree.get_root()];
+        for leaf in 1u64..=7 {
+            history.push(tree.insert(BigUint::from(leaf)));
+        }
+
+        for (count, root) in history.iter().enumerate() {
+            assert_eq!(&tree.root_at(count as u32), root, "root after {} leaves", count);
+        }
+    }
+
+    #[test]
+    fn test_commitment_index_follows_inserts_and_truncate() {
+        let mut tree = MerkleTree::new(TREE_DEPTH);
+        for leaf in [11u64, 22, 33, 44] {
+            tree.insert(BigUint::from(leaf));
+        }
+        assert_eq!(tree.find_commitment_index(&BigUint::from(33u64)), Some(2));
+        assert_eq!(tree.find_commitment_index(&BigUint::from(99u64)), None);
+
+        // Overwriting a leaf moves its index entry
+        tree.insert_at_index(1, BigUint::from(55u64));
+        assert_eq!(tree.find_commitment_index(&BigUint::from(22u64)), None);
+        assert_eq!(tree.find_commitment_index(&BigUint::from(55u64)), Some(1));
+
+        tree.truncate(2);
+        assert_eq!(tree.find_commitment_index(&BigUint::from(33u64)), None);
+        assert_eq!(tree.find_commitment_index(&BigUint::from(11u64)), Some(0));
+
+        // Trees restored from persisted nodes get the same index
+        let restored = MerkleTree::from_nodes(TREE_DEPTH, tree.get_leaf_count(), tree.nodes.clone());
+        assert_eq!(restored.find_commitment_index(&BigUint::from(55u64)), Some(1));
+    }
 }
@@ -0,0 +1,451 @@
+use crate::events::PoolEvent;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::Serialize;
+
+/// Candle intervals accepted by the time-series endpoints
+const INTERVALS: &[(&str, u64)] = &[
+    ("1m", 60),
+    ("5m", 300),
+    ("15m", 900),
+    ("1h", 3_600),
+    ("4h", 14_400),
+    ("1d", 86_400),
+];
+
+/// Upper bound on buckets returned by one time-series request
+pub const MAX_BUCKETS: u64 = 1000;
+
+/// A pool event about to be indexed, with the block it was emitted in
+#[derive(Debug, Clone)]
+pub struct PoolEventRecord {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub tx_hash: String,
+    pub event: PoolEvent,
+}
+
+/// Pool state after an event: price, tick and active liquidity
+/// Reserves are not tracked: the contract emits no Mint or Collect events, so summing event
+/// amounts would only give cumulative swap deltas. Value locked comes from the contract's
+/// balances instead (see TvlSnapshot)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolState {
+    pub sqrt_price_x128: BigUint,
+    pub tick: i32,
+    /// Liquidity of the positions in range at `tick`
+    pub liquidity: u128,
+}
+
+/// An indexed pool event as read back from storage, with the state it left the pool in
+#[derive(Debug, Clone)]
+pub struct IndexedPoolEvent {
+    pub id: i64,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub tx_hash: String,
+    pub kind: String,
+    pub account: Option<String>,
+    pub tick_lower: Option<i32>,
+    pub tick_upper: Option<i32>,
+    /// Liquidity added (mint) or removed (burn)
+    pub liquidity_delta: Option<u128>,
+    /// Token amounts as emitted; for swaps, positive means paid into the pool
+    pub amount0: i128,
+    pub amount1: i128,
+    pub state: PoolState,
+}
+
+impl PoolState {
+    /// State after `event`, following the Uniswap v3 accounting the contract uses
+    pub fn apply(&self, event: &PoolEvent) -> PoolState {
+        let mut next = self.clone();
+        match event {
+            PoolEvent::Initialize(init) => {
+                next.sqrt_price_x128 = init.sqrt_price_x128.clone();
+                next.tick = init.tick;
+            }
+            PoolEvent::Swap(swap) => {
+                next.sqrt_price_x128 = swap.sqrt_price_x128.clone();
+                next.tick = swap.tick;
+                next.liquidity = swap.liquidity;
+            }
+            PoolEvent::Mint(mint) => {
+                if (mint.tick_lower..mint.tick_upper).contains(&self.tick) {
+                    next.liquidity = next.liquidity.saturating_add(mint.amount);
+                }
+            }
+            PoolEvent::Burn(burn) => {
+                if (burn.tick_lower..burn.tick_upper).contains(&self.tick) {
+                    next.liquidity = next.liquidity.saturating_sub(burn.amount);
+                }
+            }
+            PoolEvent::Collect(_) => {}
+        }
+        next
+    }
+
+    /// Price of token0 in token1 (raw units)
+    pub fn price(&self) -> f64 {
+        price_from_sqrt_x128(&self.sqrt_price_x128)
+    }
+
+    /// Token amounts valued in token1 (raw units) at the current price
+    pub fn value_token1(&self, amount0: &BigUint, amount1: &BigUint) -> f64 {
+        value_token1(&self.sqrt_price_x128, amount0, amount1)
+    }
+}
+
+/// Token amounts valued in token1 (raw units) at a price
+pub fn value_token1(sqrt_price_x128: &BigUint, amount0: &BigUint, amount1: &BigUint) -> f64 {
+    let to_f64 = |amount: &BigUint| amount.to_f64().unwrap_or(f64::INFINITY);
+    to_f64(amount0) * price_from_sqrt_x128(sqrt_price_x128) + to_f64(amount1)
+}
+
+/// (sqrt_price_x128 / 2^128)^2
+pub fn price_from_sqrt_x128(sqrt_price_x128: &BigUint) -> f64 {
+    let sqrt_price = sqrt_price_x128.to_f64().unwrap_or(f64::INFINITY) / 2f64.powi(128);
+    sqrt_price * sqrt_price
+}
+
+/// Indexed columns of a pool event
+pub struct EventColumns {
+    pub kind: &'static str,
+    pub account: Option<String>,
+    pub ticks: Option<(i32, i32)>,
+    pub liquidity_delta: Option<u128>,
+    pub amount0: i128,
+    pub amount1: i128,
+}
+
+/// Column values for an event
+pub fn event_columns(event: &PoolEvent) -> EventColumns {
+    let (kind, account, ticks, liquidity_delta, amount0, amount1) = match event {
+        PoolEvent::Initialize(_) => ("initialize", None, None, None, 0, 0),
+        PoolEvent::Swap(swap) => (
+            "swap",
+            Some(format!("0x{:x}", swap.sender)),
+            None,
+            None,
+            swap.amount0,
+            swap.amount1,
+        ),
+        PoolEvent::Mint(mint) => (
+            "mint",
+            Some(format!("0x{:x}", mint.owner)),
+            Some((mint.tick_lower, mint.tick_upper)),
+            Some(mint.amount),
+            to_i128(mint.amount0),
+            to_i128(mint.amount1),
+        ),
+        PoolEvent::Burn(burn) => (
+            "burn",
+            Some(format!("0x{:x}", burn.owner)),
+            Some((burn.tick_lower, burn.tick_upper)),
+            Some(burn.amount),
+            to_i128(burn.amount0),
+            to_i128(burn.amount1),
+        ),
+        PoolEvent::Collect(collect) => (
+            "collect",
+            Some(format!("0x{:x}", collect.owner)),
+            Some((collect.tick_lower, collect.tick_upper)),
+            None,
+            to_i128(collect.amount0),
+            to_i128(collect.amount1),
+        ),
+    };
+    EventColumns { kind, account, ticks, liquidity_delta, amount0, amount1 }
+}
+
+fn to_i128(value: u128) -> i128 {
+    i128::try_from(value).unwrap_or(i128::MAX)
+}
+
+/// Parse an interval name ("1m", "5m", "15m", "1h", "4h", "1d") into seconds
+pub fn parse_interval(interval: &str) -> Result<u64, String> {
+    INTERVALS
+        .iter()
+        .find(|(name, _)| *name == interval)
+        .map(|(_, secs)| *secs)
+        .ok_or_else(|| {
+            format!(
+                "Invalid interval '{}' (expected one of: {})",
+                interval,
+                INTERVALS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+            )
+        })
+}
+
+/// Bucket-aligned [from, to) range, capped at MAX_BUCKETS buckets ending at `to`
+pub fn bucket_range(interval: u64, from: Option<u64>, to: u64) -> (u64, u64) {
+    let to = (to / interval + 1) * interval;
+    let earliest = to.saturating_sub(MAX_BUCKETS * interval);
+    let from = from.map(|f| f / interval * interval).unwrap_or(earliest).max(earliest);
+    (from, to)
+}
+
+/// The contract's token0/token1 balances at the end of a block in which it emitted events,
+/// read by the syncer with balance_of at that block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TvlSnapshot {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub balance0: BigUint,
+    pub balance1: BigUint,
+}
+
+/// A TVL snapshot as read back from storage, with the pool price at its block
+/// (None before the pool was initialized)
+#[derive(Debug, Clone)]
+pub struct IndexedTvlSnapshot {
+    pub snapshot: TvlSnapshot,
+    pub sqrt_price_x128: Option<BigUint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TvlPoint {
+    /// Unix timestamp the bucket starts at
+    pub time: u64,
+    /// Block of the snapshot the bucket closes with
+    pub block_number: u64,
+    /// Balances in raw token units (decimal strings)
+    pub balance0: String,
+    pub balance1: String,
+    /// Both balances valued in token1 (raw units) at that block's price
+    pub tvl_token1: Option<f64>,
+}
+
+/// Value locked at the close of each bucket in [from, to): the last snapshot taken up to the
+/// bucket's end, carried forward through buckets without one. `snapshots` is in block order
+/// and may start with the last snapshot before `from`; buckets before the first one are left out
+pub fn tvl_series(snapshots: &[IndexedTvlSnapshot], interval: u64, from: u64, to: u64) -> Vec<TvlPoint> {
+    let mut points = Vec::new();
+    let mut snapshots = snapshots.iter().peekable();
+    let mut latest = None;
+    let mut time = from;
+    while time < to {
+        let end = time + interval;
+        while let Some(snapshot) = snapshots.next_if(|s| s.snapshot.block_timestamp < end) {
+            latest = Some(snapshot);
+        }
+        if let Some(IndexedTvlSnapshot { snapshot, sqrt_price_x128 }) = latest {
+            points.push(TvlPoint {
+                time,
+                block_number: snapshot.block_number,
+                balance0: snapshot.balance0.to_string(),
+                balance1: snapshot.balance1.to_string(),
+                tvl_token1: sqrt_price_x128
+                    .as_ref()
+                    .map(|sqrt_price| value_token1(sqrt_price, &snapshot.balance0, &snapshot.balance1)),
+            });
+        }
+        time = end;
+    }
+    points
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candle {
+    /// Unix timestamp the bucket starts at
+    pub time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded amounts in raw token units (decimal strings)
+    pub volume0: String,
+    pub volume1: String,
+    pub swaps: u32,
+}
+
+/// OHLC candles from the swaps in `events`; buckets without swaps are left out
+pub fn candles(events: &[IndexedPoolEvent], interval: u64) -> Vec<Candle> {
+    let mut candles: Vec<(Candle, u128, u128)> = Vec::new();
+    for event in events.iter().filter(|e| e.kind == "swap") {
+        let time = event.block_timestamp / interval * interval;
+        let price = event.state.price();
+        let (volume0, volume1) = (event.amount0.unsigned_abs(), event.amount1.unsigned_abs());
+
+        match candles.last_mut() {
+            Some((candle, v0, v1)) if candle.time == time => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.swaps += 1;
+                *v0 = v0.saturating_add(volume0);
+                *v1 = v1.saturating_add(volume1);
+            }
+            _ => candles.push((
+                Candle {
+                    time,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume0: String::new(),
+                    volume1: String::new(),
+                    swaps: 1,
+                },
+                volume0,
+                volume1,
+            )),
+        }
+    }
+
+    candles
+        .into_iter()
+        .map(|(mut candle, v0, v1)| {
+            candle.volume0 = v0.to_string();
+            candle.volume1 = v1.to_string();
+            candle
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{Burn, Mint, PoolInitialize, Swap};
+    use starknet::core::types::FieldElement;
+
+    fn q128() -> BigUint {
+        BigUint::from(1u8) << 128
+    }
+
+    fn swap(amount0: i128, amount1: i128, sqrt_price_x128: BigUint, tick: i32) -> PoolEvent {
+        PoolEvent::Swap(Swap {
+            sender: FieldElement::ONE,
+            zero_for_one: amount0 > 0,
+            amount0,
+            amount1,
+            sqrt_price_x128,
+            liquidity: 500,
+            tick,
+        })
+    }
+
+    fn indexed(id: i64, timestamp: u64, event: &PoolEvent, state: &PoolState) -> IndexedPoolEvent {
+        let EventColumns { kind, amount0, amount1, .. } = event_columns(event);
+        IndexedPoolEvent {
+            id,
+            block_number: id as u64,
+            block_timestamp: timestamp,
+            tx_hash: format!("0x{:x}", id),
+            kind: kind.to_string(),
+            account: None,
+            tick_lower: None,
+            tick_upper: None,
+            liquidity_delta: None,
+            amount0,
+            amount1,
+            state: state.clone(),
+        }
+    }
+
+    #[test]
+    fn test_state_follows_events() {
+        let state = PoolState::default().apply(&PoolEvent::Initialize(PoolInitialize {
+            sqrt_price_x128: q128(),
+            tick: 0,
+        }));
+        assert_eq!(state.price(), 1.0);
+
+        let mint = |tick_lower, tick_upper, amount| {
+            PoolEvent::Mint(Mint {
+                sender: FieldElement::ONE,
+                owner: FieldElement::ONE,
+                tick_lower,
+                tick_upper,
+                amount,
+                amount0: 100,
+                amount1: 100,
+            })
+        };
+        let state = state.apply(&mint(-60, 60, 1000));
+        // Out of range: active liquidity doesn't change
+        let state = state.apply(&mint(60, 120, 7));
+        assert_eq!(state.liquidity, 1000);
+
+        let state = state.apply(&swap(50, -49, q128() * 2u8, 13_863));
+        assert_eq!(state.tick, 13_863);
+        assert_eq!(state.liquidity, 500);
+        assert_eq!(state.price(), 4.0);
+        assert_eq!(state.value_token1(&BigUint::from(100u8), &BigUint::from(5u8)), 405.0);
+
+        let state = state.apply(&PoolEvent::Burn(Burn {
+            owner: FieldElement::ONE,
+            tick_lower: 0,
+            tick_upper: 20_000,
+            amount: 200,
+            amount0: 10,
+            amount1: 10,
+        }));
+        assert_eq!(state.liquidity, 300);
+    }
+
+    #[test]
+    fn test_candles() {
+        let mut state = PoolState::default();
+        let mut events = Vec::new();
+        for (id, (timestamp, amount0, amount1, sqrt_mul)) in
+            [(10u64, 10i128, -9i128, 1u8), (50, -5, 6, 2), (70, 3, -2, 1)].into_iter().enumerate()
+        {
+            let event = swap(amount0, amount1, q128() * sqrt_mul, 0);
+            state = state.apply(&event);
+            events.push(indexed(id as i64, timestamp, &event, &state));
+        }
+
+        let candles = candles(&events, 60);
+        assert_eq!(candles.len(), 2);
+        assert_eq!((candles[0].time, candles[0].open, candles[0].high, candles[0].close), (0, 1.0, 4.0, 4.0));
+        assert_eq!((candles[0].volume0.as_str(), candles[0].volume1.as_str()), ("15", "15"));
+        assert_eq!((candles[1].time, candles[1].swaps), (60, 1));
+    }
+
+    #[test]
+    fn test_tvl_series() {
+        let snapshot = |block: u64, timestamp: u64, balance0: u32, balance1: u32, sqrt_mul: Option<u8>| {
+            IndexedTvlSnapshot {
+                snapshot: TvlSnapshot {
+                    block_number: block,
+                    block_timestamp: timestamp,
+                    balance0: BigUint::from(balance0),
+                    balance1: BigUint::from(balance1),
+                },
+                sqrt_price_x128: sqrt_mul.map(|mul| q128() * mul),
+            }
+        };
+        let snapshots = [
+            // Before the range: opens it
+            snapshot(1, 30, 10, 0, None),
+            snapshot(2, 70, 100, 5, Some(1)),
+            snapshot(3, 110, 100, 50, Some(2)),
+        ];
+
+        let series = tvl_series(&snapshots, 60, 60, 300);
+        let closes: Vec<_> = series.iter().map(|p| (p.time, p.block_number, p.tvl_token1)).collect();
+        // The last snapshot of a bucket closes it and is carried through empty buckets
+        assert_eq!(
+            closes,
+            vec![(60, 3, Some(450.0)), (120, 3, Some(450.0)), (180, 3, Some(450.0)), (240, 3, Some(450.0))]
+        );
+        assert_eq!((series[0].balance0.as_str(), series[0].balance1.as_str()), ("100", "50"));
+
+        // Before the pool has a price the balances are there but not valued
+        let series = tvl_series(&snapshots, 60, 0, 60);
+        assert_eq!((series.len(), series[0].block_number, series[0].tvl_token1), (1, 1, None));
+        // Nothing before the first snapshot
+        assert!(tvl_series(&snapshots[1..], 60, 0, 60).is_empty());
+    }
+
+    #[test]
+    fn test_interval_and_range() {
+        assert_eq!(parse_interval("1h").unwrap(), 3_600);
+        assert!(parse_interval("2h").is_err());
+
+        assert_eq!(bucket_range(60, Some(130), 250), (120, 300));
+        // Requests for more than MAX_BUCKETS buckets are cut at the start
+        assert_eq!(bucket_range(60, Some(0), 60 * 5000), (60 * 4001, 60 * 5001));
+    }
+}
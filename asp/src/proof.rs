@@ -1,335 +1,72 @@
 // ZK Proof generation using Circom/snarkjs
 // This module will execute Circom circuits to generate proofs
 
-use std::path::Path;
+use crate::artifacts::ArtifactRegistry;
+use crate::verifier::ProofRejection;
+use std::path::{Path, PathBuf};
 use std::fs;
-use serde_json;
 use tokio::process::Command;
 
-/// Generate swap proof using rapidsnark (fast) with correct format conversion
+/// Why a proof could not be returned
+#[derive(Debug)]
+pub enum ProofError {
+    /// Witness, proving or Garaga formatting failed
+    Failed(String),
+    /// A proof was produced but its circuit's verification key rejects it
+    Rejected(Box<ProofRejection>),
+}
+
+impl ProofError {
+    /// Prefix a failure with what was being done; rejections already say it
+    pub fn context(self, what: &str) -> Self {
+        match self {
+            ProofError::Failed(e) => ProofError::Failed(format!("{}: {}", what, e)),
+            rejected => rejected,
+        }
+    }
+}
+
+impl std::fmt::Display for ProofError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProofError::Failed(e) => f.write_str(e),
+            ProofError::Rejected(rejection) => rejection.fmt(f),
+        }
+    }
+}
+
+impl From<String> for ProofError {
+    fn from(e: String) -> Self {
+        ProofError::Failed(e)
+    }
+}
+
+impl From<&str> for ProofError {
+    fn from(e: &str) -> Self {
+        ProofError::Failed(e.to_string())
+    }
+}
+
+/// Generate swap proof (native prover, rapidsnark or snarkjs) with correct format conversion
 pub async fn generate_swap_proof(
-    circuits_path: &str,
+    artifacts: &ArtifactRegistry,
     input_json: serde_json::Value,
-) -> Result<SwapProof, String> {
-    println!("[Proof] 🔄 Starting swap proof generation with rapidsnark...");
+) -> Result<SwapProof, ProofError> {
+    println!("[Proof] 🔄 Starting swap proof generation...");
     let start_time = std::time::Instant::now();
     
-    // Create temporary files
+    // Temporary files read by the Garaga step
     let temp_dir = std::env::temp_dir();
     let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
         .unwrap().as_nanos();
-    let input_file = temp_dir.join(format!("swap_input_{}.json", timestamp));
-    let witness_file = temp_dir.join(format!("swap_witness_{}.wtns", timestamp));
     let proof_file = temp_dir.join(format!("swap_proof_{}.json", timestamp));
     let public_file = temp_dir.join(format!("swap_public_{}.json", timestamp));
     
-    fs::write(&input_file, serde_json::to_string_pretty(&input_json).unwrap())
-        .map_err(|e| format!("Failed to write input file: {}", e))?;
-    
-    println!("[Proof] 📝 Input file created: {:?}", input_file);
-    
-    // Paths to circuit files
-    let circuits_dir = Path::new(circuits_path).canonicalize()
-        .map_err(|e| format!("Failed to canonicalize circuits path: {}", e))?;
-    let wasm_path = circuits_dir.join("build").join("swap").join("swap_js").join("swap.wasm");
-    let zkey_path = circuits_dir.join("build").join("zkeys").join("swap.zkey");
-    
-    // Check for rapidsnark binary
-    let asp_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-    let rapidsnark_path = asp_dir.join("bin").join("prover");
-    let use_rapidsnark = rapidsnark_path.exists();
-    
-    if !wasm_path.exists() {
-        return Err(format!("WASM file not found: {:?}", wasm_path));
-    }
-    if !zkey_path.exists() {
-        return Err(format!("ZKey file not found: {:?}", zkey_path));
-    }
+    let prover = prove_to_files("swap", artifacts, &input_json, &proof_file, &public_file, timestamp, 8192).await?;
     
-    // Step 1: Calculate witness using snarkjs (this is fast)
-    println!("[Proof] 🔧 Step 1: Calculating witness with snarkjs...");
-    let witness_script = format!(
-        r#"
-        const snarkjs = require('snarkjs');
-        const fs = require('fs');
-        const path = require('path');
-        
-        (async () => {{
-            try {{
-                const input = JSON.parse(fs.readFileSync('{}', 'utf8'));
-                const wasmPath = path.resolve('{}');
-                
-                console.log('Calculating witness...');
-                const startTime = Date.now();
-                
-                const {{ wtns }} = await snarkjs;
-                await wtns.calculate(input, wasmPath, '{}');
-                
-                const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
-                console.log('Witness calculated in', elapsed, 'seconds');
-            }} catch (error) {{
-                console.error('Error:', error.message);
-                console.error('Stack:', error.stack);
-                process.exit(1);
-            }}
-        }})();
-        "#,
-        input_file.to_str().unwrap().replace('\\', "/"),
-        wasm_path.to_str().unwrap().replace('\\', "/"),
-        witness_file.to_str().unwrap().replace('\\', "/")
-    );
-    
-    let script_file = circuits_dir.join(format!("witness_script_{}.js", timestamp));
-    fs::write(&script_file, witness_script)
-        .map_err(|e| format!("Failed to write witness script: {}", e))?;
-    
-    let witness_start = std::time::Instant::now();
-    let witness_output = Command::new("node")
-        .env("NODE_OPTIONS", "--max-old-space-size=4096")
-        .arg(script_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run witness calculation: {}", e))?;
-    
-    let _ = fs::remove_file(&script_file);
-    
-    if !witness_output.status.success() {
-        let stderr = String::from_utf8_lossy(&witness_output.stderr);
-        let stdout = String::from_utf8_lossy(&witness_output.stdout);
-        let _ = fs::remove_file(&input_file);
-        return Err(format!("Witness calculation failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
-    }
-    
-    println!("[Proof] ✅ Witness calculated in {:.2}s", witness_start.elapsed().as_secs_f64());
-    
-    // Step 2: Generate proof (use rapidsnark if available, otherwise snarkjs)
-    if use_rapidsnark {
-        println!("[Proof] 🔧 Step 2: Generating proof with rapidsnark (fast C++ prover)...");
-        let proof_start = std::time::Instant::now();
-        
-        let rapidsnark_output = Command::new(&rapidsnark_path)
-            .arg(&zkey_path)
-            .arg(&witness_file)
-            .arg(&proof_file)
-            .arg(&public_file)
-            .output()
-            .await
-            .map_err(|e| format!("Failed to run rapidsnark: {}", e))?;
-        
-        if !rapidsnark_output.status.success() {
-            let stderr = String::from_utf8_lossy(&rapidsnark_output.stderr);
-            let stdout = String::from_utf8_lossy(&rapidsnark_output.stdout);
-            let _ = fs::remove_file(&input_file);
-            let _ = fs::remove_file(&witness_file);
-            return Err(format!("rapidsnark failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
-        }
-        
-        println!("[Proof] ✅ Proof generated with rapidsnark in {:.2}s", proof_start.elapsed().as_secs_f64());
-    } else {
-        println!("[Proof] 🔧 Step 2: Generating proof with snarkjs (fallback)...");
-        let proof_script = format!(
-            r#"
-            const snarkjs = require('snarkjs');
-            const fs = require('fs');
-            
-            (async () => {{
-                try {{
-                    console.log('Generating proof...');
-                    const startTime = Date.now();
-                    
-                    const {{ proof, publicSignals }} = await snarkjs.groth16.prove(
-                        '{}',
-                        '{}'
-                    );
-                    
-                    const elapsed = ((Date.now() - startTime) / 1000).toFixed(2);
-                    console.log('Proof generated in', elapsed, 'seconds');
-                    
-                    fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
-                    fs.writeFileSync('{}', JSON.stringify(publicSignals, null, 2));
-                }} catch (error) {{
-                    console.error('Error:', error.message);
-                    process.exit(1);
-                }}
-            }})();
-            "#,
-            zkey_path.to_str().unwrap().replace('\\', "/"),
-            witness_file.to_str().unwrap().replace('\\', "/"),
-            proof_file.to_str().unwrap().replace('\\', "/"),
-            public_file.to_str().unwrap().replace('\\', "/")
-        );
-        
-        let script_file2 = circuits_dir.join(format!("proof_script_{}.js", timestamp));
-        fs::write(&script_file2, proof_script)
-            .map_err(|e| format!("Failed to write proof script: {}", e))?;
-        
-        let proof_start = std::time::Instant::now();
-        let mut child = Command::new("node")
-            .env("NODE_OPTIONS", "--max-old-space-size=8192")
-            .arg(script_file2.file_name().unwrap())
-            .current_dir(&circuits_dir)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to spawn node: {}", e))?;
-        
-        // Wait with progress updates
-        let mut last_log = std::time::Instant::now();
-        let output = loop {
-            match child.try_wait() {
-                Ok(Some(_)) => {
-                    let output = child.wait_with_output().await
-                        .map_err(|e| format!("Failed to get output: {}", e))?;
-                    break output;
-                }
-                Ok(None) => {
-                    if last_log.elapsed().as_secs() >= 30 {
-                        println!("[Proof] ⏳ Still processing... ({}s elapsed)", proof_start.elapsed().as_secs());
-                        last_log = std::time::Instant::now();
-                    }
-                    tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-                }
-                Err(e) => return Err(format!("Error waiting: {}", e)),
-            }
-        };
-        
-        let _ = fs::remove_file(&script_file2);
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return Err(format!("snarkjs proof failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
-        }
-        
-        println!("[Proof] ✅ Proof generated with snarkjs in {:.2}s", proof_start.elapsed().as_secs_f64());
-    }
-    
-    // Step 3: Add protocol field to proof (required by convert_garaga.py script)
-    println!("[Proof] 🔧 Step 3: Adding protocol field to proof...");
-    let add_protocol_script = format!(
-        r#"
-        const fs = require('fs');
-        const proof = JSON.parse(fs.readFileSync('{}', 'utf8'));
-        
-        // Add protocol field if not present (required by convert_garaga.py)
-        if (!proof.protocol) {{
-            proof.protocol = "groth16";
-        }}
-        
-        // Ensure pi_a, pi_b, pi_c are in correct format (remove extra elements)
-        if (proof.pi_a && proof.pi_a.length > 2) {{
-            proof.pi_a = [proof.pi_a[0], proof.pi_a[1]];
-        }}
-        if (proof.pi_b && proof.pi_b.length > 2) {{
-            proof.pi_b = [proof.pi_b[0], proof.pi_b[1]];
-        }}
-        if (proof.pi_c && proof.pi_c.length > 2) {{
-            proof.pi_c = [proof.pi_c[0], proof.pi_c[1]];
-        }}
-        
-        fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
-        "#,
-        proof_file.to_str().unwrap().replace('\\', "/"),
-        proof_file.to_str().unwrap().replace('\\', "/")
-    );
-    
-    let protocol_file = circuits_dir.join(format!("add_protocol_{}.js", timestamp));
-    fs::write(&protocol_file, add_protocol_script)
-        .map_err(|e| format!("Failed to write protocol script: {}", e))?;
-    
-    let protocol_output = Command::new("node")
-        .arg(protocol_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run protocol script: {}", e))?;
-    
-    let _ = fs::remove_file(&protocol_file);
-    
-    if !protocol_output.status.success() {
-        let stderr = String::from_utf8_lossy(&protocol_output.stderr);
-        return Err(format!("Failed to add protocol field: {}", stderr));
-    }
-    
-    println!("[Proof] ✅ Protocol field added to proof");
-    
-    // Step 4: Convert proof to Garaga format and generate calldata using Python script
-    println!("[Proof] 🔧 Step 4: Converting proof to Garaga format and generating calldata...");
-    let garaga_start = std::time::Instant::now();
-    
-    // Get script path (relative to project root)
-    let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent()
-        .ok_or("Failed to get project root")?;
-    let script_path = project_root.join("scripts").join("convert_garaga.py");
-    
-    if !script_path.exists() {
-        return Err(format!("Garaga conversion script not found: {:?}", script_path));
-    }
-    
-    // Call Python script to convert proof and generate calldata directly
-    let script_output = Command::new("python3")
-        .arg(&script_path)
-        .arg(&proof_file)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run convert_garaga.py script: {}", e))?;
-    
-    if !script_output.status.success() {
-        let stderr = String::from_utf8_lossy(&script_output.stderr);
-        let stdout = String::from_utf8_lossy(&script_output.stdout);
-        println!("[Proof] ❌ Python script failed.");
-        println!("[Proof] 📋 STDERR:\n{}", stderr);
-        println!("[Proof] 📋 STDOUT:\n{}", stdout);
-        println!("[Proof] 💾 Proof saved at: {:?}", proof_file);
-        
-        let _ = fs::remove_file(&input_file);
-        let _ = fs::remove_file(&witness_file);
-        let _ = fs::remove_file(&public_file);
-        
-        return Err(format!(
-            "Garaga conversion script failed.\n\
-             STDERR: {}\n\
-             STDOUT: {}\n\
-             \n\
-             Proof file at: {:?}",
-            stderr, stdout, proof_file
-        ));
-    }
-    
-    // Parse calldata from script output (JSON array)
-    let script_stdout = String::from_utf8_lossy(&script_output.stdout);
-    let proof_calldata: Vec<String> = serde_json::from_str(script_stdout.trim())
-        .map_err(|e| format!("Failed to parse calldata from script: {}. Output: {}", e, script_stdout))?;
-    
-    println!("[Proof] ✅ Garaga calldata generated in {:.2}s", garaga_start.elapsed().as_secs_f64());
-    println!("[Proof]    Proof calldata length: {} elements", proof_calldata.len());
-    
-    // Read public signals for the response
-    let public_signals: Vec<serde_json::Value> = serde_json::from_str(
-        &fs::read_to_string(&public_file)
-            .map_err(|e| format!("Failed to read public signals: {}", e))?
-    ).map_err(|e| format!("Failed to parse public signals: {}", e))?;
-    
-    // CRITICAL: Validate public_signals length BEFORE processing
-    if public_signals.len() != 9 {
-        return Err(format!(
-            "Invalid public signals length: expected 9 elements (swap circuit), got {}. \
-            This indicates the circuit did not generate the expected number of public inputs. \
-            Check the circuit output and ensure all 9 public inputs are being generated.",
-            public_signals.len()
-        ));
-    }
-    
-    // Apply felt252 modulo to public inputs to prevent overflow
-    // STARKNET_FELT_MAX = 2^251 + 17 * 2^192 + 1
     use num_bigint::BigUint;
-    use num_traits::Num;
     use std::str::FromStr;
-    let felt_max_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-    let felt_max_big = BigUint::from_str(felt_max_str)
-        .map_err(|_| "Failed to parse FELT_MAX constant".to_string())?;
-    
+
     // Public inputs order (swap circuit):
     // 0: nullifier (felt252)
     // 1: root (felt252)
@@ -349,50 +86,18 @@ pub async fn generate_swap_proof(
     // we ensure the values are properly formatted as felt252 (within felt252 range).
     // The actual conversion issue must be handled by the contract using .low for felt252 values.
     
-    // Read public signals and apply felt252 modulo
-    let public_inputs: Vec<String> = public_signals
-        .iter()
-        .enumerate()
-        .map(|(idx, s)| {
-            let value_str = s.as_str()
-                .ok_or_else(|| format!("Public signal at index {} is not a string: {:?}", idx, s))?;
-            // Parse as BigUint (handles both hex and decimal)
-            let value_big = if value_str.starts_with("0x") {
-                BigUint::from_str_radix(&value_str[2..], 16)
-                    .map_err(|e| format!("Failed to parse hex value at index {}: {}", idx, e))?
-            } else {
-                BigUint::from_str(value_str)
-                    .map_err(|e| format!("Failed to parse decimal value at index {}: {}", idx, e))?
-            };
-            
-            // Apply felt252 modulo if value exceeds limit
-            let modulo_big = if value_big >= felt_max_big {
-                &value_big % &felt_max_big
-            } else {
-                value_big.clone()
-            };
-            
-            // Convert to string (decimal format for felt252)
-            Ok(modulo_big.to_string())
-        })
-        .collect::<Result<Vec<String>, String>>()?;
+    let public_inputs = read_public_inputs(&public_file, "swap", 9, &[0, 1, 2])?;
     
-    // Proof calldata should only contain the 8 proof elements (A.x, A.y, B.x0, B.x1, B.y0, B.y1, C.x, C.y)
-    // Public inputs are returned separately
-    // The contract expects: proof (8 elements) and public_inputs (9 elements) as separate arrays
-    let proof_len = proof_calldata.len();
+    // Step 4: Encode the proof as Garaga calldata (full_proof_with_hints)
+    // Public inputs are also returned separately: the contract checks their count
+    let proof_calldata = garaga_proof_calldata(artifacts, "swap", &proof_file, &public_file, &public_inputs).await?;
     
-    println!("[Proof]    Proof calldata length: {} elements (should be 8)", proof_len);
+    println!("[Proof]    Proof calldata length: {} elements", proof_calldata.len());
     println!("[Proof]    Public inputs length: {} elements (should be 9)", public_inputs.len());
     
-    // Verify proof has exactly 8 elements
-    if proof_len != 8 {
-        return Err(format!("Invalid proof length: expected 8 elements, got {}", proof_len));
-    }
-    
     // Verify public inputs have exactly 9 elements
     if public_inputs.len() != 9 {
-        return Err(format!("Invalid public inputs length: expected 9 elements, got {}", public_inputs.len()));
+        return Err(format!("Invalid public inputs length: expected 9 elements, got {}", public_inputs.len()).into());
     }
     
     // Log and validate felt252 values (indices 0, 1, 2) to help diagnose conversion issues
@@ -418,228 +123,156 @@ pub async fn generate_swap_proof(
     }
     
     // Clean up temp files
-    let _ = fs::remove_file(&input_file);
-    let _ = fs::remove_file(&witness_file);
     let _ = fs::remove_file(&proof_file);
     let _ = fs::remove_file(&public_file);
     
     let elapsed = start_time.elapsed().as_secs_f64();
-    println!("[Proof] ✅ Total proof time: {:.2}s ({})", elapsed, 
-        if use_rapidsnark { "with rapidsnark" } else { "with snarkjs" });
+    println!("[Proof] ✅ Total proof time: {:.2}s (with {})", elapsed, prover);
     
     Ok(SwapProof {
-        proof: proof_calldata, // Only the 8 proof elements, not combined with public inputs
+        proof: proof_calldata,
         public_inputs,
     })
 }
 
-/// Normalize rapidsnark proof format to snarkjs format for Garaga
-/// snarkjs format: { pi_a: [x, y], pi_b: [[x0, x1], [y0, y1]], pi_c: [x, y] }
-/// NO protocol, NO curve fields - Garaga detects curve from VK
-fn normalize_proof_for_garaga(proof: serde_json::Value) -> Result<serde_json::Value, String> {
-    let mut normalized = serde_json::Map::new();
-    
-    // Extract and normalize pi_a
-    // snarkjs format: [x, y] (2 elements)
-    // rapidsnark format: [x, y, "1"] (3 elements)
-    // Garaga expects snarkjs format (2 elements)
-    if let Some(pi_a) = proof.get("pi_a").and_then(|v| v.as_array()) {
-        if pi_a.len() >= 2 {
-            let mut pi_a_normalized = Vec::new();
-            pi_a_normalized.push(pi_a[0].clone());
-            pi_a_normalized.push(pi_a[1].clone());
-            // Always use 2 elements (snarkjs format) - remove "1" if present
-            normalized.insert("pi_a".to_string(), serde_json::Value::Array(pi_a_normalized));
-        } else {
-            return Err(format!("Invalid pi_a format: expected at least 2 elements, got {}", pi_a.len()));
-        }
-    } else {
-        return Err("Missing pi_a".to_string());
-    }
-    
-    // Extract and normalize pi_b
-    // rapidsnark may output pi_b as nested arrays [[x0, x1], [y0, y1], [1, 0]]
-    // or as a flat array [x0, x1, y0, y1, 1, 0]
-    if let Some(pi_b) = proof.get("pi_b").and_then(|v| v.as_array()) {
-        let mut pi_b_normalized = Vec::new();
-        
-        if pi_b.len() >= 2 {
-            // Check if first element is an array (nested format)
-            if let Some(b_x) = pi_b[0].as_array() {
-                // Nested format: [[x0, x1], [y0, y1], ...]
-                if b_x.len() >= 2 {
-                    let mut b_x_normalized = Vec::new();
-                    // CRITICAL: rapidsnark exports as [imaginario, real] but Garaga expects [real, imaginario]
-                    // Swap coordinates: [a, b] -> [b, a]
-                    b_x_normalized.push(b_x[1].clone()); // real
-                    b_x_normalized.push(b_x[0].clone()); // imaginario
-                    pi_b_normalized.push(serde_json::Value::Array(b_x_normalized));
-                } else {
-                    return Err("Invalid pi_b[0] format: expected at least 2 elements".to_string());
-                }
-                
-                if pi_b.len() >= 2 {
-                    if let Some(b_y) = pi_b[1].as_array() {
-                        if b_y.len() >= 2 {
-                            let mut b_y_normalized = Vec::new();
-                            // CRITICAL: Swap coordinates for Garaga compatibility
-                            b_y_normalized.push(b_y[1].clone()); // real
-                            b_y_normalized.push(b_y[0].clone()); // imaginario
-                            pi_b_normalized.push(serde_json::Value::Array(b_y_normalized));
-                        } else {
-                            return Err("Invalid pi_b[1] format: expected at least 2 elements".to_string());
-                        }
-                    } else {
-                        return Err("pi_b[1] is not an array".to_string());
-                    }
-                }
-            } else if pi_b.len() >= 6 {
-                // Flat format: [x0, x1, y0, y1, 1, 0]
-                // CRITICAL: Swap coordinates for Garaga compatibility
-                let mut b_x_normalized = Vec::new();
-                b_x_normalized.push(pi_b[1].clone()); // real (swap)
-                b_x_normalized.push(pi_b[0].clone()); // imaginario (swap)
-                pi_b_normalized.push(serde_json::Value::Array(b_x_normalized));
-                
-                let mut b_y_normalized = Vec::new();
-                b_y_normalized.push(pi_b[3].clone()); // real (swap)
-                b_y_normalized.push(pi_b[2].clone()); // imaginario (swap)
-                pi_b_normalized.push(serde_json::Value::Array(b_y_normalized));
-            } else {
-                return Err(format!("Invalid pi_b format: expected nested arrays or 6+ elements, got {} elements", pi_b.len()));
-            }
-        } else {
-            return Err(format!("Invalid pi_b format: expected at least 2 elements, got {}", pi_b.len()));
-        }
-        
-        normalized.insert("pi_b".to_string(), serde_json::Value::Array(pi_b_normalized));
-    } else {
-        return Err("Missing pi_b".to_string());
-    }
-    
-    // Extract and normalize pi_c
-    // snarkjs format: [x, y] (2 elements)
-    // rapidsnark format: [x, y, "1"] (3 elements)
-    // Garaga expects snarkjs format (2 elements)
-    if let Some(pi_c) = proof.get("pi_c").and_then(|v| v.as_array()) {
-        if pi_c.len() >= 2 {
-            let mut pi_c_normalized = Vec::new();
-            pi_c_normalized.push(pi_c[0].clone());
-            pi_c_normalized.push(pi_c[1].clone());
-            // Always use 2 elements (snarkjs format) - remove "1" if present
-            normalized.insert("pi_c".to_string(), serde_json::Value::Array(pi_c_normalized));
-        } else {
-            return Err(format!("Invalid pi_c format: expected at least 2 elements, got {}", pi_c.len()));
-        }
-    } else {
-        return Err("Missing pi_c".to_string());
-    }
-    
-    // snarkjs proof format does NOT include 'protocol' or 'curve' fields
-    // Garaga detects the curve from the VK file
-    // So we should NOT include these fields to match snarkjs format exactly
-    
-    Ok(serde_json::Value::Object(normalized))
+/// Steps 1-3 of proof generation: compute the witness and the Groth16 proof and write them
+/// to `proof_file` / `public_file` in snarkjs' JSON layout. Proves in-process when the native
+/// prover has the circuit loaded, otherwise through node/snarkjs (rapidsnark when asp/bin/prover
+/// exists). Fails up front when the registry has no wasm or zkey for the circuit. Returns the
+/// prover used, for logs
+async fn prove_to_files(
+    circuit: &str,
+    artifacts: &ArtifactRegistry,
+    input_json: &serde_json::Value,
+    proof_file: &Path,
+    public_file: &Path,
+    timestamp: u128,
+    proof_memory_mb: u32,
+) -> Result<&'static str, ProofError> {
+    artifacts.circuit(circuit)?;
+    let prover = match prove_natively(circuit, input_json, proof_file, public_file).await {
+        Some(result) => result.map(|_| "native prover")?,
+        None => prove_with_snarkjs(circuit, artifacts, input_json, proof_file, public_file, timestamp, proof_memory_mb).await?,
+    };
+    verify_proof_files(circuit, artifacts, input_json, proof_file, public_file)?;
+    Ok(prover)
 }
 
-/// Parse Garaga CLI array output format
-/// Garaga can output in different formats, we support the "array" format
-fn parse_garaga_array_output(output: &str) -> Result<Vec<String>, String> {
-    // Garaga array format output looks like:
-    // [0x123, 0x456, ...] or array elements on separate lines
-    let trimmed = output.trim();
-    
-    // Try parsing as JSON array first
-    if trimmed.starts_with('[') && trimmed.ends_with(']') {
-        // Remove brackets and split by comma
-        let inner = &trimmed[1..trimmed.len()-1];
-        let values: Vec<String> = inner
-            .split(',')
-            .map(|s| {
-                let s = s.trim();
-                // Remove quotes if present
-                if s.starts_with('"') && s.ends_with('"') {
-                    s[1..s.len()-1].to_string()
-                } else {
-                    s.to_string()
-                }
-            })
-            .filter(|s| !s.is_empty())
-            .collect();
-        
-        if values.is_empty() {
-            return Err("Garaga output is empty".to_string());
-        }
-        
-        return Ok(values);
-    }
-    
-    // Try parsing line by line (snforge format)
-    let lines: Vec<String> = trimmed
-        .lines()
-        .map(|l| l.trim().to_string())
-        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("//"))
-        .collect();
-    
-    if !lines.is_empty() {
-        return Ok(lines);
+/// Check the proof against the circuit's verification key before it goes any further, so a
+/// bad witness or a zkey the verifier does not match fails here instead of on-chain
+fn verify_proof_files(
+    circuit: &str,
+    artifacts: &ArtifactRegistry,
+    input_json: &serde_json::Value,
+    proof_file: &Path,
+    public_file: &Path,
+) -> Result<(), ProofError> {
+    println!("[Proof] 🔍 Verifying proof against the '{}' verification key...", circuit);
+    crate::jobs::report("verifying", 70);
+    let read = |path: &Path| -> Result<serde_json::Value, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid JSON in {:?}: {}", path, e))
+    };
+    let (proof, public_signals) = (read(proof_file)?, read(public_file)?);
+    let key = artifacts
+        .circuit(circuit)?
+        .verification_key
+        .as_ref()
+        .ok_or_else(|| format!("No verification key for '{}' circuit", circuit))?;
+    key.verify(&proof, &public_signals, input_json).map_err(|rejection| {
+        println!("[Proof] ❌ {}", rejection);
+        ProofError::Rejected(rejection)
+    })?;
+    println!("[Proof] ✅ Proof verified");
+    Ok(())
+}
+
+#[cfg(feature = "native-prover")]
+async fn prove_natively(
+    circuit: &str,
+    input_json: &serde_json::Value,
+    proof_file: &Path,
+    public_file: &Path,
+) -> Option<Result<(), String>> {
+    let prover = crate::prover::get().filter(|prover| prover.has_circuit(circuit))?;
+    println!("[Proof] 🔧 Steps 1-3: Calculating witness and proof in-process (native prover)...");
+    // A blocking task cannot be interrupted: a cancelled job's proof still runs to the end
+    // and is discarded
+    crate::jobs::report("proving", 20);
+    let proof_start = std::time::Instant::now();
+
+    let (circuit, inputs) = (circuit.to_string(), input_json.clone());
+    let result = tokio::task::spawn_blocking(move || prover.prove(&circuit, &inputs))
+        .await
+        .map_err(|e| format!("Native prover task failed: {}", e))
+        .and_then(|result| result)
+        .and_then(|proof| {
+            fs::write(proof_file, serde_json::to_string_pretty(&proof.proof_json()).unwrap())
+                .map_err(|e| format!("Failed to write proof file: {}", e))?;
+            fs::write(public_file, serde_json::to_string_pretty(&proof.public_json()).unwrap())
+                .map_err(|e| format!("Failed to write public signals: {}", e))
+        });
+    if result.is_ok() {
+        println!("[Proof] ✅ Proof generated in-process in {:.2}s", proof_start.elapsed().as_secs_f64());
     }
-    
-    Err(format!("Failed to parse Garaga output: {}", trimmed))
+    Some(result)
 }
 
-/// Generate withdraw proof using Circom circuit
-pub async fn generate_withdraw_proof(
-    _circuits_path: &str,
-    _inputs: WithdrawProofInputs,
-) -> Result<WithdrawProof, String> {
-    // TODO: Implement Circom proof generation
-    Err("Withdraw proof generation not yet implemented".to_string())
+#[cfg(not(feature = "native-prover"))]
+async fn prove_natively(
+    _circuit: &str,
+    _input_json: &serde_json::Value,
+    _proof_file: &Path,
+    _public_file: &Path,
+) -> Option<Result<(), String>> {
+    None
 }
 
-/// Generate LP proof (mint/burn) using rapidsnark (fast) with correct format conversion
-/// This function works for both mint and burn operations (same circuit)
-pub async fn generate_lp_proof(
-    circuits_path: &str,
-    input_json: serde_json::Value,
-) -> Result<LiquidityProof, String> {
-    println!("[Proof] 🔄 Starting LP proof generation with rapidsnark...");
-    let start_time = std::time::Instant::now();
-    
-    // Create temporary files
+/// Temp files removed on drop, so a failed or cancelled proof does not leave the circuit
+/// input (note secrets) or its witness behind
+struct TempFiles(Vec<PathBuf>);
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Witness with snarkjs under node, then the proof with rapidsnark if available, otherwise snarkjs
+async fn prove_with_snarkjs(
+    circuit: &str,
+    artifacts: &ArtifactRegistry,
+    input_json: &serde_json::Value,
+    proof_file: &Path,
+    public_file: &Path,
+    timestamp: u128,
+    proof_memory_mb: u32,
+) -> Result<&'static str, String> {
     let temp_dir = std::env::temp_dir();
-    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
-        .unwrap().as_nanos();
-    let input_file = temp_dir.join(format!("lp_input_{}.json", timestamp));
-    let witness_file = temp_dir.join(format!("lp_witness_{}.wtns", timestamp));
-    let proof_file = temp_dir.join(format!("lp_proof_{}.json", timestamp));
-    let public_file = temp_dir.join(format!("lp_public_{}.json", timestamp));
+    let input_file = temp_dir.join(format!("{}_input_{}.json", circuit, timestamp));
+    let witness_file = temp_dir.join(format!("{}_witness_{}.wtns", circuit, timestamp));
+    let mut temp_files = TempFiles(vec![input_file.clone(), witness_file.clone()]);
     
     fs::write(&input_file, serde_json::to_string_pretty(&input_json).unwrap())
         .map_err(|e| format!("Failed to write input file: {}", e))?;
     
     println!("[Proof] 📝 Input file created: {:?}", input_file);
     
-    // Paths to circuit files
-    let circuits_dir = Path::new(circuits_path).canonicalize()
-        .map_err(|e| format!("Failed to canonicalize circuits path: {}", e))?;
-    let wasm_path = circuits_dir.join("build").join("lp").join("lp_js").join("lp.wasm");
-    let zkey_path = circuits_dir.join("build").join("zkeys").join("lp.zkey");
+    // Paths to circuit files, resolved and checked by the registry at startup
+    let circuits_dir = artifacts.circuits_dir();
+    let circuit_artifacts = artifacts.circuit(circuit)?;
+    let (wasm_path, zkey_path) = (&circuit_artifacts.wasm.path, &circuit_artifacts.zkey.path);
     
     // Check for rapidsnark binary
     let asp_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
     let rapidsnark_path = asp_dir.join("bin").join("prover");
     let use_rapidsnark = rapidsnark_path.exists();
     
-    if !wasm_path.exists() {
-        return Err(format!("WASM file not found: {:?}", wasm_path));
-    }
-    if !zkey_path.exists() {
-        return Err(format!("ZKey file not found: {:?}", zkey_path));
-    }
-    
     // Step 1: Calculate witness using snarkjs (this is fast)
     println!("[Proof] 🔧 Step 1: Calculating witness with snarkjs...");
+    crate::jobs::report("witness", 10);
     let witness_script = format!(
         r#"
         const snarkjs = require('snarkjs');
@@ -672,39 +305,40 @@ pub async fn generate_lp_proof(
     );
     
     let script_file = circuits_dir.join(format!("witness_script_{}.js", timestamp));
+    temp_files.0.push(script_file.clone());
     fs::write(&script_file, witness_script)
         .map_err(|e| format!("Failed to write witness script: {}", e))?;
     
     let witness_start = std::time::Instant::now();
     let witness_output = Command::new("node")
+        .kill_on_drop(true)
         .env("NODE_OPTIONS", "--max-old-space-size=4096")
         .arg(script_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
+        .current_dir(circuits_dir)
         .output()
         .await
         .map_err(|e| format!("Failed to run witness calculation: {}", e))?;
     
-    let _ = fs::remove_file(&script_file);
-    
     if !witness_output.status.success() {
         let stderr = String::from_utf8_lossy(&witness_output.stderr);
         let stdout = String::from_utf8_lossy(&witness_output.stdout);
-        let _ = fs::remove_file(&input_file);
         return Err(format!("Witness calculation failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
     }
     
     println!("[Proof] ✅ Witness calculated in {:.2}s", witness_start.elapsed().as_secs_f64());
     
     // Step 2: Generate proof (use rapidsnark if available, otherwise snarkjs)
+    crate::jobs::report("proving", 30);
     if use_rapidsnark {
         println!("[Proof] 🔧 Step 2: Generating proof with rapidsnark (fast C++ prover)...");
         let proof_start = std::time::Instant::now();
         
         let rapidsnark_output = Command::new(&rapidsnark_path)
-            .arg(&zkey_path)
+            .kill_on_drop(true)
+            .arg(zkey_path)
             .arg(&witness_file)
-            .arg(&proof_file)
-            .arg(&public_file)
+            .arg(proof_file)
+            .arg(public_file)
             .output()
             .await
             .map_err(|e| format!("Failed to run rapidsnark: {}", e))?;
@@ -712,8 +346,6 @@ pub async fn generate_lp_proof(
         if !rapidsnark_output.status.success() {
             let stderr = String::from_utf8_lossy(&rapidsnark_output.stderr);
             let stdout = String::from_utf8_lossy(&rapidsnark_output.stdout);
-            let _ = fs::remove_file(&input_file);
-            let _ = fs::remove_file(&witness_file);
             return Err(format!("rapidsnark failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
         }
         
@@ -753,201 +385,235 @@ pub async fn generate_lp_proof(
         );
         
         let script_file2 = circuits_dir.join(format!("proof_script_{}.js", timestamp));
+        temp_files.0.push(script_file2.clone());
         fs::write(&script_file2, proof_script)
             .map_err(|e| format!("Failed to write proof script: {}", e))?;
         
         let proof_start = std::time::Instant::now();
-        let mut child = Command::new("node")
-            .env("NODE_OPTIONS", "--max-old-space-size=4096")
+        let child = Command::new("node")
+            .kill_on_drop(true)
+            .env("NODE_OPTIONS", format!("--max-old-space-size={}", proof_memory_mb))
             .arg(script_file2.file_name().unwrap())
-            .current_dir(&circuits_dir)
+            .current_dir(circuits_dir)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()
             .map_err(|e| format!("Failed to spawn node: {}", e))?;
         
-        // Wait with progress updates
-        let mut last_log = std::time::Instant::now();
+        // Wait with a progress line every 30s; dropping this future (a cancelled job) kills node
+        let output = child.wait_with_output();
+        tokio::pin!(output);
+        let mut progress = tokio::time::interval(tokio::time::Duration::from_secs(30));
+        progress.tick().await;
         let output = loop {
-            match child.try_wait() {
-                Ok(Some(_)) => {
-                    let output = child.wait_with_output().await
-                        .map_err(|e| format!("Failed to get output: {}", e))?;
-                    break output;
+            tokio::select! {
+                output = &mut output => break output.map_err(|e| format!("Failed to get output: {}", e))?,
+                _ = progress.tick() => {
+                    println!("[Proof] ⏳ Still processing... ({}s elapsed)", proof_start.elapsed().as_secs());
                 }
-                Ok(None) => {
-                    if last_log.elapsed().as_secs() >= 30 {
-                        println!("[Proof] ⏳ Still processing... ({}s elapsed)", proof_start.elapsed().as_secs());
-                        last_log = std::time::Instant::now();
-                    }
-                    tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-                }
-                Err(e) => return Err(format!("Error waiting: {}", e)),
             }
         };
         
-        let _ = fs::remove_file(&script_file2);
-        
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             let stdout = String::from_utf8_lossy(&output.stdout);
-            let _ = fs::remove_file(&input_file);
-            let _ = fs::remove_file(&witness_file);
             return Err(format!("snarkjs proof failed:\nSTDOUT: {}\nSTDERR: {}", stdout, stderr));
         }
         
         println!("[Proof] ✅ Proof generated with snarkjs in {:.2}s", proof_start.elapsed().as_secs_f64());
     }
     
-    // Step 3: Add protocol field to proof (required by convert_garaga.py script)
-    println!("[Proof] 🔧 Step 3: Adding protocol field to proof...");
-    let add_protocol_script = format!(
-        r#"
-        const fs = require('fs');
-        const proof = JSON.parse(fs.readFileSync('{}', 'utf8'));
-        
-        // Add protocol field if not present (required by convert_garaga.py)
-        if (!proof.protocol) {{
-            proof.protocol = "groth16";
-        }}
-        
-        // Ensure pi_a, pi_b, pi_c are in correct format (remove extra elements)
-        if (proof.pi_a && proof.pi_a.length > 2) {{
-            proof.pi_a = [proof.pi_a[0], proof.pi_a[1]];
-        }}
-        if (proof.pi_b && proof.pi_b.length > 2) {{
-            proof.pi_b = [proof.pi_b[0], proof.pi_b[1]];
-        }}
-        if (proof.pi_c && proof.pi_c.length > 2) {{
-            proof.pi_c = [proof.pi_c[0], proof.pi_c[1]];
-        }}
-        
-        fs.writeFileSync('{}', JSON.stringify(proof, null, 2));
-        "#,
-        proof_file.to_str().unwrap().replace('\\', "/"),
-        proof_file.to_str().unwrap().replace('\\', "/")
-    );
-    
-    let protocol_file = circuits_dir.join(format!("add_protocol_{}.js", timestamp));
-    fs::write(&protocol_file, add_protocol_script)
-        .map_err(|e| format!("Failed to write protocol script: {}", e))?;
-    
-    let protocol_output = Command::new("node")
-        .arg(protocol_file.file_name().unwrap())
-        .current_dir(&circuits_dir)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run protocol script: {}", e))?;
-    
-    let _ = fs::remove_file(&protocol_file);
-    
-    if !protocol_output.status.success() {
-        let stderr = String::from_utf8_lossy(&protocol_output.stderr);
-        return Err(format!("Failed to add protocol field: {}", stderr));
+    Ok(if use_rapidsnark { "rapidsnark" } else { "snarkjs" })
+}
+
+/// Generate withdraw proof (native prover, rapidsnark or snarkjs) with Garaga-formatted proof
+pub async fn generate_withdraw_proof(
+    artifacts: &ArtifactRegistry,
+    inputs: WithdrawProofInputs,
+) -> Result<WithdrawProof, ProofError> {
+    println!("[Proof] 🔄 Starting withdraw proof generation...");
+    let start_time = std::time::Instant::now();
+
+    // Public inputs first, in the order the contract reads them back from the verifier
+    let input_json = serde_json::json!({
+        "nullifier": inputs.nullifier,
+        "root": inputs.root,
+        "recipient": inputs.recipient,
+        "amount": inputs.amount.to_string(),
+        "secret": inputs.secret,
+        "pathElements": inputs.merkle_path,
+        "pathIndices": inputs.merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+    });
+
+    // Temporary files read by the Garaga step
+    let temp_dir = std::env::temp_dir();
+    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+        .unwrap().as_nanos();
+    let proof_file = temp_dir.join(format!("withdraw_proof_{}.json", timestamp));
+    let public_file = temp_dir.join(format!("withdraw_public_{}.json", timestamp));
+
+    let prover = prove_to_files("withdraw", artifacts, &input_json, &proof_file, &public_file, timestamp, 4096).await?;
+
+    // Public inputs order (withdraw circuit):
+    // 0: nullifier (felt252)
+    // 1: root (felt252)
+    // 2: recipient (ContractAddress)
+    // 3: amount (u128)
+    let result = match read_public_inputs(&public_file, "withdraw", 4, &[0, 1, 2]) {
+        Ok(public_inputs) => garaga_proof_calldata(artifacts, "withdraw", &proof_file, &public_file, &public_inputs)
+            .await
+            .map(|proof| WithdrawProof { proof, public_inputs }),
+        Err(e) => Err(e),
+    };
+
+    let _ = fs::remove_file(&proof_file);
+    let _ = fs::remove_file(&public_file);
+
+    if result.is_ok() {
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[Proof] ✅ Total proof time: {:.2}s (with {})", elapsed, prover);
     }
-    
-    println!("[Proof] ✅ Protocol field added to proof");
-    
-    // Step 4: Convert proof to Garaga format and generate calldata using Python script
+    result.map_err(ProofError::from)
+}
+
+/// Generate a membership proof that a note's commitment is a leaf under an associated-set root
+pub async fn generate_membership_proof(
+    artifacts: &ArtifactRegistry,
+    inputs: MembershipProofInputs,
+) -> Result<MembershipProof, ProofError> {
+    println!("[Proof] 🔄 Starting membership proof generation...");
+    let start_time = std::time::Instant::now();
+
+    // Public inputs first, in the order the verifier returns them
+    let input_json = serde_json::json!({
+        "root": inputs.root,
+        "commitment": inputs.commitment,
+        "secret": inputs.secret,
+        "nullifier": inputs.nullifier,
+        "amount": inputs.amount.to_string(),
+        "pathElements": inputs.merkle_path,
+        "pathIndices": inputs.merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+    });
+
+    let temp_dir = std::env::temp_dir();
+    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+        .unwrap().as_nanos();
+    let proof_file = temp_dir.join(format!("membership_proof_{}.json", timestamp));
+    let public_file = temp_dir.join(format!("membership_public_{}.json", timestamp));
+
+    let prover = prove_to_files("membership", artifacts, &input_json, &proof_file, &public_file, timestamp, 4096).await?;
+
+    // Public inputs order (membership circuit):
+    // 0: root (felt252)
+    // 1: commitment (felt252)
+    // The proof is returned as snarkjs wrote it, checked against the membership verification key
+    let result = read_public_inputs(&public_file, "membership", 2, &[0, 1]).and_then(|public_inputs| {
+        let proof = serde_json::from_str(
+            &fs::read_to_string(&proof_file).map_err(|e| format!("Failed to read proof file: {}", e))?,
+        )
+        .map_err(|e| format!("Failed to parse proof file: {}", e))?;
+        Ok(MembershipProof { proof, public_inputs })
+    });
+
+    let _ = fs::remove_file(&proof_file);
+    let _ = fs::remove_file(&public_file);
+
+    if result.is_ok() {
+        let elapsed = start_time.elapsed().as_secs_f64();
+        println!("[Proof] ✅ Total proof time: {:.2}s (with {})", elapsed, prover);
+    }
+    result.map_err(ProofError::from)
+}
+
+/// Step 4: Build the circuit verifier's full_proof_with_hints for a snarkjs proof file: the
+/// proof as u384 limbs, the public inputs, and the MPCheck and MSM hints from the garaga CLI
+async fn garaga_proof_calldata(
+    artifacts: &ArtifactRegistry,
+    circuit: &str,
+    proof_file: &Path,
+    public_file: &Path,
+    public_inputs: &[String],
+) -> Result<Vec<String>, String> {
     println!("[Proof] 🔧 Step 4: Converting proof to Garaga format and generating calldata...");
+    crate::jobs::report("formatting", 80);
     let garaga_start = std::time::Instant::now();
-    
-    // Get script path (relative to project root)
-    let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent()
-        .ok_or("Failed to get project root")?;
-    let script_path = project_root.join("scripts").join("convert_garaga.py");
-    
-    if !script_path.exists() {
-        return Err(format!("Garaga conversion script not found: {:?}", script_path));
-    }
-    
-    // Call Python script to convert proof and generate calldata directly
-    let script_output = Command::new("python3")
-        .arg(&script_path)
-        .arg(&proof_file)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run convert_garaga.py script: {}", e))?;
-    
-    if !script_output.status.success() {
-        let stderr = String::from_utf8_lossy(&script_output.stderr);
-        let stdout = String::from_utf8_lossy(&script_output.stdout);
-        println!("[Proof] ❌ Python script failed.");
-        println!("[Proof] 📋 STDERR:\n{}", stderr);
-        println!("[Proof] 📋 STDOUT:\n{}", stdout);
-        println!("[Proof] 💾 Proof saved at: {:?}", proof_file);
-        
-        let _ = fs::remove_file(&input_file);
-        let _ = fs::remove_file(&witness_file);
-        let _ = fs::remove_file(&public_file);
-        
-        return Err(format!(
-            "Garaga conversion script failed.\n\
-             STDERR: {}\n\
-             STDOUT: {}\n\
-             \n\
-             Proof file at: {:?}",
-            stderr, stdout, proof_file
-        ));
-    }
-    
-    // Parse calldata from script output (JSON array)
-    let script_stdout = String::from_utf8_lossy(&script_output.stdout);
-    let proof_calldata_raw: Vec<String> = serde_json::from_str(script_stdout.trim())
-        .map_err(|e| format!("Failed to parse calldata from script: {}. Output: {}", e, script_stdout))?;
-    
-    // Apply felt252 modulo to all proof values (BN254 field values can exceed felt252 max)
-    // STARKNET_FELT_MAX = 2^251 + 17 * 2^192 + 1
+
+    let vk_file = &artifacts.circuit(circuit)?.vk.path;
+    let proof_calldata =
+        crate::garaga::full_proof_with_hints(artifacts.garaga_bin(), vk_file, proof_file, public_file, public_inputs)
+            .await
+            .map_err(|e| format!("Garaga calldata generation failed: {}", e))?;
+
+    println!("[Proof] ✅ Garaga calldata generated in {:.2}s", garaga_start.elapsed().as_secs_f64());
+    Ok(proof_calldata)
+}
+
+/// Read a circuit's public signals as decimal strings
+/// The felt252 ones (`felt_indices`) must be below the Starknet prime: the contract reads them
+/// back as felt252, and reducing them would change the inputs the proof was made for
+fn read_public_inputs(
+    public_file: &Path,
+    circuit: &str,
+    expected: usize,
+    felt_indices: &[usize],
+) -> Result<Vec<String>, String> {
     use num_bigint::BigUint;
     use num_traits::Num;
     use std::str::FromStr;
-    let felt_max_str = "3618502788666131106986593281521497120414687020801267626233049500247285301248";
-    let felt_max_big = BigUint::from_str(felt_max_str)
-        .map_err(|_| "Failed to parse FELT_MAX constant".to_string())?;
-    
-    let proof_calldata: Vec<String> = proof_calldata_raw
-        .iter()
-        .map(|val_str| {
-            // Parse as BigUint (handles both hex and decimal)
-            let value_big = if val_str.starts_with("0x") {
-                BigUint::from_str_radix(&val_str[2..], 16)
-                    .unwrap_or_else(|_| BigUint::from(0u8))
-            } else {
-                BigUint::from_str(val_str)
-                    .unwrap_or_else(|_| BigUint::from(0u8))
-            };
-            
-            // Apply felt252 modulo if value exceeds limit
-            let modulo_big = if value_big >= felt_max_big {
-                &value_big % &felt_max_big
-            } else {
-                value_big.clone()
-            };
-            
-            // Convert to string (decimal format for felt252)
-            modulo_big.to_string()
-        })
-        .collect();
-    
-    println!("[Proof] ✅ Garaga calldata generated in {:.2}s", garaga_start.elapsed().as_secs_f64());
-    println!("[Proof]    Proof calldata length: {} elements", proof_calldata.len());
-    
-    // Read public signals for the response
+
     let public_signals: Vec<serde_json::Value> = serde_json::from_str(
-        &fs::read_to_string(&public_file)
+        &fs::read_to_string(public_file)
             .map_err(|e| format!("Failed to read public signals: {}", e))?
     ).map_err(|e| format!("Failed to parse public signals: {}", e))?;
-    
-    // CRITICAL: Validate public_signals length BEFORE processing
-    // LP circuit has 7 public inputs: nullifier, root, tick_lower, tick_upper, liquidity, new_commitment, position_commitment
-    if public_signals.len() != 7 {
+    if public_signals.len() != expected {
         return Err(format!(
-            "Invalid public signals length: expected 7 elements (LP circuit), got {}. \
-            This indicates the circuit did not generate the expected number of public inputs. \
-            Check the circuit output and ensure all 7 public inputs are being generated.",
-            public_signals.len()
+            "Invalid public signals length: expected {} elements ({} circuit), got {}",
+            expected, circuit, public_signals.len()
         ));
     }
+
+    let prime = BigUint::from_str(crate::garaga::STARKNET_PRIME)
+        .map_err(|_| "Failed to parse STARKNET_PRIME".to_string())?;
+
+    public_signals
+        .iter()
+        .enumerate()
+        .map(|(idx, s)| {
+            let value_str = s.as_str()
+                .ok_or_else(|| format!("Public signal at index {} is not a string: {:?}", idx, s))?;
+            let value_big = match value_str.strip_prefix("0x") {
+                Some(hex) => BigUint::from_str_radix(hex, 16),
+                None => BigUint::from_str(value_str),
+            }
+            .map_err(|e| format!("Failed to parse public signal at index {}: {}", idx, e))?;
+            if felt_indices.contains(&idx) && value_big >= prime {
+                return Err(format!(
+                    "Public signal at index {} ({} circuit) is not a felt252: {} is not below the Starknet prime",
+                    idx, circuit, value_big
+                ));
+            }
+            Ok(value_big.to_string())
+        })
+        .collect()
+}
+
+/// Generate LP proof (mint/burn) (native prover, rapidsnark or snarkjs) with correct format conversion
+/// This function works for both mint and burn operations (same circuit)
+pub async fn generate_lp_proof(
+    artifacts: &ArtifactRegistry,
+    input_json: serde_json::Value,
+) -> Result<LiquidityProof, ProofError> {
+    println!("[Proof] 🔄 Starting LP proof generation...");
+    let start_time = std::time::Instant::now();
+    
+    // Temporary files read by the Garaga step
+    let temp_dir = std::env::temp_dir();
+    let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+        .unwrap().as_nanos();
+    let proof_file = temp_dir.join(format!("lp_proof_{}.json", timestamp));
+    let public_file = temp_dir.join(format!("lp_public_{}.json", timestamp));
+    
+    let prover = prove_to_files("lp", artifacts, &input_json, &proof_file, &public_file, timestamp, 4096).await?;
     
     // Public inputs order (LP circuit):
     // 0: nullifier (felt252)
@@ -961,55 +627,20 @@ pub async fn generate_lp_proof(
     // NOTE: The verifier returns values as u256, and the contract converts felt252 values
     // (indices 0, 1, 5, 6) from u256 to felt252 using reconstruction when high != 0.
     
-    // Read public signals and apply felt252 modulo for felt252 values
-    let public_inputs: Vec<String> = public_signals
-        .iter()
-        .enumerate()
-        .map(|(idx, s)| {
-            let value_str = s.as_str()
-                .ok_or_else(|| format!("Public signal at index {} is not a string: {:?}", idx, s))?;
-            // Parse as BigUint (handles both hex and decimal)
-            let value_big = if value_str.starts_with("0x") {
-                BigUint::from_str_radix(&value_str[2..], 16)
-                    .map_err(|e| format!("Failed to parse hex value at index {}: {}", idx, e))?
-            } else {
-                BigUint::from_str(value_str)
-                    .map_err(|e| format!("Failed to parse decimal value at index {}: {}", idx, e))?
-            };
-            
-            // Apply felt252 modulo only for felt252 values (indices 0, 1, 5, 6)
-            // tick_lower, tick_upper, liquidity are not felt252, so don't apply modulo
-            let modulo_big = if idx == 0 || idx == 1 || idx == 5 || idx == 6 {
-                if value_big >= felt_max_big {
-                    &value_big % &felt_max_big
-                } else {
-                    value_big.clone()
-                }
-            } else {
-                value_big.clone()
-            };
-            
-            // Convert to string (decimal format for felt252)
-            Ok(modulo_big.to_string())
-        })
-        .collect::<Result<Vec<String>, String>>()?;
+    use num_bigint::BigUint;
+    use std::str::FromStr;
+    let public_inputs = read_public_inputs(&public_file, "lp", 7, &[0, 1, 5, 6])?;
     
-    // Proof calldata should only contain the 8 proof elements (A.x, A.y, B.x0, B.x1, B.y0, B.y1, C.x, C.y)
-    // Public inputs are returned separately
-    // The contract expects: proof (8 elements) and public_inputs (7 elements) as separate arrays
-    let proof_len = proof_calldata.len();
+    // Step 4: Encode the proof as Garaga calldata (full_proof_with_hints)
+    // Public inputs are also returned separately: the contract checks their count
+    let proof_calldata = garaga_proof_calldata(artifacts, "lp", &proof_file, &public_file, &public_inputs).await?;
     
-    println!("[Proof]    Proof calldata length: {} elements (should be 8)", proof_len);
+    println!("[Proof]    Proof calldata length: {} elements", proof_calldata.len());
     println!("[Proof]    Public inputs length: {} elements (should be 7)", public_inputs.len());
     
-    // Verify proof has exactly 8 elements
-    if proof_len != 8 {
-        return Err(format!("Invalid proof length: expected 8 elements, got {}", proof_len));
-    }
-    
     // Verify public inputs have exactly 7 elements
     if public_inputs.len() != 7 {
-        return Err(format!("Invalid public inputs length: expected 7 elements, got {}", public_inputs.len()));
+        return Err(format!("Invalid public inputs length: expected 7 elements, got {}", public_inputs.len()).into());
     }
     
     // Log and validate felt252 values (indices 0, 1, 5, 6) to help diagnose conversion issues
@@ -1037,82 +668,82 @@ pub async fn generate_lp_proof(
     }
     
     // Clean up temp files
-    let _ = fs::remove_file(&input_file);
-    let _ = fs::remove_file(&witness_file);
     let _ = fs::remove_file(&proof_file);
     let _ = fs::remove_file(&public_file);
     
     let elapsed = start_time.elapsed().as_secs_f64();
-    println!("[Proof] ✅ Total proof time: {:.2}s ({})", elapsed, 
-        if use_rapidsnark { "with rapidsnark" } else { "with snarkjs" });
+    println!("[Proof] ✅ Total proof time: {:.2}s (with {})", elapsed, prover);
     
     Ok(LiquidityProof {
-        proof: proof_calldata, // Only the 8 proof elements, not combined with public inputs
+        proof: proof_calldata,
         public_inputs,
     })
 }
 
 /// Generate mint liquidity proof using Circom circuit
 pub async fn generate_mint_liquidity_proof(
-    circuits_path: &str,
+    artifacts: &ArtifactRegistry,
     inputs: MintProofInputs,
-) -> Result<LiquidityProof, String> {
-    // Convert MintProofInputs to JSON for generate_lp_proof
-    let input_json = serde_json::json!({
+) -> Result<LiquidityProof, ProofError> {
+    generate_lp_proof(artifacts, lp_input_json(&inputs)).await
+}
+
+/// Generate burn liquidity proof using Circom circuit (same LP circuit as mint)
+pub async fn generate_burn_liquidity_proof(
+    artifacts: &ArtifactRegistry,
+    inputs: BurnProofInputs,
+) -> Result<LiquidityProof, ProofError> {
+    generate_lp_proof(artifacts, lp_input_json(&inputs)).await
+}
+
+/// Generate collect proof: the LP circuit with no liquidity, which re-issues the note whole
+/// (private_collect verifies against the LP verifier)
+pub async fn generate_collect_proof(
+    artifacts: &ArtifactRegistry,
+    inputs: CollectProofInputs,
+) -> Result<LiquidityProof, ProofError> {
+    if inputs.liquidity != 0 || inputs.new_amount != inputs.amount {
+        return Err(format!(
+            "A collect proof moves no liquidity: got liquidity {} and change {} for a note of {}",
+            inputs.liquidity, inputs.new_amount, inputs.amount
+        )
+        .into());
+    }
+    generate_lp_proof(artifacts, lp_input_json(&inputs)).await
+}
+
+/// LP circuit input: public inputs first, ticks as signed decimals like the frontend sends them
+fn lp_input_json(inputs: &MintProofInputs) -> serde_json::Value {
+    serde_json::json!({
         "nullifier": inputs.nullifier,
         "root": inputs.root,
-        "tick_lower": inputs.tick_lower,
-        "tick_upper": inputs.tick_upper,
-        "liquidity": inputs.liquidity,
-        "new_commitment": "", // Will be calculated from secret_out, nullifier_out, amount_out
-        "position_commitment": "", // Will be calculated from secret_in, tick_lower, tick_upper
+        "tick_lower": inputs.tick_lower.to_string(),
+        "tick_upper": inputs.tick_upper.to_string(),
+        "liquidity": inputs.liquidity.to_string(),
+        "new_commitment": inputs.new_commitment,
+        "position_commitment": inputs.position_commitment,
         "secret_in": inputs.secret,
-        "amount_in": inputs.amount,
+        "amount_in": inputs.amount.to_string(),
         "secret_out": inputs.new_secret,
         "nullifier_out": inputs.new_nullifier,
-        "amount_out": inputs.new_amount,
+        "amount_out": inputs.new_amount.to_string(),
         "pathElements": inputs.merkle_path,
         "pathIndices": inputs.merkle_path_indices.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
-    });
-    
-    generate_lp_proof(circuits_path, input_json).await
+    })
 }
 
-/// Generate burn liquidity proof using Circom circuit
-pub async fn generate_burn_liquidity_proof(
-    _circuits_path: &str,
-    _inputs: BurnProofInputs,
-) -> Result<LiquidityProof, String> {
-    // TODO: Implement Circom proof generation
-    Err("Burn liquidity proof generation not yet implemented".to_string())
+/// Signed amount as a swap public input: two's complement modulo 2^128, as the contract decodes it
+pub fn i128_public_input(value: i128) -> String {
+    (value as u128).to_string()
 }
 
-/// Format proof for Garaga verifier
-/// Garaga expects proof as array of felt252
-pub fn format_proof_for_garaga(_proof: &SwapProof) -> Vec<String> {
-    // TODO: Convert Groth16 proof format to Garaga format
-    // Garaga expects: [A.x, A.y, B.x0, B.x1, B.y0, B.y1, C.x, C.y, ...public_inputs]
-    vec![]
+/// Signed tick as a swap public input: two's complement modulo 2^32
+pub fn i32_public_input(value: i32) -> String {
+    (value as u32).to_string()
 }
 
 // Input/Output structures
 
-pub struct SwapProofInputs {
-    pub secret: String,
-    pub nullifier: String,
-    pub amount: u128,
-    pub merkle_path: Vec<String>,
-    pub merkle_path_indices: Vec<u32>,
-    pub root: String,
-    pub new_secret: String,
-    pub new_nullifier: String,
-    pub new_amount: u128,
-    // Swap-specific
-    pub zero_for_one: bool,
-    pub amount_specified: u128,
-    pub sqrt_price_limit: Option<(u128, u128)>,
-}
-
 pub struct WithdrawProofInputs {
     pub secret: String,
     pub nullifier: String,
@@ -1121,25 +752,21 @@ pub struct WithdrawProofInputs {
     pub merkle_path_indices: Vec<u32>,
     pub root: String,
     pub recipient: String,
-    pub token_address: String,
 }
 
-pub struct MintProofInputs {
+pub struct MembershipProofInputs {
     pub secret: String,
     pub nullifier: String,
     pub amount: u128,
+    /// The note's commitment, a leaf of the associated set
+    pub commitment: String,
     pub merkle_path: Vec<String>,
     pub merkle_path_indices: Vec<u32>,
+    /// Associated-set root the path leads to
     pub root: String,
-    pub tick_lower: i32,
-    pub tick_upper: i32,
-    pub liquidity: u128,
-    pub new_secret: String,
-    pub new_nullifier: String,
-    pub new_amount: u128,
 }
 
-pub struct BurnProofInputs {
+pub struct MintProofInputs {
     pub secret: String,
     pub nullifier: String,
     pub amount: u128,
@@ -1152,10 +779,20 @@ pub struct BurnProofInputs {
     pub new_secret: String,
     pub new_nullifier: String,
     pub new_amount: u128,
+    /// Change note commitment, from new_secret, new_nullifier and new_amount
+    pub new_commitment: String,
+    /// Position key, from secret and the tick range
+    pub position_commitment: String,
 }
 
+/// Burns prove the same statement as mints
+pub type BurnProofInputs = MintProofInputs;
+
+/// Collects prove it with liquidity 0 and new_amount equal to amount
+pub type CollectProofInputs = MintProofInputs;
+
 pub struct SwapProof {
-    pub proof: Vec<String>, // Groth16 proof formatted for Garaga
+    pub proof: Vec<String>, // full_proof_with_hints for the Garaga verifier
     pub public_inputs: Vec<String>,
 }
 
@@ -1164,6 +801,12 @@ pub struct WithdrawProof {
     pub public_inputs: Vec<String>,
 }
 
+pub struct MembershipProof {
+    /// Groth16 proof in snarkjs' JSON layout (pi_a, pi_b, pi_c)
+    pub proof: serde_json::Value,
+    pub public_inputs: Vec<String>,
+}
+
 pub struct LiquidityProof {
     pub proof: Vec<String>,
     pub public_inputs: Vec<String>,
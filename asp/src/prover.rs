@@ -0,0 +1,674 @@
+// In-process Groth16 prover (native-prover feature)
+// Loads each circuit's .zkey and circom witness WASM once at startup and proves with
+// arkworks, so proof generation needs neither node/snarkjs nor the rapidsnark binary.
+// The zkey reader, the witness calculator and the R1CS-to-QAP reduction follow snarkjs
+// (like ark-circom does), so proofs verify against the same verification keys.
+
+use crate::artifacts::ArtifactRegistry;
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInt, PrimeField, UniformRand};
+use ark_groth16::r1cs_to_qap::{evaluate_constraint, LibsnarkReduction, R1CSToQAP};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::{ConstraintMatrices, ConstraintSystemRef, SynthesisError};
+use num_bigint::{BigInt as SignedBigInt, BigUint, Sign};
+use num_traits::{Num, Zero};
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+use std::path::Path;
+use wasmi::core::Trap;
+use wasmi::{Caller, Engine, Extern, Instance, Linker, Module, Store, TypedFunc, WasmParams, WasmResults};
+
+static NATIVE_PROVER: OnceCell<NativeProver> = OnceCell::new();
+
+/// Load every circuit whose wasm and zkey are in the registry; missing ones are skipped
+/// and keep going through snarkjs
+pub fn init(artifacts: &ArtifactRegistry) -> Result<Vec<String>, String> {
+    let mut circuits = HashMap::new();
+    for (name, circuit_artifacts) in artifacts.iter() {
+        if !circuit_artifacts.is_ready() {
+            println!("[Proof] ⚠️  Native prover: no artifacts for '{}' circuit, skipping", name);
+            continue;
+        }
+
+        let start = std::time::Instant::now();
+        let circuit = CircuitProver::load(&circuit_artifacts.wasm.path, &circuit_artifacts.zkey.path)
+            .map_err(|e| format!("Failed to load '{}' circuit: {}", name, e))?;
+        println!("[Proof] ✅ Native prover: '{}' loaded in {:.2}s ({} constraints, {} public inputs)",
+            name, start.elapsed().as_secs_f64(), circuit.matrices.num_constraints, circuit.matrices.num_instance_variables - 1);
+        circuits.insert(name.to_string(), circuit);
+    }
+
+    let loaded = circuits.keys().cloned().collect();
+    NATIVE_PROVER
+        .set(NativeProver { circuits })
+        .map_err(|_| "Native prover already initialized".to_string())?;
+    Ok(loaded)
+}
+
+/// The loaded prover, if `init` ran
+pub fn get() -> Option<&'static NativeProver> {
+    NATIVE_PROVER.get()
+}
+
+pub struct NativeProver {
+    circuits: HashMap<String, CircuitProver>,
+}
+
+/// Proving key, constraint matrices and witness calculator of one circuit
+struct CircuitProver {
+    witness: WitnessCalculator,
+    proving_key: ProvingKey<Bn254>,
+    matrices: ConstraintMatrices<Fr>,
+}
+
+/// Groth16 proof with the public signals it proves
+pub struct NativeProof {
+    pub proof: Proof<Bn254>,
+    pub public_signals: Vec<BigUint>,
+}
+
+impl NativeProver {
+    pub fn has_circuit(&self, circuit: &str) -> bool {
+        self.circuits.contains_key(circuit)
+    }
+
+    /// Compute the witness for `inputs` (the same JSON snarkjs takes) and prove it
+    pub fn prove(&self, circuit: &str, inputs: &serde_json::Value) -> Result<NativeProof, String> {
+        let prover = self
+            .circuits
+            .get(circuit)
+            .ok_or_else(|| format!("Circuit '{}' is not loaded in the native prover", circuit))?;
+        prover.prove(inputs)
+    }
+}
+
+impl CircuitProver {
+    fn load(wasm_path: &Path, zkey_path: &Path) -> Result<Self, String> {
+        let wasm = std::fs::read(wasm_path).map_err(|e| format!("Failed to read {:?}: {}", wasm_path, e))?;
+        let zkey = std::fs::read(zkey_path).map_err(|e| format!("Failed to read {:?}: {}", zkey_path, e))?;
+        let witness = WitnessCalculator::new(&wasm)?;
+        let (proving_key, matrices) = read_zkey(&zkey)?;
+        Ok(Self { witness, proving_key, matrices })
+    }
+
+    fn prove(&self, inputs: &serde_json::Value) -> Result<NativeProof, String> {
+        let witness = self.witness.calculate(inputs)?;
+        let num_inputs = self.matrices.num_instance_variables;
+        if witness.len() != num_inputs + self.matrices.num_witness_variables {
+            return Err(format!(
+                "Witness has {} signals but the zkey expects {}",
+                witness.len(),
+                num_inputs + self.matrices.num_witness_variables
+            ));
+        }
+        let full_assignment: Vec<Fr> = witness.iter().map(|value| Fr::from(value.clone())).collect();
+
+        let mut rng = rand::thread_rng();
+        let r = Fr::rand(&mut rng);
+        let s = Fr::rand(&mut rng);
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &self.proving_key,
+            r,
+            s,
+            &self.matrices,
+            num_inputs,
+            self.matrices.num_constraints,
+            &full_assignment,
+        )
+        .map_err(|e| format!("Groth16 proving failed: {}", e))?;
+
+        Ok(NativeProof { proof, public_signals: witness[1..num_inputs].to_vec() })
+    }
+}
+
+impl NativeProof {
+    /// Proof in snarkjs' JSON layout, as the garaga CLI reads it
+    pub fn proof_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "pi_a": g1_json(&self.proof.a),
+            "pi_b": g2_json(&self.proof.b),
+            "pi_c": g1_json(&self.proof.c),
+            "protocol": "groth16",
+            "curve": "bn128",
+        })
+    }
+
+    /// Public signals in snarkjs' JSON layout (decimal strings)
+    pub fn public_json(&self) -> serde_json::Value {
+        serde_json::json!(self.public_signals.iter().map(|value| value.to_string()).collect::<Vec<_>>())
+    }
+}
+
+fn fq_string(value: &Fq) -> String {
+    BigUint::from(value.into_bigint()).to_string()
+}
+
+fn g1_json(point: &G1Affine) -> serde_json::Value {
+    if point.infinity {
+        return serde_json::json!(["0", "1", "0"]);
+    }
+    serde_json::json!([fq_string(&point.x), fq_string(&point.y), "1"])
+}
+
+fn g2_json(point: &G2Affine) -> serde_json::Value {
+    if point.infinity {
+        return serde_json::json!([["0", "0"], ["1", "0"], ["0", "0"]]);
+    }
+    serde_json::json!([
+        [fq_string(&point.x.c0), fq_string(&point.x.c1)],
+        [fq_string(&point.y.c0), fq_string(&point.y.c1)],
+        ["1", "0"]
+    ])
+}
+
+/// Witness map used by snarkjs
+/// snarkjs precomputes the H query in Lagrange form over a domain twice as large, so the
+/// witness map is the odd-index evaluations of A*B - C on that domain instead of the
+/// coefficients of (A*B - C) / Z that arkworks computes
+pub struct CircomReduction;
+
+impl R1CSToQAP for CircomReduction {
+    #[allow(clippy::type_complexity)]
+    fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+        cs: ConstraintSystemRef<F>,
+        t: &F,
+    ) -> Result<(Vec<F>, Vec<F>, Vec<F>, F, usize, usize), SynthesisError> {
+        LibsnarkReduction::instance_map_with_evaluation::<F, D>(cs, t)
+    }
+
+    fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> Result<Vec<F>, SynthesisError> {
+        let domain = D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let domain_size = domain.size();
+
+        let mut a = vec![F::zero(); domain_size];
+        let mut b = vec![F::zero(); domain_size];
+        for (i, (a_row, b_row)) in matrices.a.iter().zip(&matrices.b).take(num_constraints).enumerate() {
+            a[i] = evaluate_constraint(a_row, full_assignment);
+            b[i] = evaluate_constraint(b_row, full_assignment);
+        }
+        // snarkjs' extra constraints binding each public input
+        a[num_constraints..num_constraints + num_inputs].clone_from_slice(&full_assignment[..num_inputs]);
+
+        let mut c = vec![F::zero(); domain_size];
+        for i in 0..num_constraints {
+            c[i] = a[i] * b[i];
+        }
+
+        // Move every polynomial to the odd points of the doubled domain
+        let root_of_unity = D::new(2 * domain_size)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?
+            .element(1);
+        for values in [&mut a, &mut b, &mut c] {
+            domain.ifft_in_place(values);
+            D::distribute_powers_and_mul_by_const(values, root_of_unity, F::one());
+            domain.fft_in_place(values);
+        }
+
+        let mut ab = domain.mul_polynomials_in_evaluation_domain(&a, &b);
+        for (ab_i, c_i) in ab.iter_mut().zip(c) {
+            *ab_i -= &c_i;
+        }
+        Ok(ab)
+    }
+
+    fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
+        max_power: usize,
+        t: F,
+        _: F,
+        delta_inverse: F,
+    ) -> Result<Vec<F>, SynthesisError> {
+        // HZ has 2 * domain - 1 powers; keep the Lagrange coefficients at the odd points
+        let mut scalars: Vec<F> = (0..2 * max_power + 1)
+            .map(|i| delta_inverse * t.pow([i as u64]))
+            .collect();
+        let domain = D::new(scalars.len()).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        domain.ifft_in_place(&mut scalars);
+        Ok(scalars.into_iter().skip(1).step_by(2).collect())
+    }
+}
+
+/// Little-endian reader over a zkey file
+struct ZkeyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ZkeyReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "Unexpected end of zkey file".to_string())?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn bigint(&mut self) -> Result<BigInt<4>, String> {
+        let mut limbs = [0u64; 4];
+        for limb in limbs.iter_mut() {
+            *limb = self.u64()?;
+        }
+        Ok(BigInt::new(limbs))
+    }
+
+    /// Base field element, stored in Montgomery form like arkworks keeps it
+    fn fq(&mut self) -> Result<Fq, String> {
+        Ok(Fq::new_unchecked(self.bigint()?))
+    }
+
+    /// Coefficients are stored multiplied by R^2, so one Montgomery reduction is undone
+    fn fr_coefficient(&mut self) -> Result<Fr, String> {
+        Ok(Fr::new_unchecked(Fr::new_unchecked(self.bigint()?).into_bigint()))
+    }
+
+    fn g1(&mut self) -> Result<G1Affine, String> {
+        let (x, y) = (self.fq()?, self.fq()?);
+        if x.is_zero() && y.is_zero() {
+            return Ok(G1Affine::identity());
+        }
+        let point = G1Affine::new_unchecked(x, y);
+        if !point.is_on_curve() {
+            return Err("zkey has a G1 point off the curve".to_string());
+        }
+        Ok(point)
+    }
+
+    fn g2(&mut self) -> Result<G2Affine, String> {
+        let x = Fq2::new(self.fq()?, self.fq()?);
+        let y = Fq2::new(self.fq()?, self.fq()?);
+        if x.is_zero() && y.is_zero() {
+            return Ok(G2Affine::identity());
+        }
+        let point = G2Affine::new_unchecked(x, y);
+        if !point.is_on_curve() {
+            return Err("zkey has a G2 point off the curve".to_string());
+        }
+        Ok(point)
+    }
+}
+
+/// Parse a snarkjs Groth16 zkey into the arkworks proving key and the A/B constraint matrices
+/// Sections: 1 header, 2 Groth16 header, 3 IC, 4 coefficients, 5 A, 6 B1, 7 B2, 8 C (L), 9 H
+pub fn read_zkey(data: &[u8]) -> Result<(ProvingKey<Bn254>, ConstraintMatrices<Fr>), String> {
+    let mut reader = ZkeyReader { data, pos: 0 };
+    if reader.bytes(4)? != b"zkey" {
+        return Err("Not a zkey file".to_string());
+    }
+    let _version = reader.u32()?;
+    let num_sections = reader.u32()?;
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let id = reader.u32()?;
+        let len = usize::try_from(reader.u64()?).map_err(|_| "zkey section too large".to_string())?;
+        sections.entry(id).or_insert(reader.pos);
+        reader.bytes(len)?;
+    }
+    let section = |id: u32| -> Result<ZkeyReader, String> {
+        let pos = *sections.get(&id).ok_or_else(|| format!("zkey is missing section {}", id))?;
+        Ok(ZkeyReader { data, pos })
+    };
+
+    if section(1)?.u32()? != 1 {
+        return Err("zkey is not a Groth16 key".to_string());
+    }
+
+    let mut header = section(2)?;
+    let n8q = header.u32()?;
+    let q = header.bigint()?;
+    let n8r = header.u32()?;
+    let r = header.bigint()?;
+    if n8q != 32 || q != Fq::MODULUS || n8r != 32 || r != Fr::MODULUS {
+        return Err("zkey is not for the BN254 curve".to_string());
+    }
+    let n_vars = header.u32()? as usize;
+    let n_public = header.u32()? as usize;
+    let domain_size = header.u32()? as usize;
+    let alpha_g1 = header.g1()?;
+    let beta_g1 = header.g1()?;
+    let beta_g2 = header.g2()?;
+    let gamma_g2 = header.g2()?;
+    let delta_g1 = header.g1()?;
+    let delta_g2 = header.g2()?;
+
+    let g1_section = |reader: &mut ZkeyReader, count: usize| (0..count).map(|_| reader.g1()).collect::<Result<Vec<_>, _>>();
+    let gamma_abc_g1 = g1_section(&mut section(3)?, n_public + 1)?;
+    let a_query = g1_section(&mut section(5)?, n_vars)?;
+    let b_g1_query = g1_section(&mut section(6)?, n_vars)?;
+    let b_g2_query = {
+        let mut reader = section(7)?;
+        (0..n_vars).map(|_| reader.g2()).collect::<Result<Vec<_>, _>>()?
+    };
+    let l_query = g1_section(&mut section(8)?, n_vars - n_public - 1)?;
+    let h_query = g1_section(&mut section(9)?, domain_size)?;
+
+    // A and B rows from the coefficient section; snarkjs appends one constraint per public
+    // input (plus the constant), which the reduction adds back itself
+    let mut coefficients = section(4)?;
+    let num_coefficients = coefficients.u32()?;
+    let mut rows = [vec![Vec::new(); domain_size], vec![Vec::new(); domain_size]];
+    let mut max_constraint = 0;
+    for _ in 0..num_coefficients {
+        let matrix = coefficients.u32()? as usize;
+        let constraint = coefficients.u32()? as usize;
+        let signal = coefficients.u32()? as usize;
+        let value = coefficients.fr_coefficient()?;
+        let row = rows
+            .get_mut(matrix)
+            .and_then(|rows| rows.get_mut(constraint))
+            .ok_or_else(|| format!("zkey coefficient out of range (matrix {}, constraint {})", matrix, constraint))?;
+        row.push((value, signal));
+        max_constraint = max_constraint.max(constraint);
+    }
+    let num_constraints = max_constraint
+        .checked_sub(n_public)
+        .ok_or_else(|| "zkey has fewer constraints than public inputs".to_string())?;
+    let [mut a, mut b] = rows;
+    a.truncate(num_constraints);
+    b.truncate(num_constraints);
+
+    let vk = VerifyingKey::<Bn254> { alpha_g1, beta_g2, gamma_g2, delta_g2, gamma_abc_g1 };
+    let proving_key = ProvingKey { vk, beta_g1, delta_g1, a_query, b_g1_query, b_g2_query, h_query, l_query };
+    let matrices = ConstraintMatrices {
+        num_instance_variables: n_public + 1,
+        num_witness_variables: n_vars - n_public - 1,
+        num_constraints,
+        a_num_non_zero: a.iter().map(Vec::len).sum(),
+        b_num_non_zero: b.iter().map(Vec::len).sum(),
+        c_num_non_zero: 0,
+        a,
+        b,
+        c: Vec::new(),
+    };
+    Ok((proving_key, matrices))
+}
+
+/// Runs circom's witness-generation WASM (circom 2 runtime) in-process
+struct WitnessCalculator {
+    engine: Engine,
+    module: Module,
+}
+
+/// 64-bit FNV-1a of a signal name, as circom keys its input signals
+fn fnv_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Flatten an input value (number, numeric string or nested array) into field elements
+fn flatten_input(value: &serde_json::Value, prime: &BigUint, out: &mut Vec<BigUint>) -> Result<(), String> {
+    let parsed = match value {
+        serde_json::Value::Array(values) => {
+            for value in values {
+                flatten_input(value, prime, out)?;
+            }
+            return Ok(());
+        }
+        serde_json::Value::Number(number) => SignedBigInt::from_str_radix(&number.to_string(), 10).ok(),
+        serde_json::Value::String(text) => match text.strip_prefix("0x") {
+            Some(hex) => SignedBigInt::from_str_radix(hex, 16).ok(),
+            None => SignedBigInt::from_str_radix(text, 10).ok(),
+        },
+        serde_json::Value::Bool(flag) => Some(SignedBigInt::from(*flag as u8)),
+        serde_json::Value::Null | serde_json::Value::Object(_) => None,
+    };
+    let parsed = parsed.ok_or_else(|| format!("Invalid circuit input value: {}", value))?;
+
+    // Negative values wrap around the field like snarkjs does
+    let prime = SignedBigInt::from_biguint(Sign::Plus, prime.clone());
+    let reduced = ((parsed % &prime) + &prime) % &prime;
+    out.push(reduced.magnitude().clone());
+    Ok(())
+}
+
+fn exception_message(code: i32) -> &'static str {
+    match code {
+        1 => "Signal not found",
+        2 => "Too many signals set",
+        3 => "Signal already set",
+        4 => "Assert Failed",
+        5 => "Not enough memory",
+        6 => "Input signal array access exceeds the size",
+        _ => "Unknown error",
+    }
+}
+
+/// Read the message the circuit queued through getMessageChar
+fn read_message(caller: &mut Caller<'_, String>) -> Result<String, Trap> {
+    let get_char = caller
+        .get_export("getMessageChar")
+        .and_then(Extern::into_func)
+        .ok_or_else(|| Trap::new("Circuit does not export getMessageChar"))?
+        .typed::<(), i32>(&*caller)
+        .map_err(|e| Trap::new(e.to_string()))?;
+    let mut message = String::new();
+    loop {
+        match get_char.call(&mut *caller, ())? {
+            0 => return Ok(message),
+            c => message.push(char::from_u32(c as u32).unwrap_or('?')),
+        }
+    }
+}
+
+fn export<P: WasmParams, R: WasmResults>(instance: &Instance, store: &Store<String>, name: &str) -> Result<TypedFunc<P, R>, String> {
+    instance
+        .get_typed_func::<P, R>(store, name)
+        .map_err(|e| format!("Circuit WASM export '{}': {}", name, e))
+}
+
+impl WitnessCalculator {
+    fn new(wasm: &[u8]) -> Result<Self, String> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, wasm).map_err(|e| format!("Invalid circuit WASM: {}", e))?;
+        Ok(Self { engine, module })
+    }
+
+    /// Every signal of the witness, in order (index 0 is the constant 1, then outputs and public inputs)
+    fn calculate(&self, inputs: &serde_json::Value) -> Result<Vec<BigUint>, String> {
+        let inputs = inputs.as_object().ok_or_else(|| "Circuit inputs must be a JSON object".to_string())?;
+
+        // The store keeps the error messages printed before an exception
+        let mut store = Store::new(&self.engine, String::new());
+        let mut linker = <Linker<String>>::new(&self.engine);
+        let link_error = |e: wasmi::errors::LinkerError| e.to_string();
+        linker
+            .func_wrap("runtime", "exceptionHandler", |caller: Caller<'_, String>, code: i32| -> Result<(), Trap> {
+                Err(Trap::new(format!("{}. {}", exception_message(code), caller.data().trim_end())))
+            })
+            .map_err(link_error)?
+            .func_wrap("runtime", "printErrorMessage", |mut caller: Caller<'_, String>| -> Result<(), Trap> {
+                let message = read_message(&mut caller)?;
+                caller.data_mut().push_str(&message);
+                caller.data_mut().push('\n');
+                Ok(())
+            })
+            .map_err(link_error)?
+            .func_wrap("runtime", "writeBufferMessage", |mut caller: Caller<'_, String>| -> Result<(), Trap> {
+                // log() output from the circuit
+                let message = read_message(&mut caller)?;
+                if message != "\n" {
+                    println!("[Proof]    circuit log: {}", message);
+                }
+                Ok(())
+            })
+            .map_err(link_error)?
+            .func_wrap("runtime", "showSharedRWMemory", |_: Caller<'_, String>| {})
+            .map_err(link_error)?;
+
+        let instance = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| format!("Failed to instantiate circuit WASM: {}", e))?;
+
+        let trap = |e: Trap| format!("Witness calculation failed: {}", e);
+        let n32 = export::<(), i32>(&instance, &store, "getFieldNumLen32")?.call(&mut store, ()).map_err(trap)? as u32;
+        let read_shared = export::<i32, i32>(&instance, &store, "readSharedRWMemory")?;
+        let write_shared = export::<(i32, i32), ()>(&instance, &store, "writeSharedRWMemory")?;
+        let read_field = |store: &mut Store<String>| -> Result<BigUint, String> {
+            let mut limbs = Vec::with_capacity(n32 as usize);
+            for j in 0..n32 {
+                limbs.push(read_shared.call(&mut *store, j as i32).map_err(trap)? as u32);
+            }
+            Ok(BigUint::from_slice(&limbs))
+        };
+
+        export::<(), ()>(&instance, &store, "getRawPrime")?.call(&mut store, ()).map_err(trap)?;
+        let prime = read_field(&mut store)?;
+
+        export::<i32, ()>(&instance, &store, "init")?.call(&mut store, 0).map_err(trap)?;
+        // Runtimes before circom 2.1 do not export the input sizes, so those checks are skipped
+        let get_signal_size = export::<(i32, i32), i32>(&instance, &store, "getInputSignalSize").ok();
+        let set_signal = export::<(i32, i32, i32), ()>(&instance, &store, "setInputSignal")?;
+        let mut inputs_set = 0;
+        for (name, value) in inputs {
+            let hash = fnv_hash(name);
+            let (msb, lsb) = ((hash >> 32) as u32 as i32, hash as u32 as i32);
+            let mut values = Vec::new();
+            flatten_input(value, &prime, &mut values)?;
+            if let Some(get_signal_size) = &get_signal_size {
+                let size = get_signal_size.call(&mut store, (msb, lsb)).map_err(trap)?;
+                if size < 0 {
+                    return Err(format!("Signal {} not found", name));
+                }
+                if values.len() != size as usize {
+                    return Err(format!("Input signal {} takes {} values, got {}", name, size, values.len()));
+                }
+            }
+
+            for (i, value) in values.iter().enumerate() {
+                let limbs = value.to_u32_digits();
+                for j in 0..n32 as usize {
+                    let limb = limbs.get(j).copied().unwrap_or(0);
+                    write_shared.call(&mut store, (j as i32, limb as i32)).map_err(trap)?;
+                }
+                set_signal.call(&mut store, (msb, lsb, i as i32)).map_err(trap)?;
+                inputs_set += 1;
+            }
+        }
+        if let Ok(get_input_size) = export::<(), i32>(&instance, &store, "getInputSize") {
+            let input_size = get_input_size.call(&mut store, ()).map_err(trap)?;
+            if inputs_set < input_size {
+                return Err(format!("Not all inputs have been set. Only {} out of {}", inputs_set, input_size));
+            }
+        }
+
+        let witness_size = export::<(), i32>(&instance, &store, "getWitnessSize")?.call(&mut store, ()).map_err(trap)?;
+        let get_witness = export::<i32, ()>(&instance, &store, "getWitness")?;
+        (0..witness_size)
+            .map(|i| {
+                get_witness.call(&mut store, i).map_err(trap)?;
+                read_field(&mut store)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, OptimizationGoal};
+
+    /// a * b = c with c public
+    struct Multiply {
+        a: Fr,
+        b: Fr,
+    }
+
+    impl ConstraintSynthesizer<Fr> for Multiply {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let c = cs.new_input_variable(|| Ok(self.a * self.b))?;
+            let a = cs.new_witness_variable(|| Ok(self.a))?;
+            let b = cs.new_witness_variable(|| Ok(self.b))?;
+            cs.enforce_constraint(lc!() + a, lc!() + b, lc!() + c)?;
+            cs.enforce_constraint(lc!() + a, lc!() + a, lc!() + a)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fnv_hash_matches_circom() {
+        // Values from witness_calculator.js' fnvHash
+        assert_eq!(fnv_hash(""), 0xcbf29ce484222325);
+        assert_eq!(fnv_hash("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv_hash("nullifier"), 0xb79e97481b3cb9e9);
+        assert_eq!(fnv_hash("pathElements"), 0x4099e7b711de8b07);
+    }
+
+    #[test]
+    fn test_flatten_input_wraps_into_the_field() {
+        let prime = Fr::MODULUS.into();
+        let mut values = Vec::new();
+        let input = serde_json::json!([["1", "0x10"], -1, 7, true]);
+        flatten_input(&input, &prime, &mut values).unwrap();
+        assert_eq!(values[..2], [BigUint::from(1u8), BigUint::from(16u8)]);
+        assert_eq!(values[2], &prime - 1u8);
+        assert_eq!(values[3..], [BigUint::from(7u8), BigUint::from(1u8)]);
+        assert!(flatten_input(&serde_json::json!("price"), &prime, &mut values).is_err());
+    }
+
+    #[test]
+    fn test_circom_reduction_proves_and_verifies() {
+        let mut rng = rand::thread_rng();
+        let pk = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+            Multiply { a: Fr::from(1u8), b: Fr::from(1u8) },
+            &mut rng,
+        )
+        .unwrap();
+
+        // Prove from the matrices and full assignment, as with a zkey and a WASM witness
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        Multiply { a: Fr::from(1u8), b: Fr::from(7u8) }.generate_constraints(cs.clone()).unwrap();
+        cs.finalize();
+        let matrices = cs.to_matrices().unwrap();
+        let prover = cs.borrow().unwrap();
+        let full_assignment = [prover.instance_assignment.clone(), prover.witness_assignment.clone()].concat();
+
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &pk,
+            Fr::rand(&mut rng),
+            Fr::rand(&mut rng),
+            &matrices,
+            matrices.num_instance_variables,
+            matrices.num_constraints,
+            &full_assignment,
+        )
+        .unwrap();
+
+        let pvk = ark_groth16::prepare_verifying_key(&pk.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(7u8)]).unwrap());
+        assert!(!Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(8u8)]).unwrap());
+
+        let native = NativeProof { proof, public_signals: vec![BigUint::from(7u8)] };
+        assert_eq!(native.public_json(), serde_json::json!(["7"]));
+        assert_eq!(native.proof_json()["pi_a"][2], "1");
+        assert_eq!(native.proof_json()["pi_b"].as_array().unwrap().len(), 3);
+
+        // The snarkjs-layout files a native proof is written to pass the check every proof goes through
+        let vk = serde_json::json!({
+            "vk_alpha_1": g1_json(&pk.vk.alpha_g1),
+            "vk_beta_2": g2_json(&pk.vk.beta_g2),
+            "vk_gamma_2": g2_json(&pk.vk.gamma_g2),
+            "vk_delta_2": g2_json(&pk.vk.delta_g2),
+            "IC": pk.vk.gamma_abc_g1.iter().map(g1_json).collect::<Vec<_>>(),
+        });
+        let key = crate::verifier::VerificationKey::from_snarkjs("multiply", &vk.to_string()).unwrap();
+        key.verify(&native.proof_json(), &native.public_json(), &serde_json::json!({})).unwrap();
+        let err = key.verify(&native.proof_json(), &serde_json::json!(["8"]), &serde_json::json!({})).unwrap_err();
+        assert_eq!(err.reason, "pairing_check_failed");
+    }
+}
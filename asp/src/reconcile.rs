@@ -0,0 +1,186 @@
+use crate::blockchain::BlockchainClient;
+use crate::merkle::MerkleTree;
+use serde::Serialize;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+/// How the local deposit tree relates to the contract's tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// Local root equals the current on-chain root
+    InSync,
+    /// Local root is a historical on-chain root - newer deposits are still being synced
+    #[default]
+    CatchingUp,
+    /// Local root was never an on-chain root - some leaves are wrong or missing
+    Diverged,
+}
+
+/// Reconciliation state exposed on /deposit/status
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct SyncStatus {
+    pub state: SyncState,
+    pub local_root: String,
+    pub onchain_root: Option<String>,
+    pub leaf_count: u32,
+    /// Longest prefix of local leaves whose root the contract knows (set when diverged)
+    pub agreeing_leaf_count: Option<u32>,
+    pub last_synced_block: u64,
+    /// Unix timestamp of the last reconciliation
+    pub checked_at: u64,
+    pub last_error: Option<String>,
+    /// Set by POST /deposit/pause, cleared by POST /deposit/resume
+    pub paused: bool,
+    /// Present while a resync is rebuilding the tree
+    pub rebuild: Option<RebuildProgress>,
+}
+
+/// Progress of a tree rebuild started by /deposit/resync
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildProgress {
+    pub from_block: u64,
+    pub target_block: u64,
+    pub current_block: u64,
+    pub leaf_count: u32,
+    /// Share of the block range scanned so far (0-100)
+    pub percent: f64,
+    /// Estimated seconds until the rebuilt tree is swapped in
+    pub eta_secs: Option<u64>,
+    /// Unix timestamp the rebuild started at
+    pub started_at: u64,
+    #[serde(skip)]
+    started: Instant,
+}
+
+impl RebuildProgress {
+    pub fn new(from_block: u64, target_block: u64, leaf_count: u32) -> Self {
+        Self {
+            from_block,
+            target_block,
+            current_block: from_block.saturating_sub(1),
+            leaf_count,
+            percent: 0.0,
+            eta_secs: None,
+            started_at: unix_now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Record that every block up to `current_block` was scanned
+    pub fn advance(&mut self, current_block: u64, leaf_count: u32) {
+        self.current_block = current_block;
+        self.leaf_count = leaf_count;
+
+        let start = self.from_block.saturating_sub(1);
+        let total = self.target_block.saturating_sub(start);
+        let done = current_block.saturating_sub(start);
+        if total == 0 || done == 0 {
+            return;
+        }
+        self.percent = (done as f64 / total as f64 * 100.0).min(100.0);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        self.eta_secs = Some((elapsed / done as f64 * (total - done) as f64).round() as u64);
+    }
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub type SharedSyncStatus = Arc<RwLock<SyncStatus>>;
+
+/// Result of comparing the local tree against the contract
+#[derive(Debug, Clone)]
+pub struct Reconciliation {
+    pub state: SyncState,
+    pub local_root: String,
+    pub onchain_root: String,
+    pub leaf_count: u32,
+    pub agreeing_leaf_count: Option<u32>,
+}
+
+/// Compare the local root with the contract and, if it was never an on-chain root,
+/// find the longest prefix of local leaves the contract agrees with
+pub async fn reconcile(
+    blockchain: &BlockchainClient,
+    tree: &Mutex<MerkleTree>,
+) -> Result<Reconciliation, String> {
+    let (local_root, leaf_count) = {
+        let tree = tree.lock().unwrap();
+        (format!("0x{:x}", tree.get_root()), tree.get_leaf_count())
+    };
+    let onchain_root = blockchain.get_merkle_root().await?;
+
+    let (state, agreeing_leaf_count) = if same_felt(&local_root, &onchain_root) {
+        (SyncState::InSync, None)
+    } else if blockchain.is_root_known(&local_root).await? {
+        (SyncState::CatchingUp, None)
+    } else {
+        let agreeing = find_divergence(blockchain, tree, leaf_count).await?;
+        (SyncState::Diverged, Some(agreeing))
+    };
+
+    Ok(Reconciliation {
+        state,
+        local_root,
+        onchain_root,
+        leaf_count,
+        agreeing_leaf_count,
+    })
+}
+
+/// Binary-search the largest leaf count whose root the contract knows
+/// Relies on the contract keeping every historical root (prefix roots are monotonic:
+/// if the root after k leaves is known, so is every root before it)
+/// `leaf_count` is assumed to disagree; the empty root (0) always agrees
+async fn find_divergence(
+    blockchain: &BlockchainClient,
+    tree: &Mutex<MerkleTree>,
+    leaf_count: u32,
+) -> Result<u32, String> {
+    let mut agree = 0u32;
+    let mut disagree = leaf_count;
+
+    while disagree - agree > 1 {
+        let mid = agree + (disagree - agree) / 2;
+        let root = format!("0x{:x}", tree.lock().unwrap().root_at(mid));
+        if blockchain.is_root_known(&root).await? {
+            agree = mid;
+        } else {
+            disagree = mid;
+        }
+    }
+
+    Ok(agree)
+}
+
+/// Compare two hex felts ignoring zero padding
+fn same_felt(a: &str, b: &str) -> bool {
+    a.trim_start_matches("0x").trim_start_matches('0') == b.trim_start_matches("0x").trim_start_matches('0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebuild_progress() {
+        let mut progress = RebuildProgress::new(101, 300, 0);
+        assert_eq!(progress.percent, 0.0);
+        assert_eq!(progress.eta_secs, None);
+
+        progress.advance(150, 4);
+        assert_eq!(progress.percent, 25.0);
+        assert_eq!(progress.leaf_count, 4);
+        assert!(progress.eta_secs.is_some());
+
+        progress.advance(300, 9);
+        assert_eq!(progress.percent, 100.0);
+        assert_eq!(progress.eta_secs, Some(0));
+    }
+}
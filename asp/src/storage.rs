@@ -0,0 +1,1090 @@
+use crate::merkle::MerkleTree;
+use crate::pool_index::{
+    event_columns, EventColumns, IndexedPoolEvent, IndexedTvlSnapshot, PoolEventRecord, PoolState, TvlSnapshot,
+};
+use crate::ticks::TickMap;
+use num_bigint::BigUint;
+use num_traits::Num;
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Keys in the sync_state table
+const KEY_LAST_SYNCED_BLOCK: &str = "last_synced_block";
+const KEY_ROOT: &str = "root";
+const KEY_LEAF_COUNT: &str = "leaf_count";
+const KEY_FINALIZED_BLOCK: &str = "finalized_block";
+const KEY_EVENTS_FROM: &str = "events_indexed_from";
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS leaves (
+        leaf_index INTEGER PRIMARY KEY,
+        commitment TEXT NOT NULL,
+        block_number INTEGER
+    )",
+    "CREATE INDEX IF NOT EXISTS leaves_commitment ON leaves (commitment)",
+    "CREATE TABLE IF NOT EXISTS nodes (
+        level INTEGER NOT NULL,
+        node_index INTEGER NOT NULL,
+        hash TEXT NOT NULL,
+        PRIMARY KEY (level, node_index)
+    )",
+    "CREATE TABLE IF NOT EXISTS sync_state (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS deposit_events (
+        leaf_index INTEGER PRIMARY KEY,
+        commitment TEXT NOT NULL,
+        block_number INTEGER NOT NULL,
+        tx_hash TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS deposit_events_commitment ON deposit_events (commitment)",
+    "CREATE TABLE IF NOT EXISTS nullifier_events (
+        nullifier TEXT PRIMARY KEY,
+        block_number INTEGER NOT NULL,
+        tx_hash TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS pool_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        block_number INTEGER NOT NULL,
+        block_timestamp INTEGER NOT NULL,
+        tx_hash TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        account TEXT,
+        tick_lower INTEGER,
+        tick_upper INTEGER,
+        liquidity_delta TEXT,
+        amount0 TEXT NOT NULL,
+        amount1 TEXT NOT NULL,
+        sqrt_price_x128 TEXT NOT NULL,
+        tick INTEGER NOT NULL,
+        liquidity TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS pool_events_block ON pool_events (block_number)",
+    "CREATE INDEX IF NOT EXISTS pool_events_time ON pool_events (block_timestamp)",
+    "CREATE TABLE IF NOT EXISTS tvl_snapshots (
+        block_number INTEGER PRIMARY KEY,
+        block_timestamp INTEGER NOT NULL,
+        balance0 TEXT NOT NULL,
+        balance1 TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS tvl_snapshots_time ON tvl_snapshots (block_timestamp)",
+    "CREATE TABLE IF NOT EXISTS block_hashes (
+        block_number INTEGER PRIMARY KEY,
+        block_hash TEXT NOT NULL
+    )",
+];
+
+/// What the syncer learned about one processed block range
+#[derive(Debug, Clone, Default)]
+pub struct SyncedRange {
+    /// Last block of the range - becomes the new sync cursor
+    pub to_block: u64,
+    /// Hash of `to_block`, checked against the chain on the next iteration to detect reorgs
+    pub block_hash: Option<String>,
+    /// Block each leaf inserted in this range was emitted in
+    pub leaf_blocks: Vec<(u32, u64)>,
+    /// Deposit events seen in this range, written to the event index
+    pub deposits: Vec<DepositEvent>,
+    /// NullifierSpent events seen in this range
+    pub nullifiers: Vec<NullifierEvent>,
+    /// Pool events (swap, mint, burn, collect, initialize) seen in this range, in chain order
+    pub pool_events: Vec<PoolEventRecord>,
+    /// Contract balances at the end of each block of this range that emitted events
+    pub tvl_snapshots: Vec<TvlSnapshot>,
+    /// Highest block considered final (`to_block` minus the confirmation depth)
+    pub finalized_block: u64,
+}
+
+impl SyncedRange {
+    /// A range that only moves the cursor (no block hash, no new leaves)
+    pub fn cursor_only(to_block: u64) -> Self {
+        Self {
+            to_block,
+            ..Default::default()
+        }
+    }
+}
+
+/// A Deposit event as recorded in the event index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DepositEvent {
+    /// 0x-prefixed hex, without zero padding
+    pub commitment: String,
+    pub leaf_index: u32,
+    pub block_number: u64,
+    pub tx_hash: String,
+}
+
+/// A NullifierSpent event as recorded in the nullifier index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NullifierEvent {
+    /// 0x-prefixed hex, without zero padding
+    pub nullifier: String,
+    pub block_number: u64,
+    pub tx_hash: String,
+}
+
+/// SQLite-backed persistence for the deposit Merkle tree and the syncer cursor
+///
+/// Leaves, internal nodes and the sync cursor are written in a single transaction
+/// per synced block range, so a restart always finds a tree whose root matches
+/// the cursor it was saved with.
+#[derive(Clone)]
+pub struct TreeStore {
+    pool: SqlitePool,
+}
+
+impl TreeStore {
+    /// Open (or create) the database and make sure the schema exists
+    pub async fn open(database_url: &str) -> Result<Self, String> {
+        let options = SqliteConnectOptions::from_str(database_url)
+            .map_err(|e| format!("Invalid database URL '{}': {}", database_url, e))?
+            .create_if_missing(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await
+            .map_err(|e| format!("Failed to open database '{}': {}", database_url, e))?;
+
+        for statement in SCHEMA {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await
+                .map_err(|e| format!("Failed to create schema: {}", e))?;
+        }
+
+        Ok(Self { pool })
+    }
+
+    /// Load the persisted tree and sync cursor
+    /// If the stored nodes don't reproduce the stored root, the tree is rebuilt from its leaves
+    pub async fn load_tree(&self, depth: usize) -> Result<(MerkleTree, u64), String> {
+        let last_synced_block = self
+            .get_state(KEY_LAST_SYNCED_BLOCK)
+            .await?
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0);
+        let leaf_count = self
+            .get_state(KEY_LEAF_COUNT)
+            .await?
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(0);
+        let stored_root = self.get_state(KEY_ROOT).await?;
+
+        let rows = sqlx::query("SELECT level, node_index, hash FROM nodes")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to load nodes: {}", e))?;
+
+        let mut nodes = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let level: i64 = row.get("level");
+            let index: i64 = row.get("node_index");
+            let hash: String = row.get("hash");
+            nodes.insert((level as usize, index as u32), parse_hex(&hash)?);
+        }
+
+        let tree = MerkleTree::from_nodes(depth, leaf_count, nodes);
+        let loaded_root = format!("0x{:x}", tree.get_root());
+
+        match stored_root {
+            Some(root) if root == loaded_root => Ok((tree, last_synced_block)),
+            None if leaf_count == 0 => Ok((tree, last_synced_block)),
+            _ => {
+                println!("[Store] ⚠️  Persisted nodes don't match stored root - rebuilding tree from leaves");
+                let tree = Mutex::new(self.rebuild_from_leaves(depth).await?);
+                self.commit_range(&tree, &SyncedRange::cursor_only(last_synced_block)).await?;
+                let tree = tree.into_inner().map_err(|_| "Tree lock poisoned".to_string())?;
+                Ok((tree, last_synced_block))
+            }
+        }
+    }
+
+    /// Recompute the whole tree from the leaves table
+    async fn rebuild_from_leaves(&self, depth: usize) -> Result<MerkleTree, String> {
+        let rows = sqlx::query("SELECT leaf_index, commitment FROM leaves ORDER BY leaf_index")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to load leaves: {}", e))?;
+
+        let mut tree = MerkleTree::new(depth);
+        for row in rows {
+            let index: i64 = row.get("leaf_index");
+            let commitment: String = row.get("commitment");
+            tree.insert_at_index(index as u32, parse_hex(&commitment)?);
+        }
+        Ok(tree)
+    }
+
+    /// Persist every node modified since the last commit together with the new cursor
+    /// On failure the nodes are marked dirty again so the next commit retries them
+    /// The tree lock is only held while collecting the changes, never across the write
+    pub async fn commit_range(&self, tree: &Mutex<MerkleTree>, range: &SyncedRange) -> Result<(), String> {
+        self.commit(tree, range, None).await
+    }
+
+    /// Persist a tree rebuilt from `keep_leaves` onwards, replacing every stored leaf,
+    /// node and block hash past that point in the same transaction
+    /// `after_block` is the block the rebuild resumed after; nullifiers past it are replaced too
+    pub async fn commit_rebuild(
+        &self,
+        tree: &Mutex<MerkleTree>,
+        keep_leaves: u32,
+        after_block: u64,
+        range: &SyncedRange,
+    ) -> Result<(), String> {
+        self.commit(tree, range, Some((keep_leaves, after_block))).await
+    }
+
+    async fn commit(
+        &self,
+        tree: &Mutex<MerkleTree>,
+        range: &SyncedRange,
+        keep: Option<(u32, u64)>,
+    ) -> Result<(), String> {
+        let (dirty, root, leaf_count) = {
+            let mut tree = tree.lock().unwrap();
+            (tree.take_dirty_nodes(), format!("0x{:x}", tree.get_root()), tree.get_leaf_count())
+        };
+
+        if let Err(e) = self.write_range(&dirty, &root, leaf_count, range, keep).await {
+            tree.lock().unwrap().restore_dirty_nodes(&dirty);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn write_range(
+        &self,
+        dirty: &[((usize, u32), BigUint)],
+        root: &str,
+        leaf_count: u32,
+        range: &SyncedRange,
+        keep: Option<(u32, u64)>,
+    ) -> Result<(), String> {
+        // A rebuild that starts earlier than the event indexes extends their coverage
+        let nullifiers_from = match keep {
+            Some((_, after_block)) => self
+                .get_events_indexed_from()
+                .await?
+                .map(|from| from.min(after_block + 1)),
+            None => None,
+        };
+
+        let mut tx = self.pool.begin().await
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        if let Some((keep_leaves, after_block)) = keep {
+            delete_leaves_from_in(&mut tx, keep_leaves).await?;
+            delete_block_events_after_in(&mut tx, after_block).await?;
+            sqlx::query("DELETE FROM block_hashes")
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to clear block hashes: {}", e))?;
+        }
+        if let Some(from) = nullifiers_from {
+            set_state_in(&mut tx, KEY_EVENTS_FROM, &from.to_string()).await?;
+        }
+
+        let leaf_blocks: HashMap<u32, u64> = range.leaf_blocks.iter().copied().collect();
+        write_nodes_in(&mut tx, dirty, &leaf_blocks).await?;
+        write_deposit_events_in(&mut tx, &range.deposits).await?;
+        write_nullifier_events_in(&mut tx, &range.nullifiers).await?;
+        write_pool_events_in(&mut tx, &range.pool_events).await?;
+        write_tvl_snapshots_in(&mut tx, &range.tvl_snapshots).await?;
+
+        if let Some(hash) = &range.block_hash {
+            sqlx::query("INSERT OR REPLACE INTO block_hashes (block_number, block_hash) VALUES (?, ?)")
+                .bind(range.to_block as i64)
+                .bind(hash)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to write block hash {}: {}", range.to_block, e))?;
+        }
+
+        // Hashes of final blocks are no longer needed, except the newest one which
+        // anchors the walk back to a common ancestor
+        sqlx::query(
+            "DELETE FROM block_hashes WHERE block_number < (
+                SELECT MAX(block_number) FROM block_hashes WHERE block_number <= ?
+            )",
+        )
+        .bind(range.finalized_block as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to prune block hashes: {}", e))?;
+
+        for (key, value) in [
+            (KEY_ROOT, root.to_string()),
+            (KEY_LEAF_COUNT, leaf_count.to_string()),
+            (KEY_LAST_SYNCED_BLOCK, range.to_block.to_string()),
+            (KEY_FINALIZED_BLOCK, range.finalized_block.to_string()),
+        ] {
+            set_state_in(&mut tx, key, &value).await?;
+        }
+
+        tx.commit().await
+            .map_err(|e| format!("Failed to commit transaction: {}", e))
+    }
+
+    /// Undo every leaf emitted after `ancestor_block` and move the cursor back to it
+    /// Returns the number of leaves removed
+    pub async fn rollback_to(&self, tree: &Mutex<MerkleTree>, ancestor_block: u64) -> Result<u32, String> {
+        let leaf_count = match self.first_leaf_after(ancestor_block).await? {
+            Some(first) => first,
+            None => tree.lock().unwrap().get_leaf_count(),
+        };
+        self.truncate_to(tree, leaf_count, ancestor_block).await
+    }
+
+    /// Keep only the first `leaf_count` leaves and move the cursor to `last_synced_block`
+    /// Returns the number of leaves removed
+    pub async fn truncate_to(
+        &self,
+        tree: &Mutex<MerkleTree>,
+        leaf_count: u32,
+        last_synced_block: u64,
+    ) -> Result<u32, String> {
+        let (dirty, root, leaf_count, removed) = {
+            let mut tree = tree.lock().unwrap();
+            let old_count = tree.get_leaf_count();
+            tree.truncate(leaf_count);
+            let new_count = tree.get_leaf_count();
+            (
+                tree.take_dirty_nodes(),
+                format!("0x{:x}", tree.get_root()),
+                new_count,
+                old_count - new_count,
+            )
+        };
+
+        let result = async {
+            let mut tx = self.pool.begin().await
+                .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+            delete_leaves_from_in(&mut tx, leaf_count).await?;
+            delete_block_events_after_in(&mut tx, last_synced_block).await?;
+            sqlx::query("DELETE FROM block_hashes WHERE block_number > ?")
+                .bind(last_synced_block as i64)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to delete rolled back block hashes: {}", e))?;
+
+            write_nodes_in(&mut tx, &dirty, &HashMap::new()).await?;
+            set_state_in(&mut tx, KEY_ROOT, &root).await?;
+            set_state_in(&mut tx, KEY_LEAF_COUNT, &leaf_count.to_string()).await?;
+            set_state_in(&mut tx, KEY_LAST_SYNCED_BLOCK, &last_synced_block.to_string()).await?;
+
+            tx.commit().await
+                .map_err(|e| format!("Failed to commit transaction: {}", e))
+        }
+        .await;
+
+        if let Err(e) = result {
+            tree.lock().unwrap().restore_dirty_nodes(&dirty);
+            return Err(e);
+        }
+        Ok(removed)
+    }
+
+    /// Recorded block hashes, newest first
+    pub async fn get_block_hashes(&self) -> Result<Vec<(u64, String)>, String> {
+        let rows = sqlx::query("SELECT block_number, block_hash FROM block_hashes ORDER BY block_number DESC")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to load block hashes: {}", e))?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get::<i64, _>("block_number") as u64, row.get("block_hash")))
+            .collect())
+    }
+
+    /// Index of the first leaf emitted after `block`, if any
+    pub async fn first_leaf_after(&self, block: u64) -> Result<Option<u32>, String> {
+        let row = sqlx::query("SELECT MIN(leaf_index) AS first FROM leaves WHERE block_number > ?")
+            .bind(block as i64)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to find leaves after block {}: {}", block, e))?;
+        Ok(row.get::<Option<i64>, _>("first").map(|i| i as u32))
+    }
+
+    /// Block the leaf at `index` was emitted in, if it was recorded
+    pub async fn get_leaf_block(&self, index: u32) -> Result<Option<u64>, String> {
+        let row = sqlx::query("SELECT block_number FROM leaves WHERE leaf_index = ?")
+            .bind(index as i64)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to read block of leaf {}: {}", index, e))?;
+        Ok(row
+            .and_then(|r| r.get::<Option<i64>, _>("block_number"))
+            .map(|b| b as u64))
+    }
+
+    /// Look up a commitment in the event index
+    /// If the same commitment was deposited twice, the lowest leaf index is returned
+    pub async fn find_deposit_event(&self, commitment: &BigUint) -> Result<Option<DepositEvent>, String> {
+        let commitment = format!("0x{:x}", commitment);
+        let row = sqlx::query(
+            "SELECT leaf_index, block_number, tx_hash FROM deposit_events
+             WHERE commitment = ? ORDER BY leaf_index LIMIT 1",
+        )
+        .bind(&commitment)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to look up commitment {}: {}", commitment, e))?;
+
+        Ok(row.map(|row| DepositEvent {
+            leaf_index: row.get::<i64, _>("leaf_index") as u32,
+            block_number: row.get::<i64, _>("block_number") as u64,
+            tx_hash: row.get("tx_hash"),
+            commitment,
+        }))
+    }
+
+    /// Look up a nullifier in the nullifier index
+    pub async fn find_nullifier_event(&self, nullifier: &BigUint) -> Result<Option<NullifierEvent>, String> {
+        let nullifier = format!("0x{:x}", nullifier);
+        let row = sqlx::query("SELECT block_number, tx_hash FROM nullifier_events WHERE nullifier = ?")
+            .bind(&nullifier)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to look up nullifier {}: {}", nullifier, e))?;
+
+        Ok(row.map(|row| NullifierEvent {
+            block_number: row.get::<i64, _>("block_number") as u64,
+            tx_hash: row.get("tx_hash"),
+            nullifier,
+        }))
+    }
+
+    /// First block the nullifier and pool event indexes cover, if indexing has started
+    /// Databases created before these indexes existed only cover blocks synced since
+    pub async fn get_events_indexed_from(&self) -> Result<Option<u64>, String> {
+        Ok(self
+            .get_state(KEY_EVENTS_FROM)
+            .await?
+            .and_then(|v| v.parse::<u64>().ok()))
+    }
+
+    /// Record that the event indexes start at `from_block`, unless they already started
+    pub async fn init_event_index(&self, from_block: u64) -> Result<u64, String> {
+        if let Some(from) = self.get_events_indexed_from().await? {
+            return Ok(from);
+        }
+        sqlx::query("INSERT OR IGNORE INTO sync_state (key, value) VALUES (?, ?)")
+            .bind(KEY_EVENTS_FROM)
+            .bind(from_block.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to write sync state '{}': {}", KEY_EVENTS_FROM, e))?;
+        Ok(from_block)
+    }
+
+    /// The most recent pool event, carrying the current pool state
+    pub async fn get_latest_pool_event(&self) -> Result<Option<IndexedPoolEvent>, String> {
+        let row = sqlx::query(&format!("{} ORDER BY id DESC LIMIT 1", SELECT_POOL_EVENTS))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to read pool state: {}", e))?;
+        row.map(|row| pool_event_from_row(&row)).transpose()
+    }
+
+    /// Pool events with a block timestamp in [from, to), oldest first
+    pub async fn get_pool_events(&self, from: u64, to: u64) -> Result<Vec<IndexedPoolEvent>, String> {
+        let rows = sqlx::query(&format!(
+            "{} WHERE block_timestamp >= ? AND block_timestamp < ? ORDER BY id",
+            SELECT_POOL_EVENTS
+        ))
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to load pool events: {}", e))?;
+        rows.iter().map(pool_event_from_row).collect()
+    }
+
+    /// The newest `limit` pool events, newest first
+    pub async fn get_recent_pool_events(&self, limit: u32) -> Result<Vec<IndexedPoolEvent>, String> {
+        let rows = sqlx::query(&format!("{} ORDER BY id DESC LIMIT ?", SELECT_POOL_EVENTS))
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to load pool events: {}", e))?;
+        rows.iter().map(pool_event_from_row).collect()
+    }
+
+    /// TVL snapshots with a block timestamp in [from, to), preceded by the last one before
+    /// `from` (the balances the range opens with), oldest first. Each carries the pool price
+    /// left by the last pool event up to its block
+    pub async fn get_tvl_snapshots(&self, from: u64, to: u64) -> Result<Vec<IndexedTvlSnapshot>, String> {
+        let rows = sqlx::query(
+            "SELECT s.block_number, s.block_timestamp, s.balance0, s.balance1,
+                (SELECT p.sqrt_price_x128 FROM pool_events p WHERE p.block_number <= s.block_number
+                 ORDER BY p.id DESC LIMIT 1) AS sqrt_price_x128
+             FROM tvl_snapshots s
+             WHERE s.block_number >= COALESCE(
+                (SELECT MAX(block_number) FROM tvl_snapshots WHERE block_timestamp < ?), 0
+             ) AND s.block_timestamp < ?
+             ORDER BY s.block_number",
+        )
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to load TVL snapshots: {}", e))?;
+
+        rows.iter()
+            .map(|row| {
+                let number = |column: &str| {
+                    let value: String = row.get(column);
+                    value
+                        .parse::<BigUint>()
+                        .map_err(|_| format!("Invalid {} '{}' in tvl_snapshots", column, value))
+                };
+                let sqrt_price_x128 = row
+                    .get::<Option<String>, _>("sqrt_price_x128")
+                    .map(|price| parse_hex(&price))
+                    .transpose()?;
+                Ok(IndexedTvlSnapshot {
+                    snapshot: TvlSnapshot {
+                        block_number: row.get::<i64, _>("block_number") as u64,
+                        block_timestamp: row.get::<i64, _>("block_timestamp") as u64,
+                        balance0: number("balance0")?,
+                        balance1: number("balance1")?,
+                    },
+                    sqrt_price_x128,
+                })
+            })
+            .collect()
+    }
+
+    /// Rebuild the tick bitmap and per-tick liquidity from every indexed Mint and Burn
+    pub async fn load_tick_map(&self) -> Result<TickMap, String> {
+        let rows = sqlx::query(
+            "SELECT tick_lower, tick_upper, liquidity_delta FROM pool_events
+             WHERE kind IN ('mint', 'burn') ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Failed to load position events: {}", e))?;
+
+        let mut ticks = TickMap::new();
+        for row in rows {
+            let (Some(tick_lower), Some(tick_upper), Some(liquidity)) = (
+                row.get::<Option<i32>, _>("tick_lower"),
+                row.get::<Option<i32>, _>("tick_upper"),
+                row.get::<Option<String>, _>("liquidity_delta"),
+            ) else {
+                continue;
+            };
+            let liquidity = liquidity
+                .parse::<u128>()
+                .map_err(|e| format!("Invalid stored liquidity '{}': {}", liquidity, e))?;
+            ticks.update_position(tick_lower, tick_upper, liquidity);
+        }
+        Ok(ticks)
+    }
+
+    /// Highest block considered final by the syncer
+    pub async fn get_finalized_block(&self) -> Result<u64, String> {
+        Ok(self
+            .get_state(KEY_FINALIZED_BLOCK)
+            .await?
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0))
+    }
+
+    /// Number of leaves emitted at or below the finalized block
+    /// Leaves without a recorded block predate reorg tracking and count as final
+    pub async fn get_finalized_leaf_count(&self) -> Result<u32, String> {
+        let finalized_block = self.get_finalized_block().await?;
+        let row = sqlx::query("SELECT COUNT(*) AS count FROM leaves WHERE block_number IS NULL OR block_number <= ?")
+            .bind(finalized_block as i64)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to count finalized leaves: {}", e))?;
+        Ok(row.get::<i64, _>("count") as u32)
+    }
+
+    /// Get the persisted sync cursor
+    pub async fn get_last_synced_block(&self) -> Result<u64, String> {
+        Ok(self
+            .get_state(KEY_LAST_SYNCED_BLOCK)
+            .await?
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0))
+    }
+
+    async fn get_state(&self, key: &str) -> Result<Option<String>, String> {
+        let row = sqlx::query("SELECT value FROM sync_state WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Failed to read sync state '{}': {}", key, e))?;
+        Ok(row.map(|r| r.get("value")))
+    }
+}
+
+/// Delete leaves from `leaf_count` on and every node that only covered them
+async fn delete_leaves_from_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    leaf_count: u32,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM leaves WHERE leaf_index >= ?")
+        .bind(leaf_count as i64)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to delete leaves from {}: {}", leaf_count, e))?;
+    sqlx::query("DELETE FROM deposit_events WHERE leaf_index >= ?")
+        .bind(leaf_count as i64)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to delete deposit events from {}: {}", leaf_count, e))?;
+    // A node at (level, index) covers leaves starting at index << level
+    sqlx::query("DELETE FROM nodes WHERE (node_index << level) >= ?")
+        .bind(leaf_count as i64)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to delete nodes from {}: {}", leaf_count, e))?;
+    Ok(())
+}
+
+/// Delete nullifiers, pool events and TVL snapshots after `block` (rolled back or about to be
+/// re-fetched)
+async fn delete_block_events_after_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    block: u64,
+) -> Result<(), String> {
+    for table in ["nullifier_events", "pool_events", "tvl_snapshots"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE block_number > ?", table))
+            .bind(block as i64)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to delete {} after block {}: {}", table, block, e))?;
+    }
+    Ok(())
+}
+
+/// Upsert leaves and nodes; a leaf keeps its recorded block unless a new one is given
+async fn write_nodes_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    dirty: &[((usize, u32), BigUint)],
+    leaf_blocks: &HashMap<u32, u64>,
+) -> Result<(), String> {
+    for ((level, index), hash) in dirty {
+        let hash_hex = format!("0x{:x}", hash);
+        if *level == 0 {
+            sqlx::query(
+                "INSERT INTO leaves (leaf_index, commitment, block_number) VALUES (?, ?, ?)
+                 ON CONFLICT(leaf_index) DO UPDATE SET
+                    commitment = excluded.commitment,
+                    block_number = COALESCE(excluded.block_number, leaves.block_number)",
+            )
+            .bind(*index as i64)
+            .bind(&hash_hex)
+            .bind(leaf_blocks.get(index).map(|b| *b as i64))
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to write leaf {}: {}", index, e))?;
+        }
+        sqlx::query("INSERT OR REPLACE INTO nodes (level, node_index, hash) VALUES (?, ?, ?)")
+            .bind(*level as i64)
+            .bind(*index as i64)
+            .bind(&hash_hex)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to write node ({}, {}): {}", level, index, e))?;
+    }
+    Ok(())
+}
+
+async fn write_deposit_events_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    deposits: &[DepositEvent],
+) -> Result<(), String> {
+    for deposit in deposits {
+        sqlx::query(
+            "INSERT OR REPLACE INTO deposit_events (leaf_index, commitment, block_number, tx_hash)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(deposit.leaf_index as i64)
+        .bind(&deposit.commitment)
+        .bind(deposit.block_number as i64)
+        .bind(&deposit.tx_hash)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to index deposit {}: {}", deposit.leaf_index, e))?;
+    }
+    Ok(())
+}
+
+async fn write_nullifier_events_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    nullifiers: &[NullifierEvent],
+) -> Result<(), String> {
+    for event in nullifiers {
+        sqlx::query(
+            "INSERT OR REPLACE INTO nullifier_events (nullifier, block_number, tx_hash) VALUES (?, ?, ?)",
+        )
+        .bind(&event.nullifier)
+        .bind(event.block_number as i64)
+        .bind(&event.tx_hash)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to index nullifier {}: {}", event.nullifier, e))?;
+    }
+    Ok(())
+}
+
+/// Append pool events, each with the pool state it leaves behind
+async fn write_pool_events_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    events: &[PoolEventRecord],
+) -> Result<(), String> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let previous = sqlx::query(&format!("{} ORDER BY id DESC LIMIT 1", SELECT_POOL_EVENTS))
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to read pool state: {}", e))?;
+    let mut state = match previous {
+        Some(row) => pool_event_from_row(&row)?.state,
+        None => PoolState::default(),
+    };
+
+    for record in events {
+        state = state.apply(&record.event);
+        let EventColumns { kind, account, ticks, liquidity_delta, amount0, amount1 } =
+            event_columns(&record.event);
+        sqlx::query(
+            "INSERT INTO pool_events (
+                block_number, block_timestamp, tx_hash, kind, account, tick_lower, tick_upper,
+                liquidity_delta, amount0, amount1, sqrt_price_x128, tick, liquidity
+             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(record.block_number as i64)
+        .bind(record.block_timestamp as i64)
+        .bind(&record.tx_hash)
+        .bind(kind)
+        .bind(account)
+        .bind(ticks.map(|(lower, _)| lower))
+        .bind(ticks.map(|(_, upper)| upper))
+        .bind(liquidity_delta.map(|l| l.to_string()))
+        .bind(amount0.to_string())
+        .bind(amount1.to_string())
+        .bind(format!("0x{:x}", state.sqrt_price_x128))
+        .bind(state.tick)
+        .bind(state.liquidity.to_string())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to index {} event in tx {}: {}", kind, record.tx_hash, e))?;
+    }
+    Ok(())
+}
+
+async fn write_tvl_snapshots_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    snapshots: &[TvlSnapshot],
+) -> Result<(), String> {
+    for snapshot in snapshots {
+        sqlx::query(
+            "INSERT OR REPLACE INTO tvl_snapshots (block_number, block_timestamp, balance0, balance1)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(snapshot.block_number as i64)
+        .bind(snapshot.block_timestamp as i64)
+        .bind(snapshot.balance0.to_string())
+        .bind(snapshot.balance1.to_string())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to index TVL snapshot at block {}: {}", snapshot.block_number, e))?;
+    }
+    Ok(())
+}
+
+const SELECT_POOL_EVENTS: &str = "SELECT id, block_number, block_timestamp, tx_hash, kind, account,
+    tick_lower, tick_upper, liquidity_delta, amount0, amount1, sqrt_price_x128, tick, liquidity
+    FROM pool_events";
+
+fn pool_event_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<IndexedPoolEvent, String> {
+    fn number<T: FromStr>(row: &sqlx::sqlite::SqliteRow, column: &str) -> Result<T, String> {
+        let value: String = row.get(column);
+        value
+            .parse()
+            .map_err(|_| format!("Invalid {} '{}' in pool_events", column, value))
+    }
+
+    Ok(IndexedPoolEvent {
+        id: row.get("id"),
+        block_number: row.get::<i64, _>("block_number") as u64,
+        block_timestamp: row.get::<i64, _>("block_timestamp") as u64,
+        tx_hash: row.get("tx_hash"),
+        kind: row.get("kind"),
+        account: row.get("account"),
+        tick_lower: row.get("tick_lower"),
+        tick_upper: row.get("tick_upper"),
+        liquidity_delta: row
+            .get::<Option<String>, _>("liquidity_delta")
+            .map(|l| l.parse().map_err(|_| format!("Invalid liquidity_delta '{}' in pool_events", l)))
+            .transpose()?,
+        amount0: number(row, "amount0")?,
+        amount1: number(row, "amount1")?,
+        state: PoolState {
+            sqrt_price_x128: parse_hex(&row.get::<String, _>("sqrt_price_x128"))?,
+            tick: row.get("tick"),
+            liquidity: number(row, "liquidity")?,
+        },
+    })
+}
+
+async fn set_state_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    key: &str,
+    value: &str,
+) -> Result<(), String> {
+    sqlx::query("INSERT OR REPLACE INTO sync_state (key, value) VALUES (?, ?)")
+        .bind(key)
+        .bind(value)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to write sync state '{}': {}", key, e))?;
+    Ok(())
+}
+
+fn parse_hex(value: &str) -> Result<BigUint, String> {
+    BigUint::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("Invalid hex value '{}' in database: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{Burn, Mint, PoolEvent, Swap};
+    use crate::merkle::TREE_DEPTH;
+    use starknet::core::types::FieldElement;
+
+    fn temp_db_url(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "asp_store_{}_{}.db",
+            name,
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        format!("sqlite://{}", path.display())
+    }
+
+    fn deposit(commitment: u64, leaf_index: u32, block_number: u64) -> DepositEvent {
+        DepositEvent {
+            commitment: format!("0x{:x}", commitment),
+            leaf_index,
+            block_number,
+            tx_hash: format!("0x{:x}", block_number * 1000 + leaf_index as u64),
+        }
+    }
+
+    fn nullifier(value: u64, block_number: u64) -> NullifierEvent {
+        NullifierEvent {
+            nullifier: format!("0x{:x}", value),
+            block_number,
+            tx_hash: format!("0x{:x}", block_number),
+        }
+    }
+
+    fn swap(block_number: u64, amount0: i128, amount1: i128) -> PoolEventRecord {
+        PoolEventRecord {
+            block_number,
+            block_timestamp: block_number * 10,
+            tx_hash: format!("0x{:x}", block_number),
+            event: PoolEvent::Swap(Swap {
+                sender: FieldElement::ONE,
+                zero_for_one: amount0 > 0,
+                amount0,
+                amount1,
+                sqrt_price_x128: BigUint::from(1u8) << 128,
+                liquidity: 1000,
+                tick: 0,
+            }),
+        }
+    }
+
+    fn tvl(block_number: u64, balance0: u32, balance1: u32) -> TvlSnapshot {
+        TvlSnapshot {
+            block_number,
+            block_timestamp: block_number * 10,
+            balance0: BigUint::from(balance0),
+            balance1: BigUint::from(balance1),
+        }
+    }
+
+    fn position_event(block_number: u64, event: PoolEvent) -> PoolEventRecord {
+        PoolEventRecord {
+            block_number,
+            block_timestamp: block_number * 10,
+            tx_hash: format!("0x{:x}", block_number),
+            event,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tick_map_from_position_events() {
+        let store = TreeStore::open(&temp_db_url("ticks")).await.unwrap();
+        let tree = Mutex::new(MerkleTree::new(TREE_DEPTH));
+        let mint = PoolEvent::Mint(Mint {
+            sender: FieldElement::ONE,
+            owner: FieldElement::ONE,
+            tick_lower: -60,
+            tick_upper: 120,
+            amount: 700,
+            amount0: 10,
+            amount1: 10,
+        });
+        let burn = PoolEvent::Burn(Burn {
+            owner: FieldElement::ONE,
+            tick_lower: -60,
+            tick_upper: 120,
+            amount: 200,
+            amount0: 3,
+            amount1: 3,
+        });
+        store.commit_range(&tree, &SyncedRange {
+            pool_events: vec![position_event(10, mint), swap(11, 5, -4), position_event(12, burn)],
+            ..SyncedRange::cursor_only(20)
+        }).await.unwrap();
+
+        let ticks = store.load_tick_map().await.unwrap();
+        assert_eq!(ticks.ticks().count(), 2);
+        assert_eq!(ticks.get(-60).unwrap().liquidity_gross, 900);
+        assert_eq!(ticks.get(120).unwrap().liquidity_net, -900);
+    }
+
+    #[tokio::test]
+    async fn test_tree_roundtrip() {
+        let store = TreeStore::open(&temp_db_url("roundtrip")).await.unwrap();
+
+        let tree = Mutex::new(MerkleTree::new(TREE_DEPTH));
+        tree.lock().unwrap().insert(BigUint::from(12345u64));
+        tree.lock().unwrap().insert(BigUint::from(67890u64));
+        store.commit_range(&tree, &SyncedRange::cursor_only(100)).await.unwrap();
+
+        tree.lock().unwrap().insert(BigUint::from(11111u64));
+        store.commit_range(&tree, &SyncedRange::cursor_only(200)).await.unwrap();
+
+        let tree = tree.into_inner().unwrap();
+        let (loaded, last_block) = store.load_tree(TREE_DEPTH).await.unwrap();
+        assert_eq!(last_block, 200);
+        assert_eq!(loaded.get_leaf_count(), 3);
+        assert_eq!(loaded.get_root(), tree.get_root());
+        assert_eq!(loaded.get_proof(1).unwrap().path, tree.get_proof(1).unwrap().path);
+    }
+
+    #[tokio::test]
+    async fn test_commit_rebuild_replaces_tail() {
+        let store = TreeStore::open(&temp_db_url("rebuild")).await.unwrap();
+
+        let tree = Mutex::new(MerkleTree::new(TREE_DEPTH));
+        for leaf in [1u64, 2, 3] {
+            tree.lock().unwrap().insert(BigUint::from(leaf));
+        }
+        store.init_event_index(401).await.unwrap();
+        store.commit_range(&tree, &SyncedRange::cursor_only(500)).await.unwrap();
+
+        // Rebuild keeping the first leaf, with a different tail
+        let mut rebuilt = tree.lock().unwrap().clone();
+        rebuilt.truncate(1);
+        rebuilt.insert(BigUint::from(42u64));
+        let rebuilt = Mutex::new(rebuilt);
+        store.commit_rebuild(&rebuilt, 1, 300, &SyncedRange::cursor_only(600)).await.unwrap();
+        // Rebuilding from before the index start extends the event index coverage
+        assert_eq!(store.get_events_indexed_from().await.unwrap(), Some(301));
+
+        let mut expected = MerkleTree::new(TREE_DEPTH);
+        expected.insert(BigUint::from(1u64));
+        expected.insert(BigUint::from(42u64));
+
+        let (loaded, last_block) = store.load_tree(TREE_DEPTH).await.unwrap();
+        assert_eq!(last_block, 600);
+        assert_eq!(loaded.get_leaf_count(), 2);
+        assert_eq!(loaded.get_root(), expected.get_root());
+        assert!(loaded.get_proof(2).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_to_common_ancestor() {
+        let store = TreeStore::open(&temp_db_url("rollback")).await.unwrap();
+        let tree = Mutex::new(MerkleTree::new(TREE_DEPTH));
+
+        tree.lock().unwrap().insert(BigUint::from(1u64));
+        tree.lock().unwrap().insert(BigUint::from(2u64));
+        store.commit_range(&tree, &SyncedRange {
+            to_block: 100,
+            block_hash: Some("0xaa".to_string()),
+            leaf_blocks: vec![(0, 90), (1, 100)],
+            deposits: vec![deposit(1, 0, 90), deposit(2, 1, 100)],
+            nullifiers: vec![nullifier(7, 95)],
+            pool_events: vec![swap(95, 10, -9)],
+            tvl_snapshots: vec![tvl(90, 10, 0), tvl(95, 20, 1)],
+            finalized_block: 0,
+        }).await.unwrap();
+        let root_at_100 = tree.lock().unwrap().get_root();
+
+        tree.lock().unwrap().insert(BigUint::from(3u64));
+        tree.lock().unwrap().insert(BigUint::from(4u64));
+        store.commit_range(&tree, &SyncedRange {
+            to_block: 110,
+            block_hash: Some("0xbb".to_string()),
+            leaf_blocks: vec![(2, 105), (3, 110)],
+            deposits: vec![deposit(3, 2, 105), deposit(4, 3, 110)],
+            nullifiers: vec![nullifier(8, 108)],
+            pool_events: vec![swap(108, 5, -4)],
+            tvl_snapshots: vec![tvl(108, 25, 0)],
+            finalized_block: 0,
+        }).await.unwrap();
+
+        let found = store.find_deposit_event(&BigUint::from(3u64)).await.unwrap();
+        assert_eq!(found, Some(deposit(3, 2, 105)));
+
+        let latest = store.get_latest_pool_event().await.unwrap().unwrap();
+        assert_eq!((latest.block_number, latest.amount0, latest.amount1), (108, 5, -4));
+
+        // A range opens with the last snapshot before it; prices come from the pool events
+        let snapshots = store.get_tvl_snapshots(960, 2000).await.unwrap();
+        let blocks: Vec<_> = snapshots.iter().map(|s| s.snapshot.block_number).collect();
+        assert_eq!(blocks, vec![95, 108]);
+        assert_eq!(snapshots[0].sqrt_price_x128, Some(BigUint::from(1u8) << 128));
+        assert_eq!(store.get_tvl_snapshots(0, 950).await.unwrap()[0].sqrt_price_x128, None);
+
+        let removed = store.rollback_to(&tree, 100).await.unwrap();
+        assert_eq!(removed, 2);
+        // Rolled back deposits leave the event index with their leaves
+        assert_eq!(store.find_deposit_event(&BigUint::from(3u64)).await.unwrap(), None);
+        assert!(store.find_deposit_event(&BigUint::from(2u64)).await.unwrap().is_some());
+        assert_eq!(store.find_nullifier_event(&BigUint::from(8u64)).await.unwrap(), None);
+        let snapshots = store.get_tvl_snapshots(0, 2000).await.unwrap();
+        assert_eq!(snapshots.last().unwrap().snapshot, tvl(95, 20, 1));
+        // Pool state is back to what the block 95 swap left
+        let latest = store.get_latest_pool_event().await.unwrap().unwrap();
+        assert_eq!((latest.block_number, latest.amount0, latest.amount1), (95, 10, -9));
+        assert_eq!(
+            store.find_nullifier_event(&BigUint::from(7u64)).await.unwrap(),
+            Some(nullifier(7, 95))
+        );
+        assert_eq!(tree.lock().unwrap().get_root(), root_at_100);
+        assert_eq!(store.get_block_hashes().await.unwrap(), vec![(100, "0xaa".to_string())]);
+
+        let (loaded, last_block) = store.load_tree(TREE_DEPTH).await.unwrap();
+        assert_eq!(last_block, 100);
+        assert_eq!(loaded.get_leaf_count(), 2);
+        assert_eq!(loaded.get_root(), root_at_100);
+    }
+}
@@ -1,66 +1,98 @@
-use crate::merkle::{MerkleTree, TREE_DEPTH};
+use crate::events::{decode_zylith_event, PoolEvent, PrivacyEvent, ZylithEvent};
+use crate::merkle::MerkleTree;
+use crate::pool_index::{PoolEventRecord, TvlSnapshot};
+use crate::reconcile::{self, RebuildProgress, SharedSyncStatus};
+use crate::storage::{DepositEvent, NullifierEvent, SyncedRange, TreeStore};
 use num_bigint::BigUint;
 use starknet::{
-    core::types::{BlockId, EventFilter, FieldElement},
-    core::utils::starknet_keccak,
+    core::types::{BlockId, EventFilter, FieldElement, MaybePendingBlockWithTxHashes},
     providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider},
 };
-use std::fs;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 use url::Url;
 
-/// Deposit event selector: starknet_keccak("Deposit")
-/// This is the hash of the event name used to filter deposit events
-/// Calculated as: starknet_keccak(b"Deposit") truncated to 250 bits
-const DEPOSIT_EVENT_SELECTOR: &str =
-    "0x9149d2123147c5f43d258257fef0b7b969db78269369ebcf5ebb9eef8592f2";
-
-/// Calculate event selector from name
-fn get_event_selector(name: &str) -> FieldElement {
-    let hash = starknet_keccak(name.as_bytes());
-    // Truncate to 250 bits (Starknet field element)
-    hash & FieldElement::from_hex_be("0x3ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff").unwrap()
-}
+/// Blocks a leaf must be buried under before it counts as final
+/// Block hashes are only kept for the non-final part of the chain
+pub const DEFAULT_CONFIRMATION_DEPTH: u64 = 10;
+
+/// Blocks scanned per request while rebuilding, between progress updates
+const REBUILD_CHUNK_BLOCKS: u64 = 5_000;
 
-/// State file for persistence
-const STATE_FILE: &str = "asp_state.json";
+/// Control commands sent from the HTTP handlers to the running syncer
+#[derive(Debug, Clone, Copy)]
+pub enum SyncCommand {
+    /// Rebuild the tree from this block and swap it in once it has caught up
+    Resync { from_block: u64 },
+    /// Stop following the chain until resumed
+    Pause,
+    Resume,
+}
 
-#[derive(serde::Serialize, serde::Deserialize, Default)]
-struct SyncerState {
-    last_synced_block: u64,
+/// Wait up to `wait` for a control command
+/// Once every sender is gone the channel is dropped and this just sleeps
+async fn next_command(
+    commands: &mut Option<mpsc::Receiver<SyncCommand>>,
+    wait: Duration,
+) -> Option<SyncCommand> {
+    let Some(rx) = commands.as_mut() else {
+        sleep(wait).await;
+        return None;
+    };
+    let received = tokio::select! {
+        command = rx.recv() => Some(command),
+        _ = sleep(wait) => None,
+    };
+    match received {
+        Some(None) => {
+            *commands = None;
+            None
+        }
+        Some(command) => command,
+        None => None,
+    }
 }
 
 pub struct Syncer {
     pub provider: Arc<JsonRpcClient<HttpTransport>>,
     pub contract_address: FieldElement,
     pub tree: Arc<Mutex<MerkleTree>>,
-    pub deposit_selector: FieldElement,
-    pub swap_selector: FieldElement,
-    pub pool_event_selector: FieldElement,
+    pub store: TreeStore,
     pub blockchain_client: Option<Arc<crate::blockchain::BlockchainClient>>,
+    pub confirmation_depth: u64,
+    pub status: SharedSyncStatus,
+    /// Block the Zylith contract was deployed in - nothing before it is scanned
+    pub deployment_block: u64,
+    commands: Option<mpsc::Receiver<SyncCommand>>,
+    /// Pool token0/token1, read once the pool is initialized, for the TVL snapshots
+    pool_tokens: OnceLock<(String, String)>,
 }
 
 impl Syncer {
-    pub fn new(rpc_url: &str, contract_address: &str, tree: Arc<Mutex<MerkleTree>>) -> Self {
+    pub fn new(
+        rpc_url: &str,
+        contract_address: &str,
+        tree: Arc<Mutex<MerkleTree>>,
+        store: TreeStore,
+    ) -> Self {
         let provider = Arc::new(JsonRpcClient::new(HttpTransport::new(
             Url::parse(rpc_url).unwrap(),
         )));
         let contract_address = FieldElement::from_hex_be(contract_address).unwrap();
-        let deposit_selector = FieldElement::from_hex_be(DEPOSIT_EVENT_SELECTOR).unwrap();
-        
-        // Calculate selectors for other events
-        let swap_selector = get_event_selector("Swap");
-        let pool_event_selector = get_event_selector("PoolEvent");
 
         Self {
             provider,
             contract_address,
             tree,
-            deposit_selector,
-            swap_selector,
-            pool_event_selector,
+            store,
             blockchain_client: None,
+            confirmation_depth: DEFAULT_CONFIRMATION_DEPTH,
+            status: SharedSyncStatus::default(),
+            deployment_block: 0,
+            commands: None,
+            pool_tokens: OnceLock::new(),
         }
     }
 
@@ -69,38 +101,63 @@ impl Syncer {
         self
     }
 
-    /// Load persisted state
-    fn load_state() -> SyncerState {
-        fs::read_to_string(STATE_FILE)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
+    pub fn with_confirmation_depth(mut self, depth: u64) -> Self {
+        self.confirmation_depth = depth;
+        self
     }
 
-    /// Save state to file
-    fn save_state(state: &SyncerState) {
-        if let Ok(json) = serde_json::to_string(state) {
-            let _ = fs::write(STATE_FILE, json);
-        }
+    pub fn with_status(mut self, status: SharedSyncStatus) -> Self {
+        self.status = status;
+        self
     }
 
-    pub async fn run(&self) {
-        let mut state = Self::load_state();
+    pub fn with_deployment_block(mut self, block: u64) -> Self {
+        self.deployment_block = block;
+        self
+    }
+
+    pub fn with_commands(mut self, commands: mpsc::Receiver<SyncCommand>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+
+    pub async fn run(mut self) {
+        let mut commands = self.commands.take();
+        let mut paused = false;
+
+        let mut last_synced_block = match self.store.get_last_synced_block().await {
+            Ok(block) => block,
+            Err(e) => {
+                eprintln!("[Syncer] ❌ Failed to read sync cursor: {}", e);
+                0
+            }
+        };
+
+        // Never scan blocks before the contract was deployed
+        let start_cursor = self.deployment_block.saturating_sub(1);
+        if last_synced_block < start_cursor {
+            last_synced_block = start_cursor;
+        }
+
+        // Nullifiers and pool events are indexed from the first block synced from now on
+        if let Err(e) = self.store.init_event_index(last_synced_block + 1).await {
+            eprintln!("[Syncer] ❌ Failed to initialize event index: {}", e);
+        }
         
         // Check if we should force re-sync from a specific block
         if let Ok(reset_block_str) = std::env::var("RESYNC_FROM_BLOCK") {
             if let Ok(reset_block) = reset_block_str.parse::<u64>() {
-                state.last_synced_block = reset_block;
-                Self::save_state(&state);
+                if let Some(block) = self.rebuild(reset_block, &mut commands, &mut paused).await {
+                    last_synced_block = block;
+                }
             }
         }
         
-        // If state file doesn't exist or last_synced_block is 0, start from block 0 (genesis)
-        // This ensures we sync ALL events from the beginning
-        if state.last_synced_block == 0 {
-            state.last_synced_block = 0;
-            Self::save_state(&state);
-            println!("[Syncer] 🚀 Starting fresh sync from block 0 (genesis)");
+        if last_synced_block == start_cursor {
+            println!("[Syncer] 🚀 Starting fresh sync from deployment block {}", self.deployment_block);
+        } else {
+            let leaf_count = self.tree.lock().unwrap().get_leaf_count();
+            println!("[Syncer] 💾 Resuming from block {} ({} leaves loaded from storage)", last_synced_block, leaf_count);
         }
         
         // Check if tree is empty but contract has deposits
@@ -109,14 +166,15 @@ impl Syncer {
             tree.get_leaf_count()
         };
         
-        if leaf_count == 0 {
+        if leaf_count == 0 && last_synced_block != start_cursor {
             if let Some(ref blockchain) = self.blockchain_client {
                 match blockchain.get_merkle_root().await {
                     Ok(contract_root) if contract_root != "0x0" && contract_root != "0x0000000000000000000000000000000000000000000000000000000000000000" => {
-                        // If contract has deposits but tree is empty, start from block 0 to sync everything
-                        state.last_synced_block = 0;
-                        Self::save_state(&state);
-                        println!("[Syncer] 🚀 Contract has deposits but tree is empty - starting sync from block 0");
+                        // If contract has deposits but tree is empty, rebuild from the deployment block
+                        println!("[Syncer] 🚀 Contract has deposits but tree is empty - rebuilding from deployment block");
+                        if let Some(block) = self.rebuild(self.deployment_block, &mut commands, &mut paused).await {
+                            last_synced_block = block;
+                        }
                     }
                     _ => {}
                 }
@@ -124,242 +182,500 @@ impl Syncer {
         }
 
         loop {
-            // Reload state from file in each iteration to pick up resync requests
-            // This allows the /deposit/resync endpoint to trigger immediate resync
-            let current_state = Self::load_state();
-            if current_state.last_synced_block < state.last_synced_block {
-                // State file was reset to an earlier block - force resync
-                println!("[Syncer] 🔄 Detected resync request - resetting to block {}", current_state.last_synced_block);
-                state.last_synced_block = current_state.last_synced_block;
-                
-                // Clear the tree to force full resync
-                {
-                    let mut tree = self.tree.lock().unwrap();
-                    *tree = MerkleTree::new(TREE_DEPTH); // Reset tree - use TREE_DEPTH constant
+            if !paused {
+                last_synced_block = self.step(last_synced_block).await;
+            }
+
+            // Wait for the next tick, acting on control commands as soon as they arrive
+            match next_command(&mut commands, Duration::from_secs(5)).await {
+                Some(SyncCommand::Pause) => self.set_paused(&mut paused, true),
+                Some(SyncCommand::Resume) => self.set_paused(&mut paused, false),
+                Some(SyncCommand::Resync { from_block }) => {
+                    if let Some(block) = self.rebuild(from_block, &mut commands, &mut paused).await {
+                        last_synced_block = block;
+                    }
                 }
-            } else if current_state.last_synced_block != state.last_synced_block {
-                // State was updated but not reset - just update our state
-                state.last_synced_block = current_state.last_synced_block;
+                None => {}
             }
-            
-            // TEMPORARILY DISABLED FOR DEBUGGING - Root check causes infinite loop
-            // First, verify our tree root matches the contract
-            // Do this check separately to avoid Send issues - must drop lock before await
-            let _should_resync = if let Some(ref blockchain) = self.blockchain_client {
-                // Get local root first (drop lock before await)
-                let local_root = {
-                    let tree = self.tree.lock().unwrap();
-                    format!("0x{:x}", tree.get_root())
-                };
-                
-                // Now await without holding the lock
-                let contract_root_result = blockchain.get_merkle_root().await;
-                
-                match contract_root_result {
-                    Ok(contract_root) => {
-                        // Log comparison but don't resync for debugging
-                        if contract_root != local_root {
-                            println!("[Syncer] 🛑 Root mismatch detected (DEBUG MODE - resync disabled):");
-                            println!("[Syncer]    Local root:     {}", local_root);
-                            println!("[Syncer]    On-chain root: {}", contract_root);
-                            
-                            // Show tree status for debugging
-                            let tree = self.tree.lock().unwrap();
-                            let leaf_count = tree.get_leaf_count();
-                            println!("[Syncer]    Tree has {} leaves", leaf_count);
-                            drop(tree);
-                        } else {
-                            println!("[Syncer] ✅ Roots match: {}", local_root);
-                        }
-                        false // Don't resync in debug mode
+        }
+    }
+
+    /// One sync iteration: reorg check, new events, reconciliation
+    /// Returns the new cursor
+    async fn step(&self, mut last_synced_block: u64) -> u64 {
+        // Make sure the blocks we already ingested are still canonical
+        match self.detect_reorg().await {
+            Ok(Some(ancestor)) => match self.store.rollback_to(&self.tree, ancestor).await {
+                Ok(removed) => {
+                    println!("[Syncer] ⚠️  Reorg detected - rolled back {} leaves to common ancestor block {}", removed, ancestor);
+                    last_synced_block = ancestor;
+                }
+                Err(e) => {
+                    eprintln!("[Syncer] ❌ Failed to roll back to block {}: {}", ancestor, e);
+                    return last_synced_block;
+                }
+            },
+            Ok(None) => {}
+            Err(e) => eprintln!("[Syncer] ❌ Reorg check failed: {:?}", e),
+        }
+        
+        let mut sync_error = None;
+        match self.sync_events(last_synced_block).await {
+            Ok(range) => {
+                let new_last_block = range.to_block;
+                if new_last_block > last_synced_block {
+                    // Persist leaves, nodes, block hash and cursor for this block range in one transaction
+                    if let Err(e) = self.store.commit_range(&self.tree, &range).await {
+                        eprintln!("[Syncer] ❌ Failed to persist synced range: {}", e);
+                        return last_synced_block;
                     }
-                    Err(e) => {
-                        eprintln!("[Syncer] ❌ Failed to get contract root: {:?}", e);
-                        false
+                    let old_block = last_synced_block;
+                    last_synced_block = new_last_block;
+                    
+                    // Log progress if we synced a significant number of blocks
+                    if new_last_block - old_block > 100 {
+                        let tree = self.tree.lock().unwrap();
+                        let leaf_count = tree.get_leaf_count();
+                        drop(tree);
+                        println!("[Syncer] ✅ Synced from block {} to {} ({} leaves in tree)", 
+                            old_block, new_last_block, leaf_count);
                     }
                 }
-            } else {
-                false
-            };
+            }
+            Err(e) => {
+                eprintln!("[Syncer] ❌ Sync error: {:?}", e);
+                // Continue trying - don't exit on error; the range is fetched again next step
+                sync_error = Some(format!("Sync of blocks after {} failed: {}", last_synced_block, e));
+            }
+        }
 
-            // DISABLED FOR DEBUGGING - Uncomment to re-enable auto-resync
-            /*
-            // If root mismatch, do a full resync from block 0
-            // This ensures we sync ALL deposits from the beginning
-            if should_resync {
-                let tree = self.tree.lock().unwrap();
-                let leaf_count = tree.get_leaf_count();
-                drop(tree);
-                
-                println!("[Syncer] 🔄 Root mismatch detected - starting full resync from block 0");
-                println!("[Syncer]    Current tree has {} leaves", leaf_count);
-                state.last_synced_block = 0; // Start from genesis to sync everything
-                Self::save_state(&state);
-                
-                // Clear the tree to force full resync
-                {
-                    let mut tree = self.tree.lock().unwrap();
-                    *tree = MerkleTree::new(TREE_DEPTH); // Reset tree - use TREE_DEPTH constant
-                }
-                println!("[Syncer] ✅ Tree cleared, will sync all events from block 0");
+        // Compare against the contract and drop divergent leaves so they are re-fetched
+        if let Some(resume_block) = self.reconcile(last_synced_block).await {
+            last_synced_block = resume_block;
+        }
+        // Reconciliation resets last_error; keep the sync failure visible on /deposit/status
+        if let Some(e) = sync_error {
+            self.status.write().unwrap().last_error.get_or_insert(e);
+        }
+        last_synced_block
+    }
+
+    fn set_paused(&self, paused: &mut bool, value: bool) {
+        if *paused != value {
+            println!("[Syncer] {} Syncing {}", if value { "⏸️ " } else { "▶️ " }, if value { "paused" } else { "resumed" });
+        }
+        *paused = value;
+        self.status.write().unwrap().paused = value;
+    }
+
+    /// Rebuild the tree from `from_block` next to the live one and swap it in atomically
+    /// once it has caught up with the chain. Leaves emitted before `from_block` are kept,
+    /// and proofs keep being served from the old tree until the swap
+    /// Returns the new cursor, or None if the rebuild failed and the old tree was kept
+    async fn rebuild(
+        &self,
+        from_block: u64,
+        commands: &mut Option<mpsc::Receiver<SyncCommand>>,
+        paused: &mut bool,
+    ) -> Option<u64> {
+        let mut from_block = from_block.max(self.deployment_block);
+
+        'restart: loop {
+            let cursor = from_block.saturating_sub(1);
+            let result = async {
+                let keep_leaves = if from_block <= self.deployment_block {
+                    0
+                } else {
+                    match self.store.first_leaf_after(cursor).await? {
+                        Some(first) => first,
+                        None => self.tree.lock().unwrap().get_leaf_count(),
+                    }
+                };
+                let latest = self.provider.block_hash_and_number().await
+                    .map_err(|e| format!("Failed to get latest block: {}", e))?;
+                Ok::<_, String>((keep_leaves, latest))
             }
-            */
-
-            match self.sync_events(state.last_synced_block).await {
-                Ok(new_last_block) => {
-                    if new_last_block > state.last_synced_block {
-                        let old_block = state.last_synced_block;
-                        state.last_synced_block = new_last_block;
-                        Self::save_state(&state);
-                        
-                        // Log progress if we synced a significant number of blocks
-                        if new_last_block - old_block > 100 {
-                            let tree = self.tree.lock().unwrap();
-                            let leaf_count = tree.get_leaf_count();
-                            drop(tree);
-                            println!("[Syncer] ✅ Synced from block {} to {} ({} leaves in tree)", 
-                                old_block, new_last_block, leaf_count);
+            .await;
+            let (keep_leaves, latest) = match result {
+                Ok(result) => result,
+                Err(e) => return self.fail_rebuild(e),
+            };
+            let target_block = latest.block_number.max(cursor);
+
+            let mut rebuilt = self.tree.lock().unwrap().clone();
+            rebuilt.truncate(keep_leaves);
+            let rebuilt = Mutex::new(rebuilt);
+
+            println!(
+                "[Syncer] 🔄 Rebuilding deposit tree from block {} to {} (keeping {} leaves)",
+                from_block, target_block, keep_leaves
+            );
+            let mut progress = RebuildProgress::new(from_block, target_block, keep_leaves);
+            self.status.write().unwrap().rebuild = Some(progress.clone());
+
+            let mut range = SyncedRange {
+                to_block: target_block,
+                block_hash: Some(format!("0x{:x}", latest.block_hash)),
+                leaf_blocks: Vec::new(),
+                deposits: Vec::new(),
+                nullifiers: Vec::new(),
+                pool_events: Vec::new(),
+                tvl_snapshots: Vec::new(),
+                finalized_block: target_block.saturating_sub(self.confirmation_depth),
+            };
+            let mut current = cursor;
+
+            while current < target_block {
+                // Apply control commands between chunks; while paused, block until resumed
+                loop {
+                    let command = if *paused {
+                        next_command(commands, Duration::from_secs(5)).await
+                    } else {
+                        commands.as_mut().and_then(|rx| rx.try_recv().ok())
+                    };
+                    match command {
+                        Some(SyncCommand::Pause) => self.set_paused(paused, true),
+                        Some(SyncCommand::Resume) => self.set_paused(paused, false),
+                        Some(SyncCommand::Resync { from_block: block }) => {
+                            println!("[Syncer] 🔄 Restarting rebuild from block {}", block);
+                            from_block = block.max(self.deployment_block);
+                            continue 'restart;
                         }
+                        None if *paused => {}
+                        None => break,
+                    }
+                }
+
+                let chunk_end = (current + REBUILD_CHUNK_BLOCKS).min(target_block);
+                match self.sync_range(&rebuilt, current, chunk_end).await {
+                    Ok(chunk) => {
+                        range.leaf_blocks.extend(chunk.leaf_blocks);
+                        range.deposits.extend(chunk.deposits);
+                        range.nullifiers.extend(chunk.nullifiers);
+                        range.pool_events.extend(chunk.pool_events);
+                        range.tvl_snapshots.extend(chunk.tvl_snapshots);
                     }
+                    Err(e) => return self.fail_rebuild(format!("Failed to fetch blocks {}-{}: {:?}", current + 1, chunk_end, e)),
                 }
+                current = chunk_end;
+
+                progress.advance(current, rebuilt.lock().unwrap().get_leaf_count());
+                self.status.write().unwrap().rebuild = Some(progress.clone());
+            }
+
+            if let Err(e) = self.store.commit_rebuild(&rebuilt, keep_leaves, cursor, &range).await {
+                return self.fail_rebuild(e);
+            }
+
+            // Swap the rebuilt tree in under a single lock
+            let rebuilt = rebuilt.into_inner().unwrap();
+            let leaf_count = rebuilt.get_leaf_count();
+            *self.tree.lock().unwrap() = rebuilt;
+
+            self.status.write().unwrap().rebuild = None;
+            println!(
+                "[Syncer] ✅ Rebuilt tree swapped in: {} leaves, synced to block {}",
+                leaf_count, target_block
+            );
+            return Some(target_block);
+        }
+    }
+
+    fn fail_rebuild(&self, error: String) -> Option<u64> {
+        eprintln!("[Syncer] ❌ Rebuild failed, keeping the current tree: {}", error);
+        let mut status = self.status.write().unwrap();
+        status.rebuild = None;
+        status.last_error = Some(error);
+        None
+    }
+
+    /// Reconcile the local root with the contract and publish the result on the shared status
+    /// If the tree diverged, every leaf from the first wrong one is dropped and the cursor is
+    /// moved to just before the block it was emitted in. Returns the new cursor in that case
+    async fn reconcile(&self, last_synced_block: u64) -> Option<u64> {
+        let blockchain = self.blockchain_client.as_ref()?;
+        let checked_at = reconcile::unix_now();
+
+        let reconciliation = match reconcile::reconcile(blockchain, &self.tree).await {
+            Ok(reconciliation) => reconciliation,
+            Err(e) => {
+                let mut status = self.status.write().unwrap();
+                status.checked_at = checked_at;
+                status.last_error = Some(e);
+                return None;
+            }
+        };
+
+        let mut resume_block = None;
+        let mut last_error = None;
+        if let Some(agreeing) = reconciliation.agreeing_leaf_count {
+            println!(
+                "[Syncer] 🛑 Local root {} was never on-chain - first {} of {} leaves agree, re-fetching the rest",
+                reconciliation.local_root, agreeing, reconciliation.leaf_count
+            );
+            // Leaves without a recorded block predate block tracking - replay from deployment
+            let block = match self.store.get_leaf_block(agreeing).await {
+                Ok(block) => block.unwrap_or(self.deployment_block).saturating_sub(1),
                 Err(e) => {
-                    eprintln!("[Syncer] ❌ Sync error: {:?}", e);
-                    // Continue trying - don't exit on error
+                    last_error = Some(e);
+                    last_synced_block
+                }
+            };
+            if last_error.is_none() {
+                match self.store.truncate_to(&self.tree, agreeing, block).await {
+                    Ok(_) => resume_block = Some(block),
+                    Err(e) => last_error = Some(e),
                 }
             }
-            sleep(Duration::from_secs(5)).await;
         }
+
+        let mut status = self.status.write().unwrap();
+        status.state = reconciliation.state;
+        status.local_root = reconciliation.local_root;
+        status.onchain_root = Some(reconciliation.onchain_root);
+        status.leaf_count = reconciliation.leaf_count;
+        status.agreeing_leaf_count = reconciliation.agreeing_leaf_count;
+        status.last_synced_block = resume_block.unwrap_or(last_synced_block);
+        status.checked_at = checked_at;
+        status.last_error = last_error;
+        resume_block
     }
 
-    async fn sync_events(&self, from_block: u64) -> Result<u64, Box<dyn std::error::Error>> {
-        let latest_block = self.provider.block_number().await?;
+    /// Hash of a block on the current canonical chain
+    async fn canonical_block_hash(&self, block: u64) -> Result<Option<FieldElement>, Box<dyn std::error::Error + Send + Sync>> {
+        match self.provider.get_block_with_tx_hashes(BlockId::Number(block)).await? {
+            MaybePendingBlockWithTxHashes::Block(block) => Ok(Some(block.block_hash)),
+            MaybePendingBlockWithTxHashes::PendingBlock(_) => Ok(None),
+        }
+    }
+
+    /// Check the last recorded block against the chain
+    /// Returns the last common ancestor if the recorded block was replaced
+    async fn detect_reorg(&self) -> Result<Option<u64>, Box<dyn std::error::Error + Send + Sync>> {
+        let checkpoints = self.store.get_block_hashes().await?;
+        let Some((last_block, last_hash)) = checkpoints.first() else {
+            return Ok(None);
+        };
+        let last_hash = FieldElement::from_hex_be(last_hash)?;
+
+        // The child's parent hash is the cheapest check; fall back to the block itself
+        // while no child has been produced yet
+        let still_canonical = match self.provider.get_block_with_tx_hashes(BlockId::Number(last_block + 1)).await {
+            Ok(MaybePendingBlockWithTxHashes::Block(child)) => child.parent_hash == last_hash,
+            _ => self.canonical_block_hash(*last_block).await? == Some(last_hash),
+        };
+        if still_canonical {
+            return Ok(None);
+        }
+
+        println!("[Syncer] ⚠️  Block {} is no longer canonical - looking for common ancestor", last_block);
+        for (block, hash) in checkpoints.iter().skip(1) {
+            if self.canonical_block_hash(*block).await? == Some(FieldElement::from_hex_be(hash)?) {
+                return Ok(Some(*block));
+            }
+        }
+
+        // No recorded block survived: fall back to the finalized block, which is
+        // assumed to be beyond the reach of any reorg
+        let finalized_block = self.store.get_finalized_block().await?;
+        println!("[Syncer] ⚠️  No recorded block matches the chain - rolling back to finalized block {}", finalized_block);
+        Ok(Some(finalized_block))
+    }
+
+    async fn sync_events(&self, from_block: u64) -> Result<SyncedRange, Box<dyn std::error::Error + Send + Sync>> {
+        let latest = self.provider.block_hash_and_number().await?;
+        let latest_block = latest.block_number;
         if from_block >= latest_block {
-            return Ok(from_block);
+            return Ok(SyncedRange::cursor_only(from_block));
         }
 
-        // Filter for events from our contract
-        // Note: For nested enum events (Event::PrivacyEvent::Deposit), the Deposit selector
-        // is in keys[2], not keys[0]. So we filter only by contract address and check
-        // all keys in the loop below.
+        let mut range = self.sync_range(&self.tree, from_block, latest_block).await?;
+        range.block_hash = Some(format!("0x{:x}", latest.block_hash));
+        range.finalized_block = latest_block.saturating_sub(self.confirmation_depth);
+        Ok(range)
+    }
+
+    /// Timestamp of `block`, fetched once per block
+    async fn block_timestamp(
+        &self,
+        block: u64,
+        cache: &mut HashMap<u64, u64>,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(timestamp) = cache.get(&block) {
+            return Ok(*timestamp);
+        }
+        let timestamp = match self.provider.get_block_with_tx_hashes(BlockId::Number(block)).await? {
+            MaybePendingBlockWithTxHashes::Block(block) => block.timestamp,
+            MaybePendingBlockWithTxHashes::PendingBlock(block) => block.timestamp,
+        };
+        cache.insert(block, timestamp);
+        Ok(timestamp)
+    }
+
+    /// Contract token balances at the end of each of `blocks`, for the TVL history
+    /// Nothing is recorded without a blockchain client or before the pool is initialized
+    async fn tvl_snapshots(
+        &self,
+        blocks: &BTreeSet<u64>,
+        timestamps: &mut HashMap<u64, u64>,
+    ) -> Result<Vec<TvlSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(blockchain) = self.blockchain_client.as_ref() else {
+            return Ok(Vec::new());
+        };
+        if blocks.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (token0, token1) = match self.pool_tokens.get() {
+            Some(tokens) => tokens.clone(),
+            None => {
+                if !blockchain.is_pool_initialized().await? {
+                    return Ok(Vec::new());
+                }
+                let tokens = (blockchain.get_pool_token0().await?, blockchain.get_pool_token1().await?);
+                self.pool_tokens.get_or_init(|| tokens).clone()
+            }
+        };
+
+        let owner = format!("0x{:x}", self.contract_address);
+        let mut snapshots = Vec::with_capacity(blocks.len());
+        for &block in blocks {
+            let (low0, high0) = blockchain.get_token_balance_at(&token0, &owner, block).await?;
+            let (low1, high1) = blockchain.get_token_balance_at(&token1, &owner, block).await?;
+            snapshots.push(TvlSnapshot {
+                block_number: block,
+                block_timestamp: self.block_timestamp(block, timestamps).await?,
+                balance0: (BigUint::from(high0) << 128) + BigUint::from(low0),
+                balance1: (BigUint::from(high1) << 128) + BigUint::from(low1),
+            });
+        }
+        Ok(snapshots)
+    }
+
+    /// Insert the deposits emitted in blocks (from_block, to_block] into `tree`
+    /// The returned range records each leaf's block, nullifiers, pool events and the contract's
+    /// balances after each block with events, but no block hash
+    async fn sync_range(
+        &self,
+        tree: &Mutex<MerkleTree>,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<SyncedRange, Box<dyn std::error::Error + Send + Sync>> {
+        let mut range = SyncedRange::cursor_only(to_block);
+
+        // Filter by contract address only; the decoder tells the event variants apart
         let filter = EventFilter {
             from_block: Some(BlockId::Number(from_block + 1)),
-            to_block: Some(BlockId::Number(latest_block)),
+            to_block: Some(BlockId::Number(to_block)),
             address: Some(self.contract_address),
-            keys: None, // Don't filter by keys - we'll check in the loop for nested events
+            keys: None,
         };
 
         let chunk_size = 1000;
         let mut continuation_token = None;
         let mut swap_events_seen = 0u32;
-        let mut _total_events_seen = 0u32;
-        let mut _is_first_page = true;
+        let mut block_timestamps = HashMap::new();
+        // Blocks whose events may move tokens in or out of the contract
+        let mut tvl_blocks = BTreeSet::new();
 
         loop {
             let events_page = self
                 .provider
                 .get_events(filter.clone(), continuation_token.clone(), chunk_size)
                 .await?;
-            
-            _total_events_seen += events_page.events.len() as u32;
-            
+
             for event in events_page.events {
-                // For nested enum events (PrivacyEvent::Deposit), the structure is:
-                // keys[0] = PrivacyEvent enum selector
-                // keys[1] = Deposit variant selector (if nested)
-                // OR keys[0] = Deposit selector (if direct)
-                // Check all keys to find the Deposit variant selector
-                let is_deposit_event = !event.keys.is_empty() && 
-                    event.keys.iter().any(|key| *key == self.deposit_selector);
-                
-                // Check for PoolEvent enum (which contains Swap)
-                // Structure: keys[0] = Event enum, keys[1] = PoolEvent enum, keys[2] = Swap variant
-                let is_pool_event = !event.keys.is_empty() && 
-                    event.keys.iter().any(|key| *key == self.pool_event_selector);
-                
-                // Check for Swap events - can be at keys[1] or keys[2] depending on nesting
-                let is_swap_event = !event.keys.is_empty() && (
-                    event.keys.iter().any(|key| *key == self.swap_selector) ||
-                    (is_pool_event && event.keys.len() >= 2 && event.keys[1] == self.swap_selector) ||
-                    (is_pool_event && event.keys.len() >= 3 && event.keys[2] == self.swap_selector)
-                );
-                
-                // Only log swap events
-                if !is_deposit_event {
-                    if is_swap_event {
-                        swap_events_seen += 1;
-                        println!(
-                            "[Syncer] 🔄 Swap event #{} detected: keys={:?}, data_len={}",
-                            swap_events_seen,
-                            event.keys.iter().map(|k| format!("0x{:x}", k)).collect::<Vec<_>>(),
-                            event.data.len()
-                        );
-                        if event.data.len() >= 6 {
+                let event_block = event.block_number.unwrap_or(to_block);
+                let tx_hash = format!("0x{:x}", event.transaction_hash);
+
+                // Every event the contract emits is in its ABI, so a decode failure means the
+                // ABI is out of date: fail the range rather than commit it without this event
+                let decoded = decode_zylith_event(&event.keys, &event.data).map_err(|e| {
+                    format!("Undecodable event in tx {} (block {}): {}", tx_hash, event_block, e)
+                })?;
+
+                let deposit = match decoded {
+                    ZylithEvent::Privacy(PrivacyEvent::Deposit(deposit)) => {
+                        tvl_blocks.insert(event_block);
+                        deposit
+                    }
+                    ZylithEvent::Privacy(PrivacyEvent::NullifierSpent(spent)) => {
+                        tvl_blocks.insert(event_block);
+                        range.nullifiers.push(NullifierEvent {
+                            nullifier: format!("0x{:x}", spent.nullifier),
+                            block_number: event_block,
+                            tx_hash,
+                        });
+                        continue;
+                    }
+                    ZylithEvent::Pool(pool_event) => {
+                        if !matches!(pool_event, PoolEvent::Initialize(_)) {
+                            tvl_blocks.insert(event_block);
+                        }
+                        if let PoolEvent::Swap(swap) = &pool_event {
+                            swap_events_seen += 1;
                             println!(
-                                "  📊 Swap details: sender=0x{:x}, recipient=0x{:x}, amount0={:?}, amount1={:?}",
-                                event.data[0], event.data[1], event.data[2], event.data[3]
+                                "[Syncer] 🔄 Swap event #{}: sender=0x{:x}, amount0={}, amount1={}, tick={}",
+                                swap_events_seen, swap.sender, swap.amount0, swap.amount1, swap.tick
                             );
                         }
+                        range.pool_events.push(PoolEventRecord {
+                            block_number: event_block,
+                            block_timestamp: self.block_timestamp(event_block, &mut block_timestamps).await?,
+                            tx_hash,
+                            event: pool_event,
+                        });
+                        continue;
                     }
-                    continue;
-                }
-                
-                // Skip verbose deposit event logging - only log summary
-
-                // Parse Deposit event data:
-                // For nested events, the data structure is:
-                // data[0] = commitment (felt252)
-                // data[1] = leaf_index (u32)
-                // data[2] = root (felt252)
-                if event.data.len() >= 3 {
-                        let commitment_felt = event.data[0];
-                    let leaf_index_felt = event.data[1];
-
-                    // Convert to BigUint for our Merkle tree
-                    let commitment = BigUint::from_bytes_be(&commitment_felt.to_bytes_be());
-                    let leaf_index: u32 = {
-                        let bytes = leaf_index_felt.to_bytes_be();
-                        let mut arr = [0u8; 4];
-                        let start = bytes.len().saturating_sub(4);
-                        arr.copy_from_slice(&bytes[start..]);
-                        u32::from_be_bytes(arr)
-                    };
+                    _ => continue,
+                };
 
-                    // Get zero leaf and current count before acquiring mutable lock
-                    let (current_count, zero_leaf) = {
-                        let tree = self.tree.lock().unwrap();
-                        (tree.get_leaf_count(), tree.zeros[0].clone())
-                    };
+                // Convert to BigUint for our Merkle tree
+                let commitment = BigUint::from_bytes_be(&deposit.commitment.to_bytes_be());
+                let leaf_index = deposit.leaf_index;
 
-                    // Insert into our tree
-                    let mut tree = self.tree.lock().unwrap();
+                range.deposits.push(DepositEvent {
+                    commitment: format!("0x{:x}", commitment),
+                    leaf_index,
+                    block_number: event_block,
+                    tx_hash,
+                });
 
-                    // Handle gaps: if leaf_index is greater than current count, insert empty leaves
-                    if leaf_index > current_count {
-                        let gaps = leaf_index - current_count;
-                        // Insert empty leaves (zeros) to fill the gap
-                        for i in 0..gaps {
-                            tree.insert_at_index(current_count + i, zero_leaf.clone());
-                        }
-                    } else if leaf_index < current_count {
-                        // Check if this commitment already exists at this index
-                        if let Some(existing_leaf) = tree.nodes.get(&(0, leaf_index)) {
-                            if existing_leaf == &commitment {
-                                // Skip silently - already processed
-                                continue;
-                            }
+                // Get zero leaf and current count before acquiring mutable lock
+                let (current_count, zero_leaf) = {
+                    let tree = tree.lock().unwrap();
+                    (tree.get_leaf_count(), tree.zeros[0].clone())
+                };
+
+                // Insert into our tree
+                let mut tree = tree.lock().unwrap();
+
+                // Handle gaps: if leaf_index is greater than current count, insert empty leaves
+                if leaf_index > current_count {
+                    let gaps = leaf_index - current_count;
+                    // Insert empty leaves (zeros) to fill the gap
+                    for i in 0..gaps {
+                        tree.insert_at_index(current_count + i, zero_leaf.clone());
+                        range.leaf_blocks.push((current_count + i, event_block));
+                    }
+                } else if leaf_index < current_count {
+                    // Check if this commitment already exists at this index
+                    if let Some(existing_leaf) = tree.nodes.get(&(0, leaf_index)) {
+                        if existing_leaf == &commitment {
+                            // Skip silently - already processed
+                            continue;
                         }
                     }
+                }
 
-                    // Insert the commitment at the correct index
-                    if leaf_index == current_count {
-                        // Normal sequential insert
-                        tree.insert(commitment.clone());
-                    } else {
-                        // Insert at specific index (filling gaps already handled above)
-                        tree.insert_at_index(leaf_index, commitment.clone());
-                    }
-                    // Process silently - no logging
+                // Insert the commitment at the correct index
+                if leaf_index == current_count {
+                    // Normal sequential insert
+                    tree.insert(commitment.clone());
+                } else {
+                    // Insert at specific index (filling gaps already handled above)
+                    tree.insert_at_index(leaf_index, commitment.clone());
                 }
+                range.leaf_blocks.push((leaf_index, event_block));
             }
 
             continuation_token = events_page.continuation_token;
@@ -373,6 +689,8 @@ impl Syncer {
             println!("[Syncer] 🔄 Found {} swap event(s)", swap_events_seen);
         }
 
-        Ok(latest_block)
+        range.tvl_snapshots = self.tvl_snapshots(&tvl_blocks, &mut block_timestamps).await?;
+
+        Ok(range)
     }
 }
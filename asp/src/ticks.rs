@@ -0,0 +1,201 @@
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+
+/// Tick spacing enforced by the contract on position bounds
+pub const TICK_SPACING: i32 = 60;
+
+/// Tick range of the pool
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+
+/// Liquidity referenced by an initialized tick
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TickInfo {
+    pub liquidity_gross: u128,
+    /// Liquidity added when the tick is crossed left to right (removed right to left)
+    pub liquidity_net: i128,
+}
+
+/// Off-chain copy of the contract's tick bitmap and per-tick liquidity,
+/// rebuilt from Mint and Burn events
+/// The deployed contract does not emit those events and exposes no tick getters, so the map is
+/// only trusted when `unavailable_reason` finds nothing wrong with it
+#[derive(Debug, Clone, Default)]
+pub struct TickMap {
+    /// 256-bit words keyed by word position, one bit per tick
+    bitmap: BTreeMap<i32, BigUint>,
+    ticks: BTreeMap<i32, TickInfo>,
+}
+
+/// Word and bit position of a tick in the bitmap (ticks are not compressed by spacing)
+pub fn position(tick: i32) -> (i32, u64) {
+    (tick.div_euclid(256), tick.rem_euclid(256) as u64)
+}
+
+impl TickMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a position update, as `_update_tick` does for both bounds
+    /// The contract runs the same update on mint and on burn (burns add to the ticks too),
+    /// so both are applied with this and the map stays identical to on-chain storage
+    pub fn update_position(&mut self, tick_lower: i32, tick_upper: i32, liquidity: u128) {
+        self.update_tick(tick_lower, liquidity, false);
+        self.update_tick(tick_upper, liquidity, true);
+    }
+
+    fn update_tick(&mut self, tick: i32, liquidity_delta: u128, upper: bool) {
+        let info = self.ticks.entry(tick).or_insert_with(|| {
+            let (word_pos, bit_pos) = position(tick);
+            self.bitmap.entry(word_pos).or_default().set_bit(bit_pos, true);
+            TickInfo::default()
+        });
+
+        info.liquidity_gross = info.liquidity_gross.saturating_add(liquidity_delta);
+        let delta = i128::try_from(liquidity_delta).unwrap_or(i128::MAX);
+        info.liquidity_net = if upper {
+            info.liquidity_net.saturating_sub(delta)
+        } else {
+            info.liquidity_net.saturating_add(delta)
+        };
+    }
+
+    pub fn get(&self, tick: i32) -> Option<&TickInfo> {
+        self.ticks.get(&tick)
+    }
+
+    /// Initialized ticks in ascending order
+    pub fn ticks(&self) -> impl DoubleEndedIterator<Item = (i32, &TickInfo)> {
+        self.ticks.iter().map(|(tick, info)| (*tick, info))
+    }
+
+    /// Active liquidity implied by the ticks at `tick`: the net of every tick at or below it
+    pub fn liquidity_at(&self, tick: i32) -> i128 {
+        self.ticks
+            .range(..=tick)
+            .fold(0i128, |sum, (_, info)| sum.saturating_add(info.liquidity_net))
+    }
+
+    /// Next initialized tick in the swap direction, as `_get_next_initialized_tick` finds it
+    /// Downwards only ticks strictly below `tick` match; upwards, ticks above it. The word
+    /// scan stops at `tick_limit / 256` (truncated, like Cairo) and returns `tick_limit`
+    /// when nothing is found
+    pub fn next_initialized_tick(&self, tick: i32, tick_limit: i32, zero_for_one: bool) -> i32 {
+        let search_tick = if zero_for_one { tick } else { tick + 1 };
+        let (word_pos, bit_pos) = position(search_tick);
+
+        if zero_for_one {
+            if let Some(word) = self.bitmap.get(&word_pos) {
+                if let Some(bit) = (0..bit_pos).rev().find(|bit| word.bit(*bit)) {
+                    return word_pos * 256 + bit as i32;
+                }
+            }
+            let min_word_pos = tick_limit.max(MIN_TICK) / 256;
+            if min_word_pos < word_pos {
+                for (pos, word) in self.bitmap.range(min_word_pos..word_pos).rev() {
+                    if word.bits() > 0 {
+                        return pos * 256 + (word.bits() - 1) as i32;
+                    }
+                }
+            }
+        } else {
+            if let Some(word) = self.bitmap.get(&word_pos) {
+                if let Some(bit) = (bit_pos + 1..256).find(|bit| word.bit(*bit)) {
+                    return word_pos * 256 + bit as i32;
+                }
+            }
+            let max_word_pos = tick_limit.min(MAX_TICK) / 256;
+            if word_pos < max_word_pos {
+                for (pos, word) in self.bitmap.range(word_pos + 1..=max_word_pos) {
+                    if let Some(lowest) = word.trailing_zeros() {
+                        return pos * 256 + lowest as i32;
+                    }
+                }
+            }
+        }
+        tick_limit
+    }
+
+    /// Why the map can't stand in for the contract's tick storage, or None when it can
+    /// `pool` is the indexed (tick, active liquidity), `complete_history` whether every pool
+    /// event since deployment is indexed. An empty map is never trusted: with no position
+    /// events indexed there is no telling an empty pool from one whose events aren't emitted
+    pub fn unavailable_reason(&self, pool: Option<(i32, u128)>, complete_history: bool) -> Option<String> {
+        if !complete_history {
+            return Some("pool events are indexed from a later block than deployment; resync from the deployment block".to_string());
+        }
+        if self.ticks.is_empty() {
+            return Some("no Mint or Burn events are indexed, so the initialized ticks are unknown".to_string());
+        }
+        let (tick, liquidity) = pool?;
+        let implied = self.liquidity_at(tick);
+        (implied != liquidity as i128).then(|| format!(
+            "the ticks imply liquidity {} at tick {} but the pool reports {}",
+            implied, tick, liquidity
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_matches_contract() {
+        assert_eq!(position(0), (0, 0));
+        assert_eq!(position(255), (0, 255));
+        assert_eq!(position(256), (1, 0));
+        assert_eq!(position(-1), (-1, 255));
+        assert_eq!(position(-256), (-1, 0));
+        assert_eq!(position(-257), (-2, 255));
+    }
+
+    #[test]
+    fn test_tick_map_liquidity_and_search() {
+        let mut map = TickMap::new();
+        map.update_position(-120, 120, 1000);
+        map.update_position(60, 600, 500);
+
+        let initialized: Vec<i32> = map.ticks().map(|(tick, _)| tick).collect();
+        assert_eq!(initialized, vec![-120, 60, 120, 600]);
+        assert!(map.get(0).is_none());
+        assert_eq!(map.get(120), Some(&TickInfo { liquidity_gross: 1000, liquidity_net: -1000 }));
+
+        assert_eq!(map.liquidity_at(-121), 0);
+        assert_eq!(map.liquidity_at(0), 1000);
+        assert_eq!(map.liquidity_at(60), 1500);
+        assert_eq!(map.liquidity_at(120), 500);
+        assert_eq!(map.liquidity_at(600), 0);
+
+        // Downwards skips the tick it starts on
+        assert_eq!(map.next_initialized_tick(60, MIN_TICK, true), -120);
+        assert_eq!(map.next_initialized_tick(61, MIN_TICK, true), 60);
+        assert_eq!(map.next_initialized_tick(-120, MIN_TICK, true), MIN_TICK);
+        // Upwards crosses into the next word
+        assert_eq!(map.next_initialized_tick(0, MAX_TICK, false), 60);
+        assert_eq!(map.next_initialized_tick(120, MAX_TICK, false), 600);
+        assert_eq!(map.next_initialized_tick(600, MAX_TICK, false), MAX_TICK);
+        // The scan stops at the limit's word
+        assert_eq!(map.next_initialized_tick(120, 300, false), 300);
+
+        // Burns update the ticks like mints do on-chain
+        map.update_position(-120, 120, 1000);
+        assert_eq!(map.get(-120).unwrap().liquidity_net, 2000);
+    }
+
+    #[test]
+    fn test_unavailable_reason() {
+        // What the map looks like against the deployed contract: no position events at all
+        let empty = TickMap::new();
+        assert!(empty.unavailable_reason(Some((0, 0)), true).unwrap().contains("no Mint or Burn"));
+
+        let mut map = TickMap::new();
+        map.update_position(-120, 120, 1000);
+        assert!(map.unavailable_reason(Some((0, 1000)), false).unwrap().contains("resync"));
+        assert_eq!(map.unavailable_reason(Some((0, 1000)), true), None);
+        assert_eq!(map.unavailable_reason(None, true), None);
+        let reason = map.unavailable_reason(Some((0, 1500)), true).unwrap();
+        assert!(reason.contains("imply liquidity 1000 at tick 0"), "{}", reason);
+    }
+}
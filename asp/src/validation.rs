@@ -0,0 +1,251 @@
+// Proof request validation - decodes request fields into typed values with the ranges the
+// circuits and the contract accept, collecting every invalid field into one 400 response
+// instead of failing at the first one (or deep inside snarkjs)
+
+use crate::clmm::{self, MAX_SQRT_RATIO, MIN_SQRT_RATIO};
+use crate::garaga::STARKNET_PRIME;
+use crate::merkle::TREE_DEPTH;
+use crate::ticks::{MAX_TICK, MIN_TICK};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use num_bigint::BigUint;
+use num_traits::Num;
+use primitive_types::U256;
+use serde::Serialize;
+
+/// BN254 scalar field modulus: every circuit signal lives below it
+const BN254_MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+/// Parse a field element given as 0x-hex or decimal
+pub fn parse_field_element(name: &str, value: &str) -> Result<BigUint, String> {
+    let value = value.trim();
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => BigUint::from_str_radix(hex, 16),
+        None => BigUint::from_str_radix(value, 10),
+    };
+    parsed.map_err(|_| format!("Invalid {} '{}': expected 0x-hex or decimal", name, value))
+}
+
+/// A request field that failed validation
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct InvalidField {
+    pub field: String,
+    pub value: String,
+    pub reason: String,
+}
+
+/// Every invalid field of a request; answers 400 with the full list
+#[derive(Debug)]
+pub struct InvalidRequest(pub Vec<InvalidField>);
+
+impl IntoResponse for InvalidRequest {
+    fn into_response(self) -> Response {
+        let fields: Vec<&str> = self.0.iter().map(|f| f.field.as_str()).collect();
+        println!("[ASP] ❌ Invalid request fields: {}", fields.join(", "));
+        (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "error": format!("Invalid request: {} invalid field(s): {}", fields.len(), fields.join(", ")),
+            "invalid_fields": self.0,
+        })))
+            .into_response()
+    }
+}
+
+/// Collects invalid fields while decoding a request. Each decoder returns the typed value, or
+/// a default placeholder when the field is invalid; the placeholders are never used because
+/// `finish` fails whenever one was returned
+#[derive(Default)]
+pub struct Validator {
+    invalid: Vec<InvalidField>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an invalid field found by a check outside this module
+    pub fn reject(&mut self, field: &str, value: impl ToString, reason: impl Into<String>) {
+        self.invalid.push(InvalidField {
+            field: field.to_string(),
+            value: value.to_string(),
+            reason: reason.into(),
+        });
+    }
+
+    /// Whether a field was already recorded as invalid
+    pub fn rejected(&self, field: &str) -> bool {
+        self.invalid.iter().any(|f| f.field == field)
+    }
+
+    /// Keep a value parsed elsewhere, or record `expected` as the reason it is invalid
+    pub fn parsed<T: Default>(&mut self, field: &str, value: &str, parsed: Option<T>, expected: &str) -> T {
+        parsed.unwrap_or_else(|| {
+            self.reject(field, value, format!("expected {}", expected));
+            T::default()
+        })
+    }
+
+    fn field_below(&mut self, field: &str, value: &str, modulus: &str, name: &str) -> BigUint {
+        let modulus = BigUint::from_str_radix(modulus, 10).expect("valid modulus");
+        match parse_field_element(field, value) {
+            Ok(parsed) if parsed < modulus => parsed,
+            Ok(_) => {
+                self.reject(field, value, format!("must be below the {} modulus", name));
+                BigUint::default()
+            }
+            Err(_) => {
+                self.reject(field, value, "expected 0x-hex or decimal");
+                BigUint::default()
+            }
+        }
+    }
+
+    /// A value the contract reads as felt252 (hashes, addresses): below the Starknet prime
+    pub fn felt252(&mut self, field: &str, value: &str) -> BigUint {
+        self.field_below(field, value, STARKNET_PRIME, "felt252")
+    }
+
+    /// A private circuit signal: below the BN254 scalar field modulus
+    pub fn bn254(&mut self, field: &str, value: &str) -> BigUint {
+        self.field_below(field, value, BN254_MODULUS, "BN254 scalar field")
+    }
+
+    /// An amount or liquidity: a decimal u128
+    pub fn u128(&mut self, field: &str, value: &str) -> u128 {
+        self.parsed(field, value, value.trim().parse().ok(), "a decimal u128")
+    }
+
+    /// A non-zero amount or liquidity
+    pub fn positive_u128(&mut self, field: &str, value: &str) -> u128 {
+        let parsed = value.trim().parse::<u128>().ok().filter(|v| *v > 0);
+        self.parsed(field, value, parsed, "a positive decimal u128")
+    }
+
+    /// A signed amount, as a signed decimal or its two's complement modulo 2^128
+    pub fn i128(&mut self, field: &str, value: &str) -> i128 {
+        let value = value.trim();
+        let parsed = value.parse::<i128>().ok().or_else(|| value.parse::<u128>().ok().map(|v| v as i128));
+        self.parsed(field, value, parsed, "a signed decimal i128 or its two's complement u128")
+    }
+
+    /// A Q128.128 sqrt price (decimal, 0x-hex or "low,high"): a price the pool can reach, in
+    /// [MIN_SQRT_RATIO, MAX_SQRT_RATIO], and a circuit signal below the BN254 modulus
+    pub fn sqrt_price_x128(&mut self, field: &str, value: &str) -> U256 {
+        let modulus = BigUint::from_str_radix(BN254_MODULUS, 10).expect("valid modulus");
+        let parsed = clmm::parse_u256(value).ok().filter(|price| {
+            (MIN_SQRT_RATIO..=MAX_SQRT_RATIO).contains(price) && clmm::biguint_from_u256(*price) < modulus
+        });
+        self.parsed(field, value, parsed, "a sqrt price X128 in [2^64, 2^192], decimal, 0x-hex or \"low,high\"")
+    }
+
+    /// A tick, as a signed decimal or its two's complement modulo 2^32, within the pool's range
+    pub fn tick(&mut self, field: &str, value: &str) -> i32 {
+        let value = value.trim();
+        match value.parse::<i32>().ok().or_else(|| value.parse::<u32>().ok().map(|v| v as i32)) {
+            Some(tick) => self.check_tick(field, tick),
+            None => self.parsed(field, value, None, "a signed decimal i32 or its two's complement u32"),
+        }
+    }
+
+    /// Range check of a tick already decoded as i32
+    pub fn check_tick(&mut self, field: &str, tick: i32) -> i32 {
+        if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+            self.reject(field, tick, format!("tick outside [{}, {}]", MIN_TICK, MAX_TICK));
+        }
+        tick
+    }
+
+    /// A boolean circuit signal given as "0" or "1"
+    pub fn bit(&mut self, field: &str, value: &str) -> bool {
+        let parsed = match value.trim() {
+            "0" => Some(false),
+            "1" => Some(true),
+            _ => None,
+        };
+        self.parsed(field, value, parsed, "\"0\" or \"1\"")
+    }
+
+    /// A Merkle path of the deposit tree: TREE_DEPTH siblings below the BN254 modulus and
+    /// as many 0/1 indices
+    pub fn merkle_path(&mut self, elements: &[String], indices: &[u32]) -> (Vec<BigUint>, Vec<u32>) {
+        for (field, len) in [("pathElements", elements.len()), ("pathIndices", indices.len())] {
+            if len != TREE_DEPTH {
+                self.reject(field, format!("{} elements", len), format!(
+                    "expected {} elements (the Merkle proof from /deposit/proof/:index or a prepare endpoint)",
+                    TREE_DEPTH
+                ));
+            }
+        }
+        let elements = elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| self.bn254(&format!("pathElements[{}]", i), element))
+            .collect();
+        for (i, index) in indices.iter().enumerate() {
+            if *index > 1 {
+                self.reject(&format!("pathIndices[{}]", i), index, "expected 0 or 1");
+            }
+        }
+        (elements, indices.to_vec())
+    }
+
+    /// Ok when every decoded field was valid
+    pub fn finish(self) -> Result<(), InvalidRequest> {
+        if self.invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidRequest(self.invalid))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collects_every_invalid_field() {
+        let mut v = Validator::new();
+        assert_eq!(v.felt252("root", "0x10"), BigUint::from(16u8));
+        v.felt252("new_commitment", STARKNET_PRIME);
+        v.bn254("secret", BN254_MODULUS);
+        assert_eq!(v.u128("amount_in", "340282366920938463463374607431768211455"), u128::MAX);
+        v.u128("amount_out", "340282366920938463463374607431768211456");
+        v.positive_u128("liquidity", "0");
+        assert_eq!(v.i128("amount0_delta", "-5"), -5);
+        assert_eq!(v.i128("amount1_delta", &(-5i128 as u128).to_string()), -5);
+        assert_eq!(v.tick("new_tick", &(-60i32 as u32).to_string()), -60);
+        v.tick("tick_upper", "887273");
+        v.bit("zero_for_one", "2");
+        assert_eq!(v.sqrt_price_x128("sqrt_price_old", "0x100000000000000000000000000000000"), clmm::Q128);
+        assert_eq!(v.sqrt_price_x128("sqrt_price_limit", "0,1"), clmm::Q128);
+        v.sqrt_price_x128("new_sqrt_price_x128", "0");
+        v.sqrt_price_x128("sqrt_price_limit_x128", &(MAX_SQRT_RATIO + 1).to_string());
+        v.sqrt_price_x128("sqrt_price_x128", "1.5");
+
+        let mut elements = vec!["0".to_string(); TREE_DEPTH];
+        elements[3] = BN254_MODULUS.to_string();
+        let mut indices = vec![0u32; TREE_DEPTH - 1];
+        indices[7] = 2;
+        v.merkle_path(&elements, &indices);
+
+        let fields: Vec<String> = v.finish().unwrap_err().0.into_iter().map(|f| f.field).collect();
+        assert_eq!(fields, vec![
+            "new_commitment", "secret", "amount_out", "liquidity", "tick_upper", "zero_for_one",
+            "new_sqrt_price_x128", "sqrt_price_limit_x128", "sqrt_price_x128", "pathIndices", "pathElements[3]", "pathIndices[7]",
+        ]);
+    }
+
+    #[test]
+    fn test_valid_request_finishes() {
+        let mut v = Validator::new();
+        v.felt252("recipient", "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7");
+        v.merkle_path(&vec!["1".to_string(); TREE_DEPTH], &[1; TREE_DEPTH]);
+        assert_eq!(v.check_tick("tick_lower", MIN_TICK), MIN_TICK);
+        assert!(v.finish().is_ok());
+    }
+}
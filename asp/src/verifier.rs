@@ -0,0 +1,308 @@
+// Groth16 verification of the proofs the ASP generates
+// Every proof is checked against its circuit's verification key before it is formatted for
+// Garaga, so a bad witness or a zkey that does not match the deployed verifier fails here,
+// naming the public input at fault, instead of as a reverted transaction and a
+// ProofRejected event on-chain
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::Num;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Public inputs of a circuit in the order snarkjs lists them: declaration order in the
+/// circom template (none of the circuits has outputs)
+pub fn public_input_names(circuit: &str) -> &'static [&'static str] {
+    match circuit {
+        "swap" => &[
+            "nullifier",
+            "root",
+            "new_commitment",
+            "amount_specified",
+            "zero_for_one",
+            "amount0_delta",
+            "amount1_delta",
+            "new_sqrt_price_x128",
+            "new_tick",
+        ],
+        "withdraw" => &["nullifier", "root", "recipient", "amount"],
+        "lp" => &[
+            "nullifier",
+            "root",
+            "tick_lower",
+            "tick_upper",
+            "liquidity",
+            "new_commitment",
+            "position_commitment",
+        ],
+        "membership" => &["root", "commitment"],
+        _ => &[],
+    }
+}
+
+/// A circuit's verification key, prepared for pairing checks
+#[derive(Debug, Clone)]
+pub struct VerificationKey {
+    circuit: String,
+    key: PreparedVerifyingKey<Bn254>,
+}
+
+/// Why a generated proof was rejected
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofRejection {
+    pub circuit: String,
+    /// public_input_count, public_input_out_of_range, public_input_mismatch,
+    /// malformed_proof or pairing_check_failed
+    pub reason: &'static str,
+    /// The public input at fault, when one can be named
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_input: Option<RejectedInput>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedInput {
+    pub index: usize,
+    pub name: &'static str,
+    /// What the proof's public signals hold
+    pub value: String,
+    /// What the circuit input asked for (reduced into the BN254 scalar field)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+}
+
+impl std::fmt::Display for ProofRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl VerificationKey {
+    /// Read a verification key exported by `snarkjs zkey export verificationkey`
+    pub fn from_snarkjs(circuit: &str, json: &str) -> Result<Self, String> {
+        let vk: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let ic = vk["IC"]
+            .as_array()
+            .ok_or("Missing IC")?
+            .iter()
+            .map(g1)
+            .collect::<Result<Vec<_>, String>>()?;
+        let key = VerifyingKey::<Bn254> {
+            alpha_g1: g1(&vk["vk_alpha_1"]).map_err(|e| format!("vk_alpha_1: {}", e))?,
+            beta_g2: g2(&vk["vk_beta_2"]).map_err(|e| format!("vk_beta_2: {}", e))?,
+            gamma_g2: g2(&vk["vk_gamma_2"]).map_err(|e| format!("vk_gamma_2: {}", e))?,
+            delta_g2: g2(&vk["vk_delta_2"]).map_err(|e| format!("vk_delta_2: {}", e))?,
+            gamma_abc_g1: ic,
+        };
+        Ok(Self { circuit: circuit.to_string(), key: ark_groth16::prepare_verifying_key(&key) })
+    }
+
+    pub fn n_public(&self) -> usize {
+        self.key.vk.gamma_abc_g1.len() - 1
+    }
+
+    /// Check a snarkjs-layout proof and its public signals. Public signals are first compared
+    /// with the circuit input they came from, so a witness that moved one is reported by name;
+    /// a pairing failure with matching inputs means the zkey and verification key differ
+    pub fn verify(
+        &self,
+        proof: &serde_json::Value,
+        public_signals: &serde_json::Value,
+        input: &serde_json::Value,
+    ) -> Result<(), Box<ProofRejection>> {
+        let names = public_input_names(&self.circuit);
+        let signals = public_signals.as_array().map(Vec::as_slice).unwrap_or_default();
+        if signals.len() != self.n_public() {
+            return Err(self.reject(
+                "public_input_count",
+                None,
+                format!("proof has {} public signals, the verification key takes {}", signals.len(), self.n_public()),
+            ));
+        }
+
+        let modulus = BigUint::from(Fr::MODULUS);
+        let mut public_inputs = Vec::with_capacity(signals.len());
+        for (index, signal) in signals.iter().enumerate() {
+            let name = names.get(index).copied().unwrap_or("unnamed");
+            let rejected = |expected: Option<String>| RejectedInput {
+                index,
+                name,
+                value: signal.as_str().map(str::to_string).unwrap_or_else(|| signal.to_string()),
+                expected,
+            };
+
+            let value = match signal.as_str().and_then(|s| BigUint::from_str(s).ok()) {
+                Some(value) if value < modulus => value,
+                _ => {
+                    return Err(self.reject(
+                        "public_input_out_of_range",
+                        Some(rejected(None)),
+                        format!("public input {} ({}) is not a BN254 scalar: {}", index, name, signal),
+                    ))
+                }
+            };
+            if let Some(expected) = input.get(name).and_then(|value| field_value(value, &modulus)) {
+                if expected != value {
+                    return Err(self.reject(
+                        "public_input_mismatch",
+                        Some(rejected(Some(expected.to_string()))),
+                        format!(
+                            "public input {} ({}) is {} but the circuit input was {}",
+                            index, name, value, expected
+                        ),
+                    ));
+                }
+            }
+            public_inputs.push(Fr::from(value));
+        }
+
+        let proof = parse_proof(proof).map_err(|e| self.reject("malformed_proof", None, e))?;
+        match Groth16::<Bn254>::verify_proof(&self.key, &proof, &public_inputs) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(self.reject(
+                "pairing_check_failed",
+                None,
+                "proof does not verify with these public inputs: the zkey does not match the verification key".to_string(),
+            )),
+            Err(e) => Err(self.reject("malformed_proof", None, format!("verification failed: {}", e))),
+        }
+    }
+
+    fn reject(&self, reason: &'static str, public_input: Option<RejectedInput>, detail: String) -> Box<ProofRejection> {
+        Box::new(ProofRejection {
+            circuit: self.circuit.clone(),
+            reason,
+            public_input,
+            message: format!("Proof rejected by the '{}' verification key: {}", self.circuit, detail),
+        })
+    }
+}
+
+/// A circuit input value (decimal, negative decimal, 0x-hex, number or bool) reduced into the
+/// scalar field, as the witness calculator reads it
+fn field_value(value: &serde_json::Value, modulus: &BigUint) -> Option<BigUint> {
+    let value = match value {
+        serde_json::Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => BigInt::from_str_radix(hex, 16).ok()?,
+            None => BigInt::from_str(s).ok()?,
+        },
+        serde_json::Value::Number(n) => BigInt::from_str(&n.to_string()).ok()?,
+        serde_json::Value::Bool(b) => BigInt::from(*b as u8),
+        _ => return None,
+    };
+    let modulus = BigInt::from_biguint(Sign::Plus, modulus.clone());
+    (((value % &modulus) + &modulus) % &modulus).to_biguint()
+}
+
+fn coordinate(value: &serde_json::Value) -> Result<Fq, String> {
+    value
+        .as_str()
+        .and_then(|s| BigUint::from_str(s).ok())
+        .filter(|n| *n < BigUint::from(Fq::MODULUS))
+        .map(Fq::from)
+        .ok_or_else(|| format!("invalid coordinate {}", value))
+}
+
+/// [x, y] or [x, y, z] with z = 1 (z = 0 is the point at infinity)
+fn g1(point: &serde_json::Value) -> Result<G1Affine, String> {
+    if point.get(2).and_then(|z| z.as_str()) == Some("0") {
+        return Ok(G1Affine::identity());
+    }
+    let point = G1Affine::new_unchecked(coordinate(&point[0])?, coordinate(&point[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err("G1 point is not on the curve".to_string());
+    }
+    Ok(point)
+}
+
+/// [[x0, x1], [y0, y1]] or with a third [z0, z1]
+fn g2(point: &serde_json::Value) -> Result<G2Affine, String> {
+    if point.get(2).is_some_and(|z| z[0].as_str() == Some("0") && z[1].as_str() == Some("0")) {
+        return Ok(G2Affine::identity());
+    }
+    let x = Fq2::new(coordinate(&point[0][0])?, coordinate(&point[0][1])?);
+    let y = Fq2::new(coordinate(&point[1][0])?, coordinate(&point[1][1])?);
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err("G2 point is not on the curve".to_string());
+    }
+    Ok(point)
+}
+
+fn parse_proof(proof: &serde_json::Value) -> Result<Proof<Bn254>, String> {
+    Ok(Proof {
+        a: g1(&proof["pi_a"]).map_err(|e| format!("pi_a: {}", e))?,
+        b: g2(&proof["pi_b"]).map_err(|e| format!("pi_b: {}", e))?,
+        c: g1(&proof["pi_c"]).map_err(|e| format!("pi_c: {}", e))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn withdraw_key() -> VerificationKey {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../circuits/build/vkeys/withdraw_vk.json");
+        VerificationKey::from_snarkjs("withdraw", &std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// A well-formed proof made of the generators, which no verification key accepts
+    fn generator_proof() -> serde_json::Value {
+        serde_json::json!({
+            "pi_a": ["1", "2", "1"],
+            "pi_b": [
+                ["10857046999023057135944570762232829481370756359578518086990519993285655852781",
+                 "11559732032986387107991004021392285783925812861821192530917403151452391805634"],
+                ["8495653923123431417604973247489272438418190587263600148770280649306958101930",
+                 "4082367875863433681332203403145435568316851327593401208105741076214120093531"],
+                ["1", "0"]
+            ],
+            "pi_c": ["1", "2", "1"],
+        })
+    }
+
+    #[test]
+    fn test_rejections_name_the_public_input() {
+        let key = withdraw_key();
+        assert_eq!(key.n_public(), public_input_names("withdraw").len());
+        let input = serde_json::json!({ "nullifier": "0x10", "root": "5", "recipient": "7", "amount": 1000 });
+
+        let err = key.verify(&generator_proof(), &serde_json::json!(["16", "5", "7"]), &input).unwrap_err();
+        assert_eq!(err.reason, "public_input_count");
+
+        // The witness moved the amount: named before any pairing is attempted
+        let err = key.verify(&generator_proof(), &serde_json::json!(["16", "5", "7", "999"]), &input).unwrap_err();
+        assert_eq!(err.reason, "public_input_mismatch");
+        let rejected = err.public_input.unwrap();
+        assert_eq!((rejected.index, rejected.name, rejected.expected.as_deref()), (3, "amount", Some("1000")));
+
+        let modulus = BigUint::from(Fr::MODULUS).to_string();
+        let err = key.verify(&generator_proof(), &serde_json::json!(["16", modulus, "7", "1000"]), &input).unwrap_err();
+        assert_eq!(err.reason, "public_input_out_of_range");
+        assert_eq!(err.public_input.unwrap().name, "root");
+
+        // Matching inputs with a proof the key does not accept
+        let signals = serde_json::json!(["16", "5", "7", "1000"]);
+        let err = key.verify(&generator_proof(), &signals, &input).unwrap_err();
+        assert_eq!(err.reason, "pairing_check_failed");
+        assert!(err.public_input.is_none());
+        assert!(err.to_string().contains("'withdraw' verification key"));
+
+        let mut off_curve = generator_proof();
+        off_curve["pi_a"][1] = serde_json::json!("3");
+        assert_eq!(key.verify(&off_curve, &signals, &input).unwrap_err().reason, "malformed_proof");
+    }
+
+    #[test]
+    fn test_negative_inputs_wrap_into_the_field() {
+        let modulus = BigUint::from(Fr::MODULUS);
+        assert_eq!(field_value(&serde_json::json!("-600"), &modulus), Some(&modulus - 600u32));
+        assert_eq!(field_value(&serde_json::json!(-1), &modulus), Some(&modulus - 1u32));
+        assert_eq!(field_value(&serde_json::json!("0xff"), &modulus), Some(BigUint::from(255u32)));
+        assert_eq!(field_value(&serde_json::json!(true), &modulus), Some(BigUint::from(1u32)));
+        assert_eq!(field_value(&serde_json::json!(["1"]), &modulus), None);
+    }
+}
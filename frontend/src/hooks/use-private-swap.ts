@@ -226,12 +226,9 @@ export function usePrivateSwap() {
       })
       
       // Use same default values as ASP when sqrt_price_old or liquidity are 0
-      // Q128 = 2^128 = 340282366920938463463374607431768211456
-      // BUT: u128::MAX = 2^128 - 1 = 340282366920938463463374607431768211455
-      // IMPORTANT: The circuit uses Q128 = 2^128, so we MUST use Q128 for calculations
-      // When sending to ASP (Rust), we'll use U128_MAX as the string value (closest approximation)
-      const Q128 = BigInt("340282366920938463463374607431768211456") // Q128 = 2^128 (for calculations)
-      const U128_MAX = BigInt("340282366920938463463374607431768211455") // u128::MAX (for Rust parsing)
+      // Q128 = 2^128 = 340282366920938463463374607431768211456 (1:1 price)
+      // The ASP parses sqrt prices as u256, so they are sent exactly
+      const Q128 = BigInt("340282366920938463463374607431768211456")
       const sqrtPriceOldStr = "0" // TODO: Get from pool state
       const liquidityStr = "0" // TODO: Get from pool state
       const newSqrtPriceX128Str = "0" // TODO: Get from pool state
@@ -239,8 +236,6 @@ export function usePrivateSwap() {
       console.log("[Frontend] 🔢 Starting CLMM calculation with defaults...")
       
       // Apply same defaults as ASP
-      // For calculations, use Q128 (circuit expects this)
-      // For sending to ASP, we'll convert to U128_MAX string (Rust can't parse 2^128)
       console.log("[Frontend] 🔧 Applying default values...")
       let sqrtPriceOldFinal = sqrtPriceOldStr === "0" ? Q128 : BigInt(sqrtPriceOldStr)
       let newSqrtPriceX128Final = newSqrtPriceX128Str === "0" ? sqrtPriceOldFinal : BigInt(newSqrtPriceX128Str)
@@ -272,7 +267,7 @@ export function usePrivateSwap() {
         console.log("[Frontend] 📊 Initial diff:", diff.toString())
         if (diff > 0n) {
           // Calculate liquidity to get amount_out ≈ amountSpecified
-          // Use Q128 (not U128_MAX) because circuit expects Q128 = 2^128
+          // Use Q128 = 2^128, as the circuit does
           liquidityFinal = (amountSpecified * Q128) / diff
           // Round up to ensure we have enough liquidity
           if ((amountSpecified * Q128) % diff !== 0n) {
@@ -335,7 +330,7 @@ export function usePrivateSwap() {
           // But that could be slow. Better: use the fact that we need (k * Q128) % diff === 0
           
           // Calculate targetNumerator = kMax * Q128 (largest multiple of Q128 <= numerator)
-          // Use Q128 (not U128_MAX) because circuit expects Q128 = 2^128
+          // Use Q128 = 2^128, as the circuit does
           let targetNumerator = kMax * Q128
           
           // Check if targetNumerator is divisible by diff
@@ -572,7 +567,7 @@ export function usePrivateSwap() {
         zero_for_one: zeroForOne ? "1" : "0",
         amount0_delta: amount0Delta.toString(),
         amount1_delta: amount1Delta.toString(),
-        new_sqrt_price_x128: newSqrtPriceX128Final === Q128 ? U128_MAX.toString() : newSqrtPriceX128Final.toString(), // Convert Q128 to U128_MAX for Rust
+        new_sqrt_price_x128: newSqrtPriceX128Final.toString(),
         new_tick: newTick.toString(),
         // Private inputs
         secret_in: verifiedInputNote.secret.toString(),
@@ -582,7 +577,7 @@ export function usePrivateSwap() {
         amount_out: calculatedAmountOut.toString(), // Use calculated value, not outputNote.amount
         pathElements: merkleProof.path,
         pathIndices: merkleProof.path_indices,
-        sqrt_price_old: sqrtPriceOldFinal === Q128 ? U128_MAX.toString() : sqrtPriceOldFinal.toString(), // Convert Q128 to U128_MAX for Rust
+        sqrt_price_old: sqrtPriceOldFinal.toString(),
         liquidity: liquidityAdjusted.toString(), // Use adjusted value (multiple of Q128)
       };
       
@@ -1537,14 +1537,15 @@ pub mod Zylith {
             };
 
             // Step 2 - Extract verified values from ZK proof
-            // Expected public inputs for private_collect:
+            // Collects are proven with the LP circuit, so the layout matches private_burn_liquidity:
             // 0: nullifier (for the position note being used)
             // 1: root (Merkle root for membership proof)
             // 2: tick_lower
             // 3: tick_upper
-            // 4: new_commitment (commitment for the collected fees)
-            // 5: position_commitment (unique identifier for the LP position)
-            assert(_verified_inputs.len() >= 6, 'INVALID_VERIFIED_INPUTS_LEN');
+            // 4: liquidity (always 0: a collect does not change the position's liquidity)
+            // 5: new_commitment (commitment for the collected fees)
+            // 6: position_commitment (unique identifier for the LP position)
+            assert(_verified_inputs.len() >= 7, 'INVALID_VERIFIED_INPUTS_LEN');
 
             // Extract felt252 values from u256
             // Extract felt252 values from u256 - always use .low for felt252 values
@@ -1577,7 +1578,8 @@ pub mod Zylith {
             } else {
                 tick_upper_u256.low.try_into().unwrap()
             };
-            let verified_new_commitment_u256 = *_verified_inputs.at(4);
+            let verified_liquidity_u256 = *_verified_inputs.at(4);
+            let verified_new_commitment_u256 = *_verified_inputs.at(5);
             let verified_new_commitment: felt252 = if verified_new_commitment_u256.high == 0 {
                 verified_new_commitment_u256.low.try_into().unwrap()
             } else {
@@ -1587,7 +1589,7 @@ pub mod Zylith {
                 let reconstructed: u256 = high_u256 * q128 + low_u256;
                 reconstructed.try_into().unwrap()
             };
-            let position_commitment_u256 = *_verified_inputs.at(5);
+            let position_commitment_u256 = *_verified_inputs.at(6);
             let position_commitment: felt252 = if position_commitment_u256.high == 0 {
                 position_commitment_u256.low.try_into().unwrap()
             } else {
@@ -1603,6 +1605,7 @@ pub mod Zylith {
             assert(is_valid_root, 'INVALID_MERKLE_ROOT');
             assert(verified_tick_lower == tick_lower, 'TICK_LOWER_MISMATCH');
             assert(verified_tick_upper == tick_upper, 'TICK_UPPER_MISMATCH');
+            assert(verified_liquidity_u256 == 0, 'LIQUIDITY_MISMATCH');
             assert(verified_new_commitment == new_commitment, 'COMMITMENT_MISMATCH');
 
             // Check nullifier hasn't been spent
@@ -584,3 +584,45 @@ fn test_multiple_positions() {
     assert!(fees0 >= 0);
     assert!(fees1 >= 0);
 }
+
+// ==================== Math Vectors ====================
+// The ASP's Rust port (asp/src/clmm.rs) checks get_sqrt_ratio_at_tick against these values
+
+#[test]
+fn test_sqrt_ratio_at_tick_vectors() {
+    // Up to |tick| = 100 the step is Q128 / 20000 per tick
+    assert!(math::get_sqrt_ratio_at_tick(0) == math::Q128);
+    assert!(math::get_sqrt_ratio_at_tick(1) == 340299381039284510386547776162139799866);
+    assert!(math::get_sqrt_ratio_at_tick(60) == 341303214021701278853764731254063516090);
+    assert!(math::get_sqrt_ratio_at_tick(100) == 341983778755543155780691480468927052513);
+    assert!(math::get_sqrt_ratio_at_tick(-1) == 340265352802592416540201438701396623046);
+    assert!(math::get_sqrt_ratio_at_tick(-60) == 339261519820175648072984483609472906822);
+    assert!(math::get_sqrt_ratio_at_tick(-100) == 338580955086333771146057734394609370399);
+
+    // From 101 to 1000 it is Q128 / 2000
+    assert!(math::get_sqrt_ratio_at_tick(101) == 357466626450445855868275025107072506134);
+    assert!(math::get_sqrt_ratio_at_tick(600) == 442367076997220002502386989661298674892);
+    assert!(math::get_sqrt_ratio_at_tick(1000) == 510423550381407695195061911147652317184);
+    assert!(math::get_sqrt_ratio_at_tick(-101) == 323098107391431071058474189756463916778);
+    assert!(math::get_sqrt_ratio_at_tick(-600) == 238197656844656924424362225202237748020);
+    assert!(math::get_sqrt_ratio_at_tick(-1000) == 170141183460469231731687303715884105728);
+
+    // Above 1000 it is Q128 / 200, and every tick below -1000 clamps to MIN_SQRT_RATIO
+    assert!(math::get_sqrt_ratio_at_tick(1001) == 2043395613360235473097564517627768109793);
+    assert!(
+        math::get_sqrt_ratio_at_tick(math::MAX_TICK) == 1509955363680295500233839948033430990786396,
+    );
+    assert!(math::get_sqrt_ratio_at_tick(-1001) == math::MIN_SQRT_RATIO);
+    assert!(math::get_sqrt_ratio_at_tick(math::MIN_TICK) == math::MIN_SQRT_RATIO);
+}
+
+#[test]
+fn test_tick_at_sqrt_ratio_vectors() {
+    assert!(math::get_tick_at_sqrt_ratio(341303214021701278853764731254063516090) == 60);
+    assert!(math::get_tick_at_sqrt_ratio(341303214021701278853764731254063516089) == 59);
+    assert!(math::get_tick_at_sqrt_ratio(339261519820175648072984483609472906822) == -60);
+    assert!(math::get_tick_at_sqrt_ratio(357466626450445855868275025107072506134) == 101);
+    // The clamped ticks share MIN_SQRT_RATIO; the search returns the highest of them
+    assert!(math::get_tick_at_sqrt_ratio(math::MIN_SQRT_RATIO) == -1001);
+    assert!(math::get_tick_at_sqrt_ratio(math::MAX_SQRT_RATIO) == math::MAX_TICK);
+}
@@ -332,3 +332,69 @@ fn test_private_burn_liquidity_with_valid_proof() {
     assert!(amount0 > 0 || amount1 > 0, "Burn should return tokens");
     assert!(setup.zylith.is_nullifier_spent(burn_nullifier), "Burn nullifier must be spent");
 }
+
+// ============================================================================
+//  Private Collect
+// ============================================================================
+
+/// Deposits, mints a private position over [120, 240] and returns its position commitment
+fn mint_private_position(setup: @TestSetup) -> felt252 {
+    let zylith = *setup.zylith;
+    let token0 = *setup.token0.contract_address;
+    let token1 = *setup.token1.contract_address;
+
+    start_cheat_caller_address(zylith.contract_address, caller());
+    zylith.initialize(token0, token1, TEST_FEE, TICK_SPACING, sqrt_price);
+    let deposit_amount: u256 = 10_000_000_000_000_000_000;
+    zylith.private_deposit(token0, deposit_amount, 0x111);
+    zylith.private_deposit(token1, deposit_amount, 0x111);
+
+    let position_commitment: felt252 = 0x333;
+    let liquidity: u128 = 2_000_000;
+    let mint_inputs = array![
+        0x01, zylith.get_merkle_root(), 120, 240, liquidity.into(), 0x222, position_commitment,
+    ];
+    zylith.private_mint_liquidity(120, 240, liquidity, 0x222, mint_inputs.clone(), mint_inputs);
+    stop_cheat_caller_address(zylith.contract_address);
+
+    position_commitment
+}
+
+#[test]
+fn test_private_collect_with_lp_circuit_inputs() {
+    let setup = setup_with_erc20();
+    initialize_pool(@setup);
+    let position_commitment = mint_private_position(@setup);
+
+    // LP circuit layout: nullifier, root, tick_lower, tick_upper, liquidity = 0,
+    // new_commitment, position_commitment
+    let collect_nullifier: felt252 = 0x777;
+    let new_commitment: felt252 = 0x888;
+    let collect_inputs = array![
+        collect_nullifier, setup.zylith.get_merkle_root(), 120, 240, 0, new_commitment,
+        position_commitment,
+    ];
+
+    start_cheat_caller_address(setup.zylith.contract_address, caller());
+    setup.zylith.private_collect(120, 240, new_commitment, collect_inputs.clone(), collect_inputs);
+    stop_cheat_caller_address(setup.zylith.contract_address);
+
+    assert!(setup.zylith.is_nullifier_spent(collect_nullifier), "Collect nullifier must be spent");
+}
+
+#[test]
+#[should_panic]
+fn test_private_collect_rejects_nonzero_liquidity() {
+    let setup = setup_with_erc20();
+    initialize_pool(@setup);
+    let position_commitment = mint_private_position(@setup);
+
+    let new_commitment: felt252 = 0x888;
+    let collect_inputs = array![
+        0x777, setup.zylith.get_merkle_root(), 120, 240, 1, new_commitment, position_commitment,
+    ];
+
+    start_cheat_caller_address(setup.zylith.contract_address, caller());
+    setup.zylith.private_collect(120, 240, new_commitment, collect_inputs.clone(), collect_inputs);
+    stop_cheat_caller_address(setup.zylith.contract_address);
+}